@@ -0,0 +1,64 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::edition::{registered_editions, Edition};
+use crate::hooks::HookConfig;
+use crate::profile::LaunchProfile;
+use crate::utils::paths::app_dir;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub hooks: Vec<HookConfig>,
+    #[serde(default)]
+    pub custom_editions: Vec<Edition>,
+    #[serde(default)]
+    pub profiles: Vec<LaunchProfile>,
+}
+
+impl Config {
+    pub fn default_path() -> PathBuf {
+        app_dir().join("config.json")
+    }
+
+    pub fn load() -> io::Result<Self> {
+        Self::load_from(&Self::default_path())
+    }
+
+    // 配置文件不存在时使用默认配置
+    pub fn load_from(path: &Path) -> io::Result<Self> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let data = fs::read_to_string(path)?;
+        serde_json::from_str(&data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn editions(&self) -> Vec<Edition> {
+        registered_editions(&self.custom_editions)
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&LaunchProfile> {
+        self.profiles.iter().find(|profile| profile.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn set_profile(&mut self, profile: LaunchProfile) {
+        self.profiles.retain(|existing| !existing.name.eq_ignore_ascii_case(&profile.name));
+        self.profiles.push(profile);
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        self.save_to(&Self::default_path())
+    }
+
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let data = serde_json::to_string_pretty(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, data)
+    }
+}
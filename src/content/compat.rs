@@ -0,0 +1,180 @@
+use std::fs;
+use std::path::Path;
+
+use super::import::read_level_name;
+use super::{installed_packs, ContentKind, PACK_KINDS};
+use crate::nbt::{LevelDat, Tag};
+use crate::validation::Severity;
+use crate::version::PackageVersion;
+
+#[derive(Debug, Clone)]
+pub struct CompatibilityIssue {
+    pub severity: Severity,
+    pub kind: ContentKind,
+    // 世界目录 ID 或包 UUID
+    pub id: String,
+    pub name: String,
+    pub required: PackageVersion,
+    pub message: String,
+}
+
+// 包版本 1.20.8005.0 对应游戏版本 1.20.80.5
+pub fn game_version_from_package(package_version: &PackageVersion) -> PackageVersion {
+    let [major, minor, build, _] = package_version.0;
+    PackageVersion([major, minor, build / 100, build % 100])
+}
+
+fn version_from_list(tag: &Tag) -> Option<PackageVersion> {
+    let parts: Vec<u32> = tag.as_list()?.iter().filter_map(|part| part.as_i64()).map(|part| part.max(0) as u32).collect();
+    (!parts.is_empty()).then(|| PackageVersion::from_parts(&parts))
+}
+
+// 只比较前三段，同一小版本的修订号不影响存档格式
+fn is_newer(required: &PackageVersion, game_version: &PackageVersion) -> bool {
+    required.0[..3] > game_version.0[..3]
+}
+
+fn short_version(version: &PackageVersion) -> String {
+    format!("{}.{}.{}", version.0[0], version.0[1], version.0[2])
+}
+
+pub fn check_worlds(com_mojang: &Path, game_version: &PackageVersion) -> Vec<CompatibilityIssue> {
+    let Ok(entries) = fs::read_dir(com_mojang.join(ContentKind::World.folder_name())) else {
+        return Vec::new();
+    };
+
+    let mut issues = Vec::new();
+    for world_dir in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        let Ok(level) = LevelDat::from_file(&world_dir.join("level.dat")) else {
+            continue;
+        };
+        let id = world_dir.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let name = read_level_name(&world_dir)
+            .or_else(|| level.get("LevelName").and_then(|name| name.as_str()).map(|name| name.to_string()))
+            .unwrap_or_else(|| id.clone());
+
+        // 最低兼容版本比游戏新时无法打开；只是被新版本打开过时仍能打开，但可能被写坏
+        if let Some(required) = level.get("MinimumCompatibleClientVersion").and_then(version_from_list).filter(|version| is_newer(version, game_version)) {
+            issues.push(CompatibilityIssue {
+                severity: Severity::Error,
+                kind: ContentKind::World,
+                message: format!("世界 {} ({}) 至少需要 {}，当前游戏版本 {} 无法打开", name, id, short_version(&required), short_version(game_version)),
+                id: id.clone(),
+                name: name.clone(),
+                required,
+            });
+        } else if let Some(required) = level.get("lastOpenedWithVersion").and_then(version_from_list).filter(|version| is_newer(version, game_version)) {
+            issues.push(CompatibilityIssue {
+                severity: Severity::Warning,
+                kind: ContentKind::World,
+                message: format!("世界 {} ({}) 已被更新的版本 {} 打开过，在 {} 中打开可能会损坏", name, id, short_version(&required), short_version(game_version)),
+                id,
+                name,
+                required,
+            });
+        }
+    }
+    issues
+}
+
+pub fn check_packs(com_mojang: &Path, game_version: &PackageVersion) -> Vec<CompatibilityIssue> {
+    PACK_KINDS.iter()
+        .flat_map(|kind| installed_packs(com_mojang, *kind).into_iter().map(move |(_, manifest)| (*kind, manifest)))
+        .filter_map(|(kind, manifest)| {
+            let min_engine_version = manifest.min_engine_version?;
+            let required = PackageVersion::from_parts(&min_engine_version.0);
+            is_newer(&required, game_version).then(|| CompatibilityIssue {
+                severity: Severity::Warning,
+                kind,
+                message: format!("{} {} 需要游戏版本 {}，当前为 {}，将无法加载", kind.name(), manifest.name, min_engine_version, short_version(game_version)),
+                id: manifest.uuid,
+                name: manifest.name,
+                required,
+            })
+        })
+        .collect()
+}
+
+pub fn check_compatibility(com_mojang: &Path, game_version: &PackageVersion) -> Vec<CompatibilityIssue> {
+    let mut issues = check_worlds(com_mojang, game_version);
+    issues.extend(check_packs(com_mojang, game_version));
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::content::level::apply_level_setting;
+    use crate::utils::files::{copy_dir, create_temp_dir};
+
+    fn fixtures() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures")
+    }
+
+    fn version(text: &str) -> PackageVersion {
+        text.parse().unwrap()
+    }
+
+    fn add_world(com_mojang: &Path, id: &str, settings: &[(&str, &str)]) {
+        let world_dir = com_mojang.join("minecraftWorlds").join(id);
+        fs::create_dir_all(&world_dir).unwrap();
+        let mut level = LevelDat::from_file(&fixtures().join("level.dat")).unwrap();
+        for (key, value) in settings {
+            apply_level_setting(&mut level, key, value).unwrap();
+        }
+        level.write_to_file(&world_dir.join("level.dat")).unwrap();
+        fs::write(world_dir.join("levelname.txt"), id).unwrap();
+    }
+
+    #[test]
+    fn maps_package_version_to_game_version() {
+        assert_eq!(game_version_from_package(&version("1.20.8005.0")), version("1.20.80.5"));
+        assert_eq!(game_version_from_package(&version("1.21.2")), version("1.21.0.2"));
+    }
+
+    #[test]
+    fn ignores_revision_when_comparing() {
+        assert!(is_newer(&version("1.21.0"), &version("1.20.80.5")));
+        assert!(!is_newer(&version("1.20.80.9"), &version("1.20.80.5")));
+        assert!(!is_newer(&version("1.20.0"), &version("1.20.80")));
+    }
+
+    #[test]
+    fn reports_worlds_newer_than_the_game() {
+        let com_mojang = create_temp_dir("bbl-compat").unwrap();
+        add_world(&com_mojang, "future", &[("MinimumCompatibleClientVersion", "1.21.0"), ("lastOpenedWithVersion", "1.21.0")]);
+        add_world(&com_mojang, "opened", &[("MinimumCompatibleClientVersion", "1.20.0"), ("lastOpenedWithVersion", "1.21.0")]);
+        add_world(&com_mojang, "current", &[("MinimumCompatibleClientVersion", "1.20.0"), ("lastOpenedWithVersion", "1.20.80.9")]);
+        fs::create_dir_all(com_mojang.join("minecraftWorlds").join("empty")).unwrap();
+
+        let mut issues = check_worlds(&com_mojang, &version("1.20.80.5"));
+        fs::remove_dir_all(&com_mojang).unwrap();
+        issues.sort_by(|a, b| a.id.cmp(&b.id));
+
+        assert_eq!(issues.len(), 2);
+        assert_eq!((issues[0].id.as_str(), issues[0].severity), ("future", Severity::Error));
+        assert_eq!(issues[0].required, version("1.21.0"));
+        assert_eq!((issues[1].id.as_str(), issues[1].severity), ("opened", Severity::Warning));
+        assert_eq!(issues[1].name, "opened");
+    }
+
+    #[test]
+    fn reports_packs_needing_a_newer_engine() {
+        let com_mojang = create_temp_dir("bbl-compat").unwrap();
+        copy_dir(&fixtures().join("packs"), &com_mojang).unwrap();
+
+        let current = check_packs(&com_mojang, &version("1.20.80.5"));
+        let mut old = check_packs(&com_mojang, &version("1.19.80"));
+        let all = check_compatibility(&com_mojang, &version("1.19.80"));
+        fs::remove_dir_all(&com_mojang).unwrap();
+        old.sort_by(|a, b| a.name.cmp(&b.name));
+
+        assert!(current.is_empty());
+        let names: Vec<&str> = old.iter().map(|issue| issue.name.as_str()).collect();
+        assert_eq!(names, ["Addon BP", "Core BP", "Lib BP", "UI RP"]);
+        assert!(old.iter().all(|issue| issue.severity == Severity::Warning && issue.required == version("1.20.0")));
+        assert_eq!(all.len(), old.len());
+    }
+}
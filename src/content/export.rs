@@ -0,0 +1,156 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::import::read_level_name;
+use super::world::world_pack_references;
+use super::{find_installed_pack, find_pack, ContentKind};
+use crate::utils::archive::{to_io_error, zip_dirs};
+use crate::utils::files::sanitize_file_name;
+
+#[derive(Debug, Clone, Default)]
+pub struct WorldExport {
+    pub archive_path: PathBuf,
+    // 从 com.mojang 一起打包进去的包
+    pub included_packs: Vec<(ContentKind, String)>,
+    // 世界引用了但本机没有安装的包
+    pub missing_packs: Vec<String>,
+}
+
+pub fn default_world_archive_name(world_dir: &Path) -> String {
+    let name = read_level_name(world_dir)
+        .map(|name| sanitize_file_name(&name))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| world_dir.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default());
+    format!("{}.mcworld", name)
+}
+
+// include_packs 时把世界引用、但没有内嵌在世界目录里的包放到压缩包的 resource_packs / behavior_packs 下
+pub fn export_world(world_dir: &Path, com_mojang: &Path, archive_path: &Path, include_packs: bool) -> io::Result<WorldExport> {
+    let mut export = WorldExport { archive_path: archive_path.to_path_buf(), ..Default::default() };
+    let mut pack_dirs: Vec<(PathBuf, String)> = Vec::new();
+
+    if include_packs {
+        for kind in [ContentKind::ResourcePack, ContentKind::BehaviorPack] {
+            for reference in world_pack_references(world_dir, kind)? {
+                if find_installed_pack(world_dir, kind, &reference.pack_id).is_some() {
+                    continue;
+                }
+                match find_installed_pack(com_mojang, kind, &reference.pack_id) {
+                    Some((pack_dir, manifest)) => {
+                        let folder_name = pack_dir.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_else(|| manifest.uuid.clone());
+                        pack_dirs.push((pack_dir, format!("{}/{}", kind.folder_name(), folder_name)));
+                        export.included_packs.push((kind, manifest.name));
+                    }
+                    None => export.missing_packs.push(reference.pack_id),
+                }
+            }
+        }
+    }
+
+    let mut sources: Vec<(&Path, &str)> = vec![(world_dir, "")];
+    sources.extend(pack_dirs.iter().map(|(dir, prefix)| (dir.as_path(), prefix.as_str())));
+    zip_dirs(&sources, archive_path).map_err(to_io_error)?;
+    Ok(export)
+}
+
+pub fn default_pack_archive_name(name: &str, uuid: &str) -> String {
+    let name = Some(sanitize_file_name(name)).filter(|name| !name.is_empty()).unwrap_or_else(|| uuid.to_string());
+    format!("{}.mcpack", name)
+}
+
+// 在资源包、行为包、皮肤包中查找，archive_path 为 None 时用包名命名
+pub fn export_pack(com_mojang: &Path, uuid: &str, archive_path: Option<&Path>) -> io::Result<(ContentKind, PathBuf)> {
+    let (kind, pack_dir, manifest) = find_pack(com_mojang, uuid)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("找不到 UUID 为 {} 的包", uuid)))?;
+    let archive_path = archive_path
+        .map(|path| path.to_path_buf())
+        .unwrap_or_else(|| PathBuf::from(default_pack_archive_name(&manifest.name, &manifest.uuid)));
+
+    zip_dirs(&[(&pack_dir, "")], &archive_path).map_err(to_io_error)?;
+    Ok((kind, archive_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+
+    use zip::ZipArchive;
+
+    use super::*;
+    use crate::utils::files::{copy_dir, create_temp_dir};
+
+    const ABSENT_BP: &str = "eeeeeeee-0000-4000-8000-000000000005";
+
+    // 世界内嵌了 Addon BP，另外引用了一个本机没有的包
+    fn fixture() -> PathBuf {
+        let com_mojang = create_temp_dir("bbl-export").unwrap();
+        copy_dir(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("packs"), &com_mojang).unwrap();
+        let world_dir = com_mojang.join("minecraftWorlds").join("world");
+        fs::write(world_dir.join("levelname.txt"), "My: World\n").unwrap();
+        copy_dir(&com_mojang.join("behavior_packs").join("addon_bp"), &world_dir.join("behavior_packs").join("embedded")).unwrap();
+        let references = fs::read_to_string(world_dir.join("world_behavior_packs.json")).unwrap();
+        let references = references.trim_end().trim_end_matches(']').to_string() + &format!(",\n  {{ \"pack_id\": \"{}\", \"version\": [1, 0, 0] }}\n]\n", ABSENT_BP);
+        fs::write(world_dir.join("world_behavior_packs.json"), references).unwrap();
+        com_mojang
+    }
+
+    fn entry_names(archive_path: &Path) -> Vec<String> {
+        let archive = ZipArchive::new(File::open(archive_path).unwrap()).unwrap();
+        let mut names: Vec<String> = archive.file_names().filter(|name| !name.ends_with('/')).map(|name| name.to_string()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn includes_referenced_packs_that_are_not_embedded() {
+        let com_mojang = fixture();
+        let world_dir = com_mojang.join("minecraftWorlds").join("world");
+        let archive_path = com_mojang.join("out").join(default_world_archive_name(&world_dir));
+
+        let export = export_world(&world_dir, &com_mojang, &archive_path, true).unwrap();
+        let names = entry_names(&archive_path);
+        fs::remove_dir_all(&com_mojang).unwrap();
+
+        assert_eq!(archive_path.file_name().unwrap(), "My_ World.mcworld");
+        assert_eq!(export.included_packs, vec![(ContentKind::ResourcePack, "UI RP".to_string()), (ContentKind::BehaviorPack, "Core BP".to_string())]);
+        assert_eq!(export.missing_packs, vec![ABSENT_BP.to_string()]);
+        assert_eq!(names, [
+            "behavior_packs/core_bp/manifest.json",
+            "behavior_packs/embedded/manifest.json",
+            "levelname.txt",
+            "resource_packs/ui_rp/manifest.json",
+            "world_behavior_packs.json",
+            "world_resource_packs.json",
+        ]);
+    }
+
+    #[test]
+    fn exports_only_the_world_without_include_packs() {
+        let com_mojang = fixture();
+        let world_dir = com_mojang.join("minecraftWorlds").join("world");
+        let archive_path = com_mojang.join("world.mcworld");
+
+        let export = export_world(&world_dir, &com_mojang, &archive_path, false).unwrap();
+        let names = entry_names(&archive_path);
+        fs::remove_dir_all(&com_mojang).unwrap();
+
+        assert!(export.included_packs.is_empty() && export.missing_packs.is_empty());
+        assert_eq!(names, ["behavior_packs/embedded/manifest.json", "levelname.txt", "world_behavior_packs.json", "world_resource_packs.json"]);
+    }
+
+    #[test]
+    fn exports_pack_by_uuid() {
+        let com_mojang = fixture();
+        let archive_path = com_mojang.join("lib.mcpack");
+
+        let exported = export_pack(&com_mojang, "DDDDDDDD-0000-4000-8000-000000000004", Some(&archive_path)).unwrap();
+        let names = entry_names(&archive_path);
+        let missing = export_pack(&com_mojang, ABSENT_BP, Some(&archive_path)).unwrap_err();
+        fs::remove_dir_all(&com_mojang).unwrap();
+
+        assert_eq!(exported, (ContentKind::BehaviorPack, archive_path));
+        assert_eq!(names, ["manifest.json"]);
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+        assert_eq!(default_pack_archive_name("", ABSENT_BP), format!("{}.mcpack", ABSENT_BP));
+    }
+}
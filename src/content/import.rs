@@ -0,0 +1,288 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::manifest::{PackManifest, PackVersion};
+use super::{find_installed_pack, new_world_id, ContentKind};
+use crate::utils::archive::{to_io_error, unzip};
+use crate::utils::files::{copy_dir, create_temp_dir, sanitize_file_name, unique_dir};
+
+const CONTENT_EXTENSIONS: [&str; 5] = ["mcworld", "mcpack", "mcaddon", "mctemplate", "zip"];
+// .mcaddon 里可以再嵌套这些
+const NESTED_EXTENSIONS: [&str; 3] = ["mcworld", "mcpack", "mctemplate"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportAction {
+    Installed,
+    Replaced { previous: PackVersion },
+    Skipped(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedContent {
+    pub kind: ContentKind,
+    pub name: String,
+    pub uuid: Option<String>,
+    pub version: Option<PackVersion>,
+    pub destination: PathBuf,
+    pub action: ImportAction,
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .map(|extension| extensions.iter().any(|candidate| extension.eq_ignore_ascii_case(candidate)))
+        .unwrap_or(false)
+}
+
+// 含 manifest.json 或 level.dat 的目录就是一个内容，嵌套的压缩包解压到旁边继续找
+fn find_content_roots(dir: &Path, roots: &mut Vec<PathBuf>) -> io::Result<()> {
+    if dir.join("manifest.json").is_file() || dir.join("level.dat").is_file() {
+        roots.push(dir.to_path_buf());
+        return Ok(());
+    }
+
+    // 先列出目录再处理，避免把刚解压出来的目录再遍历一遍
+    let paths = fs::read_dir(dir)?.map(|entry| entry.map(|entry| entry.path())).collect::<io::Result<Vec<_>>>()?;
+    for path in paths {
+        if path.is_dir() {
+            find_content_roots(&path, roots)?;
+        } else if has_extension(&path, &NESTED_EXTENSIONS) {
+            let extracted = path.with_extension("extracted");
+            unzip(&path, &extracted).map_err(to_io_error)?;
+            find_content_roots(&extracted, roots)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn read_level_name(world_dir: &Path) -> Option<String> {
+    fs::read_to_string(world_dir.join("levelname.txt"))
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+fn import_world(root: &Path, com_mojang: &Path) -> io::Result<ImportedContent> {
+    let worlds_dir = com_mojang.join(ContentKind::World.folder_name());
+    let destination = worlds_dir.join(new_world_id(&worlds_dir, &HashSet::new()));
+    copy_dir(root, &destination)?;
+
+    Ok(ImportedContent {
+        kind: ContentKind::World,
+        name: read_level_name(root).unwrap_or_else(|| "未命名世界".to_string()),
+        uuid: None,
+        version: None,
+        destination,
+        action: ImportAction::Installed,
+    })
+}
+
+// 同 UUID 已安装时，只有新版本 (或 force) 才替换，替换时沿用原来的目录
+fn import_pack(root: &Path, manifest: PackManifest, kind: ContentKind, com_mojang: &Path, force: bool) -> io::Result<ImportedContent> {
+    let existing = find_installed_pack(com_mojang, kind, &manifest.uuid);
+    let (destination, action) = match existing {
+        None => {
+            let folder_name = Some(sanitize_file_name(&manifest.name))
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| manifest.uuid.clone());
+            (unique_dir(&com_mojang.join(kind.folder_name()), &folder_name), ImportAction::Installed)
+        }
+        Some((path, installed)) if force || manifest.version > installed.version => {
+            (path, ImportAction::Replaced { previous: installed.version })
+        }
+        Some((path, installed)) if manifest.version == installed.version => {
+            (path, ImportAction::Skipped(format!("已安装相同版本 {}", installed.version)))
+        }
+        Some((path, installed)) => (path, ImportAction::Skipped(format!("已安装更新的版本 {}", installed.version))),
+    };
+
+    match action {
+        ImportAction::Installed => copy_dir(root, &destination)?,
+        ImportAction::Replaced { .. } => {
+            fs::remove_dir_all(&destination)?;
+            copy_dir(root, &destination)?;
+        }
+        ImportAction::Skipped(_) => {}
+    }
+
+    Ok(ImportedContent {
+        kind,
+        name: manifest.name,
+        uuid: Some(manifest.uuid),
+        version: Some(manifest.version),
+        destination,
+        action,
+    })
+}
+
+fn import_root(root: &Path, com_mojang: &Path, force: bool) -> io::Result<ImportedContent> {
+    let manifest_path = root.join("manifest.json");
+    if !manifest_path.is_file() {
+        return import_world(root, com_mojang);
+    }
+
+    let manifest = PackManifest::from_file(&manifest_path)?;
+    match manifest.kind() {
+        Some(kind) => import_pack(root, manifest, kind, com_mojang, force),
+        // 世界里也可能带 manifest.json (如市场内容)，没有可识别的模块时按世界处理
+        None if root.join("level.dat").is_file() => import_world(root, com_mojang),
+        None => Err(io::Error::new(io::ErrorKind::InvalidData, format!("无法识别的内容类型: {}", manifest_path.display()))),
+    }
+}
+
+pub fn import_content(archive_path: &Path, com_mojang: &Path, force: bool) -> io::Result<Vec<ImportedContent>> {
+    if !has_extension(archive_path, &CONTENT_EXTENSIONS) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("不支持的文件类型: {} (支持 .mcworld .mcpack .mcaddon .mctemplate)", archive_path.display())));
+    }
+
+    let staging_dir = create_temp_dir("bbl-import")?;
+    let result = (|| {
+        unzip(archive_path, &staging_dir).map_err(to_io_error)?;
+        let mut roots = Vec::new();
+        find_content_roots(&staging_dir, &mut roots)?;
+        if roots.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "压缩包中没有 manifest.json 或 level.dat"));
+        }
+        roots.iter().map(|root| import_root(root, com_mojang, force)).collect()
+    })();
+
+    let _ = fs::remove_dir_all(&staging_dir);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::installed_packs;
+    use crate::utils::archive::{zip_dir, zip_dirs};
+
+    const LIB_BP: &str = "dddddddd-0000-4000-8000-000000000004";
+
+    fn fixture() -> PathBuf {
+        let com_mojang = create_temp_dir("bbl-import-target").unwrap();
+        copy_dir(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("packs"), &com_mojang).unwrap();
+        com_mojang
+    }
+
+    fn write_pack(dir: &Path, name: &str, uuid: &str, version: [u32; 3], module_type: &str) {
+        fs::create_dir_all(dir).unwrap();
+        let manifest = format!(
+            "{{\n  // 注释\n  \"format_version\": 2,\n  \"header\": {{ \"name\": \"{}\", \"uuid\": \"{}\", \"version\": [{}, {}, {}] }},\n  \"modules\": [{{ \"type\": \"{}\", \"uuid\": \"{}\" }}]\n}}",
+            name, uuid, version[0], version[1], version[2], module_type, uuid
+        );
+        fs::write(dir.join("manifest.json"), manifest).unwrap();
+    }
+
+    // 在临时目录里打包一个 .mcpack，返回压缩包路径
+    fn lib_pack(work_dir: &Path, version: [u32; 3]) -> PathBuf {
+        let source = work_dir.join(format!("lib-{}-{}-{}", version[0], version[1], version[2]));
+        write_pack(&source, "Lib BP", &LIB_BP.to_uppercase(), version, "data");
+        fs::write(source.join("marker.txt"), PackVersion(version).to_string()).unwrap();
+        let archive = source.with_extension("mcpack");
+        zip_dir(&source, &archive).unwrap();
+        archive
+    }
+
+    fn import_one(archive: &Path, com_mojang: &Path, force: bool) -> ImportedContent {
+        let mut imported = import_content(archive, com_mojang, force).unwrap();
+        assert_eq!(imported.len(), 1);
+        imported.remove(0)
+    }
+
+    fn installed_marker(com_mojang: &Path) -> String {
+        fs::read_to_string(com_mojang.join("behavior_packs").join("lib_bp").join("marker.txt")).unwrap_or_default()
+    }
+
+    #[test]
+    fn installs_new_pack_under_its_name() {
+        let com_mojang = fixture();
+        let work_dir = create_temp_dir("bbl-import-source").unwrap();
+        let source = work_dir.join("new");
+        write_pack(&source, "New: RP", "ffffffff-0000-4000-8000-000000000006", [1, 0, 0], "resources");
+        let archive = work_dir.join("new.mcpack");
+        zip_dir(&source, &archive).unwrap();
+
+        let imported = import_one(&archive, &com_mojang, false);
+        let installed = find_installed_pack(&com_mojang, ContentKind::ResourcePack, "ffffffff-0000-4000-8000-000000000006");
+        fs::remove_dir_all(&com_mojang).unwrap();
+        fs::remove_dir_all(&work_dir).unwrap();
+
+        assert_eq!(imported.kind, ContentKind::ResourcePack);
+        assert_eq!(imported.action, ImportAction::Installed);
+        assert_eq!(installed.map(|(path, _)| path), Some(imported.destination));
+    }
+
+    #[test]
+    fn replaces_only_with_newer_version_unless_forced() {
+        let com_mojang = fixture();
+        let work_dir = create_temp_dir("bbl-import-source").unwrap();
+
+        let same = import_one(&lib_pack(&work_dir, [1, 2, 0]), &com_mojang, false);
+        assert_eq!(same.action, ImportAction::Skipped("已安装相同版本 1.2.0".to_string()));
+        assert_eq!(installed_marker(&com_mojang), "");
+
+        let newer = import_one(&lib_pack(&work_dir, [2, 0, 0]), &com_mojang, false);
+        assert_eq!(newer.action, ImportAction::Replaced { previous: PackVersion([1, 2, 0]) });
+        assert_eq!(newer.destination, com_mojang.join("behavior_packs").join("lib_bp"));
+        assert_eq!(installed_marker(&com_mojang), "2.0.0");
+
+        let older = import_one(&lib_pack(&work_dir, [1, 0, 0]), &com_mojang, false);
+        assert_eq!(older.action, ImportAction::Skipped("已安装更新的版本 2.0.0".to_string()));
+        assert_eq!(installed_marker(&com_mojang), "2.0.0");
+
+        let forced = import_one(&lib_pack(&work_dir, [1, 0, 0]), &com_mojang, true);
+        assert_eq!(forced.action, ImportAction::Replaced { previous: PackVersion([2, 0, 0]) });
+        assert_eq!(installed_marker(&com_mojang), "1.0.0");
+
+        // 同 UUID 只保留一份
+        let copies = installed_packs(&com_mojang, ContentKind::BehaviorPack).into_iter().filter(|(_, manifest)| manifest.uuid == LIB_BP).count();
+        fs::remove_dir_all(&com_mojang).unwrap();
+        fs::remove_dir_all(&work_dir).unwrap();
+        assert_eq!(copies, 1);
+    }
+
+    #[test]
+    fn extracts_nested_archives_from_mcaddon() {
+        let com_mojang = fixture();
+        let work_dir = create_temp_dir("bbl-import-source").unwrap();
+        let addon_dir = work_dir.join("addon");
+        fs::create_dir_all(&addon_dir).unwrap();
+
+        let resource_pack = work_dir.join("rp");
+        write_pack(&resource_pack, "Addon RP", "ffffffff-0000-4000-8000-000000000007", [1, 0, 0], "resources");
+        zip_dir(&resource_pack, &addon_dir.join("rp.mcpack")).unwrap();
+        let world = work_dir.join("world");
+        fs::create_dir_all(&world).unwrap();
+        fs::copy(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("level.dat"), world.join("level.dat")).unwrap();
+        fs::write(world.join("levelname.txt"), "Addon World\n").unwrap();
+        zip_dir(&world, &addon_dir.join("world.mcworld")).unwrap();
+        let behavior_pack = work_dir.join("bp");
+        write_pack(&behavior_pack, "Addon Extra BP", "ffffffff-0000-4000-8000-000000000008", [1, 0, 0], "data");
+
+        let archive = work_dir.join("bundle.mcaddon");
+        zip_dirs(&[(&addon_dir, ""), (&behavior_pack, "bp")], &archive).unwrap();
+
+        let mut imported: Vec<(ContentKind, String, ImportAction)> = import_content(&archive, &com_mojang, false).unwrap()
+            .into_iter()
+            .map(|content| (content.kind, content.name, content.action))
+            .collect();
+        imported.sort_by(|a, b| a.1.cmp(&b.1));
+        let worlds = fs::read_dir(com_mojang.join("minecraftWorlds")).unwrap().count();
+        fs::remove_dir_all(&com_mojang).unwrap();
+        fs::remove_dir_all(&work_dir).unwrap();
+
+        assert_eq!(imported, vec![
+            (ContentKind::BehaviorPack, "Addon Extra BP".to_string(), ImportAction::Installed),
+            (ContentKind::ResourcePack, "Addon RP".to_string(), ImportAction::Installed),
+            (ContentKind::World, "Addon World".to_string(), ImportAction::Installed),
+        ]);
+        assert_eq!(worlds, 2);
+    }
+
+    #[test]
+    fn rejects_unsupported_files() {
+        let err = import_content(Path::new("pack.rar"), Path::new("."), false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,280 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+
+use super::world::EXPERIMENT_MARKERS;
+use crate::nbt::{LevelDat, Tag, TAG_INT};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LevelChange {
+    pub key: String,
+    pub old_value: Option<String>,
+    pub new_value: String,
+}
+
+pub fn parse_game_mode(value: &str) -> Option<i32> {
+    match value.to_lowercase().as_str() {
+        "survival" | "生存" => Some(0),
+        "creative" | "创造" => Some(1),
+        "adventure" | "冒险" => Some(2),
+        "default" | "默认" => Some(5),
+        "spectator" | "旁观" => Some(6),
+        value => value.parse().ok(),
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "on" | "yes" | "1" => Some(true),
+        "false" | "off" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+pub fn format_tag(tag: &Tag) -> String {
+    match tag {
+        Tag::Byte(value) => value.to_string(),
+        Tag::Short(value) => value.to_string(),
+        Tag::Int(value) => value.to_string(),
+        Tag::Long(value) => value.to_string(),
+        Tag::Float(value) => value.to_string(),
+        Tag::Double(value) => value.to_string(),
+        Tag::String(value) => value.clone(),
+        Tag::List(TAG_INT, items) => items.iter().filter_map(|item| item.as_i64()).map(|part| part.to_string()).collect::<Vec<_>>().join("."),
+        Tag::List(_, items) => format!("[{} 项]", items.len()),
+        Tag::Compound(entries) => format!("{{{} 项}}", entries.len()),
+        Tag::ByteArray(values) => format!("[{} 字节]", values.len()),
+        Tag::IntArray(values) => format!("[{} 个 int]", values.len()),
+        Tag::LongArray(values) => format!("[{} 个 long]", values.len()),
+    }
+}
+
+// 按原来的类型解析，保证写回后类型不变
+fn parse_value(existing: &Tag, value: &str) -> Result<Tag, String> {
+    let invalid = || format!("无效的值: {}", value);
+    Ok(match existing {
+        Tag::Byte(_) => match parse_bool(value) {
+            Some(flag) => Tag::Byte(flag as i8),
+            None => Tag::Byte(value.parse().map_err(|_| invalid())?),
+        },
+        Tag::Short(_) => Tag::Short(value.parse().map_err(|_| invalid())?),
+        Tag::Int(_) => Tag::Int(value.parse().map_err(|_| invalid())?),
+        Tag::Long(_) => Tag::Long(value.parse().map_err(|_| invalid())?),
+        Tag::Float(_) => Tag::Float(value.parse().map_err(|_| invalid())?),
+        Tag::Double(_) => Tag::Double(value.parse().map_err(|_| invalid())?),
+        Tag::String(_) => Tag::String(value.to_string()),
+        // 版本号列表: 1.20.80 -> [1, 20, 80, 0, 0]，长度和原来保持一致
+        Tag::List(TAG_INT, items) => {
+            let parts = value.split('.').map(|part| part.trim().parse::<i32>()).collect::<Result<Vec<_>, _>>().map_err(|_| invalid())?;
+            let length = items.len().max(parts.len());
+            if !items.is_empty() && parts.len() > items.len() {
+                return Err(format!("版本号最多 {} 段: {}", items.len(), value));
+            }
+            Tag::List(TAG_INT, (0..length).map(|index| Tag::Int(parts.get(index).copied().unwrap_or(0))).collect())
+        }
+        _ => return Err("不支持修改该类型的字段".to_string()),
+    })
+}
+
+// 先精确匹配，再忽略大小写匹配，返回文件里实际的名字
+fn resolve_name(compound: &Tag, name: &str) -> Option<String> {
+    let entries = compound.as_compound()?;
+    entries.iter()
+        .find(|(key, _)| key == name)
+        .or_else(|| entries.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)))
+        .map(|(key, _)| key.clone())
+}
+
+fn set_tag(level: &mut LevelDat, path: &[&str], value: &str) -> Result<LevelChange, String> {
+    let (leaf, parents) = path.split_last().ok_or_else(|| "字段名不能为空".to_string())?;
+    let mut compound = &mut level.root;
+    let mut key_parts = Vec::new();
+    for parent in parents {
+        let name = resolve_name(compound, parent).ok_or_else(|| format!("level.dat 中没有 {}", parent))?;
+        compound = compound.get_mut(&name).filter(|tag| tag.as_compound().is_some()).ok_or_else(|| format!("{} 不是 compound", name))?;
+        key_parts.push(name);
+    }
+
+    let name = resolve_name(compound, leaf).ok_or_else(|| format!("level.dat 中没有 {}", path.join(".")))?;
+    key_parts.push(name.clone());
+    let existing = compound.get(&name).ok_or_else(|| format!("level.dat 中没有 {}", name))?;
+    let tag = parse_value(existing, value)?;
+    let change = LevelChange { key: key_parts.join("."), old_value: Some(format_tag(existing)), new_value: format_tag(&tag) };
+    compound.insert(&name, tag);
+    Ok(change)
+}
+
+fn set_experiment(level: &mut LevelDat, name: &str, value: &str) -> Result<LevelChange, String> {
+    let enabled = parse_bool(value).ok_or_else(|| format!("无效的值: {} (应为 true/false)", value))?;
+    if level.get("experiments").is_none() {
+        level.root.insert("experiments", Tag::Compound(Vec::new()));
+    }
+    let experiments = level.get_mut("experiments")
+        .filter(|tag| tag.as_compound().is_some())
+        .ok_or_else(|| "experiments 不是 compound".to_string())?;
+
+    let name = resolve_name(experiments, name).unwrap_or_else(|| name.to_string());
+    let old_value = experiments.get(&name).map(format_tag);
+    experiments.insert(&name, Tag::Byte(enabled as i8));
+    if enabled {
+        for marker in EXPERIMENT_MARKERS {
+            experiments.insert(marker, Tag::Byte(1));
+        }
+    }
+    Ok(LevelChange { key: format!("experiments.{}", name), old_value, new_value: (enabled as i8).to_string() })
+}
+
+// 支持的写法:
+//   gamemode=creative            GameType
+//   cheats=true                  commandsEnabled (以及旧版的 cheatsEnabled)
+//   experiments.<名称>=true       不存在时新建
+//   MinimumCompatibleClientVersion=1.20.80
+//   其它已有字段，嵌套用 . 分隔，如 abilities.mayfly=1
+pub fn apply_level_setting(level: &mut LevelDat, key: &str, value: &str) -> Result<Vec<LevelChange>, String> {
+    let lower_key = key.to_lowercase();
+    match lower_key.as_str() {
+        "gamemode" | "gametype" => {
+            let game_mode = parse_game_mode(value).ok_or_else(|| format!("无效的游戏模式: {}", value))?;
+            Ok(vec![set_tag(level, &["GameType"], &game_mode.to_string())?])
+        }
+        "cheats" => {
+            let enabled = parse_bool(value).ok_or_else(|| format!("无效的值: {} (应为 true/false)", value))?;
+            let mut changes = vec![set_tag(level, &["commandsEnabled"], &enabled.to_string())?];
+            if level.get("cheatsEnabled").is_some() {
+                changes.push(set_tag(level, &["cheatsEnabled"], &enabled.to_string())?);
+            }
+            Ok(changes)
+        }
+        _ => match lower_key.strip_prefix("experiments.") {
+            Some(_) => Ok(vec![set_experiment(level, &key["experiments.".len()..], value)?]),
+            None => Ok(vec![set_tag(level, &key.split('.').collect::<Vec<_>>(), value)?]),
+        },
+    }
+}
+
+// key=value 形式
+pub fn parse_assignment(assignment: &str) -> Option<(&str, &str)> {
+    let (key, value) = assignment.split_once('=')?;
+    let key = key.trim();
+    (!key.is_empty()).then_some((key, value.trim()))
+}
+
+// 备份到 level.dat.<时间>.bak，同一秒内已有备份时加序号，绝不覆盖已有的备份，返回备份路径
+pub fn backup_level_dat(world_dir: &Path) -> io::Result<PathBuf> {
+    let data = fs::read(world_dir.join("level.dat"))?;
+    let timestamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let mut index = 0;
+    loop {
+        let name = match index {
+            0 => format!("level.dat.{}.bak", timestamp),
+            index => format!("level.dat.{}-{}.bak", timestamp, index),
+        };
+        let backup_path = world_dir.join(name);
+        match OpenOptions::new().write(true).create_new(true).open(&backup_path) {
+            Ok(mut file) => {
+                file.write_all(&data)?;
+                return Ok(backup_path);
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => index += 1,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+pub fn save_level_dat(world_dir: &Path, level: &LevelDat) -> io::Result<PathBuf> {
+    let backup_path = backup_level_dat(world_dir)?;
+    level.write_to_file(&world_dir.join("level.dat"))?;
+    Ok(backup_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &[u8] = include_bytes!("../../tests/fixtures/level.dat");
+
+    fn apply(level: &mut LevelDat, key: &str, value: &str) -> Vec<LevelChange> {
+        apply_level_setting(level, key, value).unwrap()
+    }
+
+    #[test]
+    fn settings_survive_round_trip() {
+        let mut level = LevelDat::parse(SAMPLE).unwrap();
+        apply(&mut level, "gamemode", "creative");
+        apply(&mut level, "cheats", "true");
+        apply(&mut level, "experiments.gametest", "on");
+        apply(&mut level, "experiments.data_driven_biomes", "false");
+        apply(&mut level, "MinimumCompatibleClientVersion", "1.21.0");
+        apply(&mut level, "abilities.mayfly", "1");
+
+        let reparsed = LevelDat::parse(&level.to_bytes().unwrap()).unwrap();
+        assert_eq!(reparsed, level);
+        assert_eq!(reparsed.get("GameType"), Some(&Tag::Int(1)));
+        assert_eq!(reparsed.get("commandsEnabled"), Some(&Tag::Byte(1)));
+        assert_eq!(reparsed.get("MinimumCompatibleClientVersion").map(format_tag), Some("1.21.0.0.0".to_string()));
+        assert_eq!(reparsed.get("abilities").and_then(|abilities| abilities.get("mayfly")), Some(&Tag::Byte(1)));
+
+        let experiments = reparsed.get("experiments").unwrap();
+        assert_eq!(experiments.get("gametest"), Some(&Tag::Byte(1)));
+        assert_eq!(experiments.get("data_driven_biomes"), Some(&Tag::Byte(0)));
+        assert_eq!(experiments.get("experiments_ever_used"), Some(&Tag::Byte(1)));
+    }
+
+    #[test]
+    fn unknown_tags_are_preserved() {
+        let original = LevelDat::parse(SAMPLE).unwrap();
+        let mut level = original.clone();
+        apply(&mut level, "GameType", "adventure");
+
+        let reparsed = LevelDat::parse(&level.to_bytes().unwrap()).unwrap();
+        for name in ["unknownFutureFlag", "emptyList", "compoundList", "bytes", "ints", "longs", "FlatWorldLayers"] {
+            assert_eq!(reparsed.get(name), original.get(name), "{}", name);
+        }
+        // 只改了数值，长度不变
+        assert_eq!(level.to_bytes().unwrap().len(), SAMPLE.len());
+    }
+
+    #[test]
+    fn rejects_invalid_settings() {
+        let mut level = LevelDat::parse(SAMPLE).unwrap();
+        assert!(apply_level_setting(&mut level, "gamemode", "hardcore").is_err());
+        assert!(apply_level_setting(&mut level, "Difficulty", "abc").is_err());
+        assert!(apply_level_setting(&mut level, "noSuchField", "1").is_err());
+        assert!(apply_level_setting(&mut level, "MinimumCompatibleClientVersion", "1.2.3.4.5.6").is_err());
+        assert!(apply_level_setting(&mut level, "abilities", "1").is_err());
+        assert_eq!(level, LevelDat::parse(SAMPLE).unwrap());
+    }
+
+    #[test]
+    fn save_keeps_backup_of_original() {
+        let world_dir = crate::utils::files::create_temp_dir("bbl-level-test").unwrap();
+        fs::write(world_dir.join("level.dat"), SAMPLE).unwrap();
+
+        let mut level = LevelDat::from_file(&world_dir.join("level.dat")).unwrap();
+        apply(&mut level, "cheats", "on");
+        let backup_path = save_level_dat(&world_dir, &level).unwrap();
+
+        assert_eq!(fs::read(&backup_path).unwrap(), SAMPLE);
+        assert_eq!(LevelDat::from_file(&world_dir.join("level.dat")).unwrap(), level);
+        fs::remove_dir_all(&world_dir).unwrap();
+    }
+
+    #[test]
+    fn repeated_saves_keep_every_backup() {
+        let world_dir = crate::utils::files::create_temp_dir("bbl-level-test").unwrap();
+        fs::write(world_dir.join("level.dat"), SAMPLE).unwrap();
+
+        let mut level = LevelDat::from_file(&world_dir.join("level.dat")).unwrap();
+        apply(&mut level, "cheats", "on");
+        let first = save_level_dat(&world_dir, &level).unwrap();
+        apply(&mut level, "gamemode", "creative");
+        let second = save_level_dat(&world_dir, &level).unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(fs::read(&first).unwrap(), SAMPLE);
+        assert_ne!(fs::read(&second).unwrap(), SAMPLE);
+        fs::remove_dir_all(&world_dir).unwrap();
+    }
+}
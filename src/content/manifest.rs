@@ -0,0 +1,240 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use super::ContentKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct PackVersion(pub [u32; 3]);
+
+impl PackVersion {
+    // 旧格式是 [1, 0, 0]，format_version 3 起是 "1.0.0"
+    pub fn from_json(value: &Value) -> Option<Self> {
+        match value {
+            Value::Array(parts) => {
+                let mut version = [0u32; 3];
+                for (i, part) in parts.iter().take(3).enumerate() {
+                    version[i] = part.as_u64()? as u32;
+                }
+                Some(PackVersion(version))
+            }
+            Value::String(version) => version.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::Array(self.0.iter().map(|part| Value::from(*part)).collect())
+    }
+}
+
+impl FromStr for PackVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut version = [0u32; 3];
+        let parts: Vec<&str> = s.trim().split('.').collect();
+        if parts.is_empty() || parts.len() > 3 {
+            return Err(format!("无效的版本号: {}", s));
+        }
+        for (i, part) in parts.iter().enumerate() {
+            version[i] = part.parse().map_err(|_| format!("无效的版本号: {}", s))?;
+        }
+        Ok(PackVersion(version))
+    }
+}
+
+impl fmt::Display for PackVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.0[0], self.0[1], self.0[2])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackModule {
+    pub module_type: String,
+    pub uuid: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackDependency {
+    pub uuid: Option<String>,
+    // 脚本 API 依赖用模块名，如 @minecraft/server
+    pub module_name: Option<String>,
+    pub version: Option<PackVersion>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PackManifest {
+    pub format_version: u32,
+    pub name: String,
+    pub description: String,
+    pub uuid: String,
+    pub version: PackVersion,
+    pub min_engine_version: Option<PackVersion>,
+    pub modules: Vec<PackModule>,
+    pub dependencies: Vec<PackDependency>,
+}
+
+// 游戏允许 manifest.json 里有 // 和 /* */ 注释
+pub fn strip_json_comments(json: &str) -> String {
+    let mut output = String::with_capacity(json.len());
+    let mut chars = json.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            match c {
+                '\\' => {
+                    if let Some(escaped) = chars.next() {
+                        output.push(escaped);
+                    }
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                output.push(c);
+            }
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        output.push('\n');
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = '\0';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+pub fn read_json(path: &Path) -> io::Result<Value> {
+    let data = fs::read_to_string(path)?;
+    serde_json::from_str(strip_json_comments(data.trim_start_matches('\u{feff}')).as_str())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+}
+
+impl PackManifest {
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        Self::from_json(&read_json(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn from_json(json: &Value) -> Result<Self, String> {
+        let header = json.get("header").ok_or("manifest.json 缺少 header")?;
+        let text = |value: &Value, key: &str| value.get(key).and_then(|value| value.as_str()).unwrap_or_default().to_string();
+
+        let uuid = text(header, "uuid").to_lowercase();
+        if uuid.is_empty() {
+            return Err("manifest.json 缺少 header.uuid".to_string());
+        }
+
+        let modules = json.get("modules")
+            .and_then(|modules| modules.as_array())
+            .map(|modules| {
+                modules.iter()
+                    .map(|module| PackModule { module_type: text(module, "type").to_lowercase(), uuid: text(module, "uuid").to_lowercase() })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let dependencies = json.get("dependencies")
+            .and_then(|dependencies| dependencies.as_array())
+            .map(|dependencies| {
+                dependencies.iter()
+                    .map(|dependency| PackDependency {
+                        uuid: dependency.get("uuid").and_then(|uuid| uuid.as_str()).map(|uuid| uuid.to_lowercase()),
+                        module_name: dependency.get("module_name").and_then(|name| name.as_str()).map(|name| name.to_string()),
+                        version: dependency.get("version").and_then(PackVersion::from_json),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(PackManifest {
+            format_version: json.get("format_version").and_then(|version| version.as_u64()).unwrap_or(1) as u32,
+            name: text(header, "name"),
+            description: text(header, "description"),
+            uuid,
+            version: header.get("version").and_then(PackVersion::from_json).unwrap_or_default(),
+            min_engine_version: header.get("min_engine_version").and_then(PackVersion::from_json),
+            modules,
+            dependencies,
+        })
+    }
+
+    pub fn kind(&self) -> Option<ContentKind> {
+        let has_module = |module_type: &str| self.modules.iter().any(|module| module.module_type == module_type);
+        if has_module("world_template") {
+            Some(ContentKind::WorldTemplate)
+        } else if has_module("resources") {
+            Some(ContentKind::ResourcePack)
+        } else if has_module("data") || has_module("script") || has_module("javascript") {
+            Some(ContentKind::BehaviorPack)
+        } else if has_module("skin_pack") {
+            Some(ContentKind::SkinPack)
+        } else {
+            None
+        }
+    }
+
+    // 依赖的其他包（不含脚本模块）
+    pub fn pack_dependencies(&self) -> impl Iterator<Item = (&str, Option<PackVersion>)> {
+        self.dependencies.iter().filter_map(|dependency| dependency.uuid.as_deref().map(|uuid| (uuid, dependency.version)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn strips_comments_outside_strings() {
+        let json = "{\n  // 行注释\n  \"url\": \"http://example.com/*x*/\", /* 块\n注释 */ \"quote\": \"a\\\"//b\"\n}";
+        let value: Value = serde_json::from_str(&strip_json_comments(json)).unwrap();
+        assert_eq!(value["url"], "http://example.com/*x*/");
+        assert_eq!(value["quote"], "a\"//b");
+        assert_eq!(value.as_object().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn strips_unterminated_comments() {
+        assert_eq!(strip_json_comments("{} // end"), "{} ");
+        assert_eq!(strip_json_comments("{} /* end"), "{} ");
+    }
+
+    #[test]
+    fn reads_array_and_string_versions() {
+        assert_eq!(PackVersion::from_json(&json!([1, 20, 80])), Some(PackVersion([1, 20, 80])));
+        assert_eq!(PackVersion::from_json(&json!([2, 1])), Some(PackVersion([2, 1, 0])));
+        assert_eq!(PackVersion::from_json(&json!([1, 2, 3, 4])), Some(PackVersion([1, 2, 3])));
+        assert_eq!(PackVersion::from_json(&json!("1.8.0")), Some(PackVersion([1, 8, 0])));
+        assert_eq!(PackVersion::from_json(&json!("2")), Some(PackVersion([2, 0, 0])));
+        assert_eq!(PackVersion::from_json(&json!("1.8.0-beta")), None);
+        assert_eq!(PackVersion::from_json(&json!([1, "a", 0])), None);
+        assert_eq!(PackVersion::from_json(&json!(1)), None);
+        assert!(PackVersion([1, 10, 0]) > PackVersion([1, 9, 9]));
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::edition::Edition;
+
+pub mod compat;
+pub mod export;
+pub mod import;
+pub mod level;
+pub mod manifest;
+pub mod packs;
+pub mod sync;
+pub mod world;
+
+use manifest::PackManifest;
+
+// 存档目录名使用的字符，和游戏生成的一样是 12 位
+const WORLD_ID_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+-";
+const WORLD_ID_LENGTH: usize = 12;
+
+pub const PACK_KINDS: [ContentKind; 3] = [ContentKind::ResourcePack, ContentKind::BehaviorPack, ContentKind::SkinPack];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContentKind {
+    World,
+    WorldTemplate,
+    ResourcePack,
+    BehaviorPack,
+    SkinPack,
+}
+
+impl ContentKind {
+    pub fn folder_name(&self) -> &'static str {
+        match self {
+            ContentKind::World => "minecraftWorlds",
+            ContentKind::WorldTemplate => "world_templates",
+            ContentKind::ResourcePack => "resource_packs",
+            ContentKind::BehaviorPack => "behavior_packs",
+            ContentKind::SkinPack => "skin_packs",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ContentKind::World => "世界",
+            ContentKind::WorldTemplate => "世界模板",
+            ContentKind::ResourcePack => "资源包",
+            ContentKind::BehaviorPack => "行为包",
+            ContentKind::SkinPack => "皮肤包",
+        }
+    }
+}
+
+// <数据目录>\LocalState\games\com.mojang
+pub fn com_mojang_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("LocalState").join("games").join("com.mojang")
+}
+
+pub fn edition_com_mojang_dir(edition: &Edition) -> Option<PathBuf> {
+    edition.package_data_dir().map(|data_dir| com_mojang_dir(&data_dir))
+}
+
+// 读不到 manifest.json 的目录直接忽略
+pub fn installed_packs(com_mojang: &Path, kind: ContentKind) -> Vec<(PathBuf, PackManifest)> {
+    fs::read_dir(com_mojang.join(kind.folder_name()))
+        .map(|entries| {
+            entries.filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .filter_map(|path| PackManifest::from_file(&path.join("manifest.json")).ok().map(|manifest| (path, manifest)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn find_installed_pack(com_mojang: &Path, kind: ContentKind, uuid: &str) -> Option<(PathBuf, PackManifest)> {
+    installed_packs(com_mojang, kind)
+        .into_iter()
+        .find(|(_, manifest)| manifest.uuid.eq_ignore_ascii_case(uuid))
+}
+
+pub fn all_installed_packs(com_mojang: &Path) -> Vec<(ContentKind, PathBuf, PackManifest)> {
+    PACK_KINDS.iter()
+        .flat_map(|kind| installed_packs(com_mojang, *kind).into_iter().map(move |(path, manifest)| (*kind, path, manifest)))
+        .collect()
+}
+
+pub fn find_pack(com_mojang: &Path, uuid: &str) -> Option<(ContentKind, PathBuf, PackManifest)> {
+    PACK_KINDS.iter().find_map(|kind| find_installed_pack(com_mojang, *kind, uuid).map(|(path, manifest)| (*kind, path, manifest)))
+}
+
+// reserved 是同一批操作中已经分配出去、还没写入磁盘的 ID
+pub fn new_world_id(worlds_dir: &Path, reserved: &HashSet<String>) -> String {
+    let mut seed = (SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_nanos() as u64).unwrap_or(0) ^ ((process::id() as u64) << 32)) | 1;
+    loop {
+        let id: String = (0..WORLD_ID_LENGTH)
+            .map(|_| {
+                // xorshift，够用来避免重名
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                WORLD_ID_ALPHABET[(seed % WORLD_ID_ALPHABET.len() as u64) as usize] as char
+            })
+            .collect();
+        if !reserved.contains(&id) && !worlds_dir.join(&id).exists() {
+            return id;
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use super::manifest::{PackManifest, PackVersion};
+use super::world::{read_world_pack_list, world_pack_list_file, world_pack_references, write_world_pack_list, WorldPackReference};
+use super::{find_pack, installed_packs, ContentKind, PACK_KINDS};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingDependency {
+    pub required_by: String,
+    pub uuid: String,
+    pub version: Option<PackVersion>,
+    // 已安装但版本过低时的版本
+    pub installed: Option<PackVersion>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PackResolution {
+    // 要启用的包，第一个是请求的包，后面是它的依赖
+    pub packs: Vec<(ContentKind, PathBuf, PackManifest)>,
+    pub missing: Vec<MissingDependency>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PackActivation {
+    pub enabled: Vec<(ContentKind, String)>,
+    pub already_enabled: Vec<(ContentKind, String)>,
+    pub missing: Vec<MissingDependency>,
+    // 有缺失依赖且没有 force 时不写入
+    pub written: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct PackDeactivation {
+    pub kind: ContentKind,
+    // 仍然启用、并且依赖这个包的其他包
+    pub dependents: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WorldPackStatus {
+    pub kind: ContentKind,
+    pub reference: WorldPackReference,
+    pub manifest: Option<PackManifest>,
+    pub missing: Vec<MissingDependency>,
+    // 已安装但没有在这个世界启用的依赖
+    pub not_enabled: Vec<String>,
+}
+
+// 世界自带的包优先，其次是 com.mojang 中安装的包
+pub fn find_available_pack(com_mojang: &Path, world_dir: Option<&Path>, uuid: &str) -> Option<(ContentKind, PathBuf, PackManifest)> {
+    world_dir.and_then(|world_dir| find_pack(world_dir, uuid)).or_else(|| find_pack(com_mojang, uuid))
+}
+
+fn check_dependency(com_mojang: &Path, world_dir: Option<&Path>, manifest: &PackManifest, uuid: &str, version: Option<PackVersion>) -> Result<(ContentKind, PathBuf, PackManifest), MissingDependency> {
+    let found = find_available_pack(com_mojang, world_dir, uuid);
+    match found {
+        Some((_, _, ref dependency)) if version.is_some_and(|version| dependency.version < version) => Err(MissingDependency {
+            required_by: manifest.name.clone(),
+            uuid: uuid.to_string(),
+            version,
+            installed: Some(dependency.version),
+        }),
+        Some(found) => Ok(found),
+        None => Err(MissingDependency { required_by: manifest.name.clone(), uuid: uuid.to_string(), version, installed: None }),
+    }
+}
+
+// 只检查直接依赖
+pub fn missing_dependencies(com_mojang: &Path, world_dir: Option<&Path>, manifest: &PackManifest) -> Vec<MissingDependency> {
+    manifest.pack_dependencies()
+        .filter_map(|(uuid, version)| check_dependency(com_mojang, world_dir, manifest, uuid, version).err())
+        .collect()
+}
+
+// 递归展开依赖，资源包和行为包互相依赖的情况按 UUID 去重
+pub fn resolve_dependencies(com_mojang: &Path, world_dir: Option<&Path>, uuid: &str) -> io::Result<PackResolution> {
+    let root = find_available_pack(com_mojang, world_dir, uuid)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("找不到 UUID 为 {} 的包", uuid)))?;
+
+    let mut resolution = PackResolution::default();
+    let mut visited = HashSet::from([root.2.uuid.to_ascii_lowercase()]);
+    let mut pending = vec![root];
+    while let Some((kind, path, manifest)) = pending.pop() {
+        for (dependency_uuid, version) in manifest.pack_dependencies() {
+            if !visited.insert(dependency_uuid.to_ascii_lowercase()) {
+                continue;
+            }
+            match check_dependency(com_mojang, world_dir, &manifest, dependency_uuid, version) {
+                Ok(dependency) => pending.push(dependency),
+                Err(missing) => resolution.missing.push(missing),
+            }
+        }
+        resolution.packs.push((kind, path, manifest));
+    }
+    Ok(resolution)
+}
+
+fn entry_matches(entry: &Value, uuid: &str) -> bool {
+    entry.get("pack_id").and_then(Value::as_str).is_some_and(|pack_id| pack_id.eq_ignore_ascii_case(uuid))
+}
+
+// 新启用的包放在列表最前面 (优先级最高)，已启用的只更新版本号
+pub fn enable_pack(world_dir: &Path, com_mojang: &Path, uuid: &str, force: bool) -> io::Result<PackActivation> {
+    let resolution = resolve_dependencies(com_mojang, Some(world_dir), uuid)?;
+    let (kind, _, manifest) = &resolution.packs[0];
+    if world_pack_list_file(*kind).is_none() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{}不能在世界中启用: {}", kind.name(), manifest.name)));
+    }
+
+    let mut activation = PackActivation { missing: resolution.missing, ..Default::default() };
+    if !activation.missing.is_empty() && !force {
+        return Ok(activation);
+    }
+
+    for list_kind in [ContentKind::ResourcePack, ContentKind::BehaviorPack] {
+        let packs: Vec<&PackManifest> = resolution.packs.iter().filter(|(kind, _, _)| *kind == list_kind).map(|(_, _, manifest)| manifest).collect();
+        if packs.is_empty() {
+            continue;
+        }
+
+        let mut entries = read_world_pack_list(world_dir, list_kind)?;
+        let mut new_entries = Vec::new();
+        for manifest in packs {
+            match entries.iter_mut().find(|entry| entry_matches(entry, &manifest.uuid)) {
+                Some(entry) => {
+                    entry["version"] = manifest.version.to_json();
+                    activation.already_enabled.push((list_kind, manifest.name.clone()));
+                }
+                None => {
+                    new_entries.push(json!({ "pack_id": manifest.uuid, "version": manifest.version.to_json() }));
+                    activation.enabled.push((list_kind, manifest.name.clone()));
+                }
+            }
+        }
+        new_entries.extend(entries);
+        write_world_pack_list(world_dir, list_kind, &new_entries)?;
+    }
+    activation.written = true;
+    Ok(activation)
+}
+
+pub fn disable_pack(world_dir: &Path, com_mojang: &Path, uuid: &str) -> io::Result<PackDeactivation> {
+    let mut removed_from = None;
+    for kind in [ContentKind::ResourcePack, ContentKind::BehaviorPack] {
+        let mut entries = read_world_pack_list(world_dir, kind)?;
+        let count = entries.len();
+        entries.retain(|entry| !entry_matches(entry, uuid));
+        if entries.len() != count {
+            write_world_pack_list(world_dir, kind, &entries)?;
+            removed_from = Some(kind);
+        }
+    }
+    let kind = removed_from.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("世界没有启用 UUID 为 {} 的包", uuid)))?;
+
+    let mut dependents = Vec::new();
+    for kind in [ContentKind::ResourcePack, ContentKind::BehaviorPack] {
+        for reference in world_pack_references(world_dir, kind)? {
+            if let Some((_, _, manifest)) = find_available_pack(com_mojang, Some(world_dir), &reference.pack_id) {
+                if manifest.pack_dependencies().any(|(dependency, _)| dependency.eq_ignore_ascii_case(uuid)) {
+                    dependents.push(manifest.name);
+                }
+            }
+        }
+    }
+    Ok(PackDeactivation { kind, dependents })
+}
+
+pub fn check_world_packs(world_dir: &Path, com_mojang: &Path) -> io::Result<Vec<WorldPackStatus>> {
+    // UUID 不区分大小写
+    let mut enabled = HashSet::new();
+    let mut references = Vec::new();
+    for kind in [ContentKind::ResourcePack, ContentKind::BehaviorPack] {
+        for reference in world_pack_references(world_dir, kind)? {
+            enabled.insert(reference.pack_id.to_ascii_lowercase());
+            references.push((kind, reference));
+        }
+    }
+
+    Ok(references.into_iter()
+        .map(|(kind, reference)| {
+            let manifest = find_available_pack(com_mojang, Some(world_dir), &reference.pack_id).map(|(_, _, manifest)| manifest);
+            let (missing, not_enabled) = match &manifest {
+                Some(manifest) => (
+                    missing_dependencies(com_mojang, Some(world_dir), manifest),
+                    manifest.pack_dependencies()
+                        .filter(|(uuid, _)| !enabled.contains(&uuid.to_ascii_lowercase()) && find_available_pack(com_mojang, Some(world_dir), uuid).is_some())
+                        .map(|(uuid, _)| uuid.to_string())
+                        .collect(),
+                ),
+                None => (Vec::new(), Vec::new()),
+            };
+            WorldPackStatus { kind, reference, manifest, missing, not_enabled }
+        })
+        .collect())
+}
+
+// 所有已安装包中缺少依赖的项
+pub fn installed_missing_dependencies(com_mojang: &Path) -> Vec<MissingDependency> {
+    PACK_KINDS.iter()
+        .flat_map(|kind| installed_packs(com_mojang, *kind))
+        .flat_map(|(_, manifest)| missing_dependencies(com_mojang, None, &manifest))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::utils::files::{copy_dir, create_temp_dir};
+
+    const UI_RP: &str = "aaaaaaaa-0000-4000-8000-000000000001";
+    const CORE_BP: &str = "bbbbbbbb-0000-4000-8000-000000000002";
+    const ADDON_BP: &str = "cccccccc-0000-4000-8000-000000000003";
+    const LIB_BP: &str = "dddddddd-0000-4000-8000-000000000004";
+    const ABSENT_BP: &str = "eeeeeeee-0000-4000-8000-000000000005";
+
+    // tests/fixtures/packs 是一个 com.mojang 目录，复制一份以免测试修改它
+    fn fixture() -> PathBuf {
+        let com_mojang = create_temp_dir("bbl-packs").unwrap();
+        copy_dir(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("packs"), &com_mojang).unwrap();
+        com_mojang
+    }
+
+    fn world(com_mojang: &Path) -> PathBuf {
+        com_mojang.join("minecraftWorlds").join("world")
+    }
+
+    fn names(packs: &[(ContentKind, PathBuf, PackManifest)]) -> Vec<&str> {
+        packs.iter().map(|(_, _, manifest)| manifest.name.as_str()).collect()
+    }
+
+    #[test]
+    fn resolves_cross_dependencies_between_resource_and_behavior_packs() {
+        let com_mojang = fixture();
+        let resolution = resolve_dependencies(&com_mojang, None, &UI_RP.to_uppercase()).unwrap();
+        fs::remove_dir_all(&com_mojang).unwrap();
+
+        assert_eq!(names(&resolution.packs), ["UI RP", "Core BP"]);
+        assert_eq!(resolution.packs[1].0, ContentKind::BehaviorPack);
+        assert!(resolution.missing.is_empty());
+    }
+
+    #[test]
+    fn reports_missing_and_outdated_dependencies() {
+        let com_mojang = fixture();
+        let resolution = resolve_dependencies(&com_mojang, None, ADDON_BP).unwrap();
+        fs::remove_dir_all(&com_mojang).unwrap();
+
+        // 依赖自身的条目不会重复展开
+        assert_eq!(names(&resolution.packs), ["Addon BP"]);
+        assert_eq!(resolution.missing, vec![
+            MissingDependency { required_by: "Addon BP".to_string(), uuid: LIB_BP.to_string(), version: Some(PackVersion([2, 0, 0])), installed: Some(PackVersion([1, 2, 0])) },
+            MissingDependency { required_by: "Addon BP".to_string(), uuid: ABSENT_BP.to_string(), version: Some(PackVersion([1, 0, 0])), installed: None },
+        ]);
+    }
+
+    #[test]
+    fn enable_requires_force_when_dependencies_are_missing() {
+        let com_mojang = fixture();
+        let world_dir = world(&com_mojang);
+        fs::remove_file(world_dir.join("world_behavior_packs.json")).unwrap();
+
+        let refused = enable_pack(&world_dir, &com_mojang, ADDON_BP, false).unwrap();
+        let list_written = world_dir.join("world_behavior_packs.json").exists();
+        let forced = enable_pack(&world_dir, &com_mojang, ADDON_BP, true).unwrap();
+        let references = world_pack_references(&world_dir, ContentKind::BehaviorPack).unwrap();
+        fs::remove_dir_all(&com_mojang).unwrap();
+
+        assert!(!refused.written);
+        assert!(!list_written);
+        assert!(forced.written);
+        assert_eq!(references.iter().map(|reference| reference.pack_id.as_str()).collect::<Vec<_>>(), [ADDON_BP]);
+    }
+
+    #[test]
+    fn disable_reports_dependents_regardless_of_case() {
+        let com_mojang = fixture();
+        let world_dir = world(&com_mojang);
+        let deactivation = disable_pack(&world_dir, &com_mojang, &UI_RP.to_uppercase()).unwrap();
+        let references = world_pack_references(&world_dir, ContentKind::ResourcePack).unwrap();
+        fs::remove_dir_all(&com_mojang).unwrap();
+
+        assert_eq!(deactivation.kind, ContentKind::ResourcePack);
+        assert_eq!(deactivation.dependents, ["Core BP"]);
+        assert!(references.is_empty());
+    }
+
+    #[test]
+    fn check_matches_enabled_packs_regardless_of_case() {
+        let com_mojang = fixture();
+        let statuses = check_world_packs(&world(&com_mojang), &com_mojang).unwrap();
+        fs::remove_dir_all(&com_mojang).unwrap();
+
+        let status = |uuid: &str| statuses.iter().find(|status| status.reference.pack_id == uuid).unwrap();
+        assert!(status(UI_RP).not_enabled.is_empty());
+        assert!(status(CORE_BP).not_enabled.is_empty());
+        assert!(status(CORE_BP).missing.is_empty());
+        assert_eq!(status(ADDON_BP).not_enabled, [LIB_BP]);
+        assert_eq!(status(ADDON_BP).missing.len(), 2);
+    }
+}
@@ -0,0 +1,269 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use chrono::Local;
+
+use super::import::read_level_name;
+use super::manifest::PackManifest;
+use super::{all_installed_packs, find_pack, new_world_id, ContentKind};
+use crate::nbt::LevelDat;
+use crate::utils::archive::{to_io_error, zip_dirs};
+use crate::utils::files::{copy_dir, unique_dir};
+use crate::utils::paths::app_dir;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    #[default]
+    Skip,
+    Overwrite,
+    // 世界比较最后游玩时间，包比较版本号
+    Newer,
+    // 世界以新的目录 ID 另存一份；包的 UUID 不能重复，不能用于包
+    Rename,
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "skip" => Ok(ConflictPolicy::Skip),
+            "overwrite" => Ok(ConflictPolicy::Overwrite),
+            "newer" => Ok(ConflictPolicy::Newer),
+            "rename" => Ok(ConflictPolicy::Rename),
+            _ => Err(format!("未知的冲突处理方式: {} (可选 skip, overwrite, newer, rename)", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncSelection {
+    All,
+    Only(Vec<String>),
+    None,
+}
+
+impl SyncSelection {
+    // "all" 或逗号分隔的 ID 列表
+    pub fn parse(value: Option<&str>) -> Self {
+        match value {
+            None => SyncSelection::None,
+            Some(value) if value.eq_ignore_ascii_case("all") => SyncSelection::All,
+            Some(value) => SyncSelection::Only(value.split(',').map(|id| id.trim().to_string()).filter(|id| !id.is_empty()).collect()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncAction {
+    Copy,
+    Overwrite,
+    CopyAs(PathBuf),
+    Skip(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct SyncEntry {
+    pub kind: ContentKind,
+    // 世界目录 ID 或包 UUID
+    pub id: String,
+    pub name: String,
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub action: SyncAction,
+}
+
+impl SyncEntry {
+    // 实际写入的位置
+    pub fn target(&self) -> &Path {
+        match &self.action {
+            SyncAction::CopyAs(path) => path,
+            _ => &self.destination,
+        }
+    }
+}
+
+fn world_last_played(world_dir: &Path) -> Option<i64> {
+    LevelDat::from_file(&world_dir.join("level.dat")).ok()?.get("LastPlayed")?.as_i64()
+}
+
+// planned_ids 记录本次计划中会写入目标的世界 ID，避免另存的世界互相重名
+fn plan_world(source_dir: &Path, destination_worlds: &Path, policy: ConflictPolicy, planned_ids: &mut HashSet<String>) -> SyncEntry {
+    let id = source_dir.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let destination = destination_worlds.join(&id);
+    let action = if !destination.exists() && !planned_ids.contains(&id) {
+        planned_ids.insert(id.clone());
+        SyncAction::Copy
+    } else {
+        match policy {
+            ConflictPolicy::Skip => SyncAction::Skip("目标已存在同 ID 的世界".to_string()),
+            ConflictPolicy::Overwrite => SyncAction::Overwrite,
+            ConflictPolicy::Rename => {
+                let new_id = new_world_id(destination_worlds, planned_ids);
+                planned_ids.insert(new_id.clone());
+                SyncAction::CopyAs(destination_worlds.join(new_id))
+            }
+            ConflictPolicy::Newer if world_last_played(source_dir) > world_last_played(&destination) => SyncAction::Overwrite,
+            ConflictPolicy::Newer => SyncAction::Skip("目标中的世界不比源旧".to_string()),
+        }
+    };
+    SyncEntry {
+        kind: ContentKind::World,
+        name: read_level_name(source_dir).unwrap_or_else(|| id.clone()),
+        id,
+        source: source_dir.to_path_buf(),
+        destination,
+        action,
+    }
+}
+
+fn plan_pack(kind: ContentKind, source_dir: &Path, manifest: &PackManifest, destination: &Path, policy: ConflictPolicy) -> SyncEntry {
+    let (destination, action) = match find_pack(destination, &manifest.uuid) {
+        None => {
+            let folder_name = source_dir.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_else(|| manifest.uuid.clone());
+            (unique_dir(&destination.join(kind.folder_name()), &folder_name), SyncAction::Copy)
+        }
+        Some((_, path, installed)) => {
+            let action = match policy {
+                ConflictPolicy::Overwrite => SyncAction::Overwrite,
+                ConflictPolicy::Newer if manifest.version > installed.version => SyncAction::Overwrite,
+                ConflictPolicy::Newer => SyncAction::Skip(format!("目标已安装 {}，不比源旧", installed.version)),
+                ConflictPolicy::Skip | ConflictPolicy::Rename => SyncAction::Skip(format!("目标已安装 {}", installed.version)),
+            };
+            (path, action)
+        }
+    };
+    SyncEntry { kind, id: manifest.uuid.clone(), name: manifest.name.clone(), source: source_dir.to_path_buf(), destination, action }
+}
+
+// 只生成计划，不修改任何文件，dry-run 直接输出它
+pub fn plan_sync(source: &Path, destination: &Path, worlds: &SyncSelection, packs: &SyncSelection, policy: ConflictPolicy) -> io::Result<Vec<SyncEntry>> {
+    if policy == ConflictPolicy::Rename && *packs != SyncSelection::None {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "rename 只能用于世界，包的 UUID 不能重复；同步包时请使用 skip、overwrite 或 newer"));
+    }
+    let mut entries = Vec::new();
+    let source_worlds = source.join(ContentKind::World.folder_name());
+    let destination_worlds = destination.join(ContentKind::World.folder_name());
+
+    let world_dirs: Vec<PathBuf> = match worlds {
+        SyncSelection::None => Vec::new(),
+        SyncSelection::All => match fs::read_dir(&source_worlds) {
+            Ok(read_dir) => read_dir.filter_map(|entry| entry.ok()).map(|entry| entry.path()).filter(|path| path.join("level.dat").is_file()).collect(),
+            Err(_) => Vec::new(),
+        },
+        SyncSelection::Only(ids) => ids.iter()
+            .map(|id| {
+                let world_dir = source_worlds.join(id);
+                if world_dir.join("level.dat").is_file() {
+                    Ok(world_dir)
+                } else {
+                    Err(io::Error::new(io::ErrorKind::NotFound, format!("源中找不到世界: {}", id)))
+                }
+            })
+            .collect::<io::Result<_>>()?,
+    };
+    let mut planned_ids = HashSet::new();
+    entries.extend(world_dirs.iter().map(|world_dir| plan_world(world_dir, &destination_worlds, policy, &mut planned_ids)));
+
+    let source_packs = all_installed_packs(source);
+    let selected_packs: Vec<_> = match packs {
+        SyncSelection::None => Vec::new(),
+        SyncSelection::All => source_packs.iter().collect(),
+        SyncSelection::Only(uuids) => uuids.iter()
+            .map(|uuid| {
+                source_packs.iter()
+                    .find(|(_, _, manifest)| manifest.uuid.eq_ignore_ascii_case(uuid))
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("源中找不到包: {}", uuid)))
+            })
+            .collect::<io::Result<_>>()?,
+    };
+    entries.extend(selected_packs.iter().map(|(kind, path, manifest)| plan_pack(*kind, path, manifest, destination, policy)));
+    Ok(entries)
+}
+
+pub fn default_sync_backup_dir() -> PathBuf {
+    app_dir().join("backups")
+}
+
+// 把会被覆盖的目标内容打包，没有要覆盖的内容时返回 None
+pub fn backup_destination(entries: &[SyncEntry], destination: &Path, backup_dir: &Path) -> io::Result<Option<PathBuf>> {
+    let prefixes: Vec<(&Path, String)> = entries.iter()
+        .filter(|entry| entry.action == SyncAction::Overwrite)
+        .map(|entry| {
+            let relative = entry.destination.strip_prefix(destination).unwrap_or(&entry.destination);
+            (entry.destination.as_path(), relative.to_string_lossy().replace('\\', "/"))
+        })
+        .collect();
+    if prefixes.is_empty() {
+        return Ok(None);
+    }
+
+    fs::create_dir_all(backup_dir)?;
+    let archive_path = backup_dir.join(format!("sync-{}.zip", Local::now().format("%Y%m%d-%H%M%S")));
+    let sources: Vec<(&Path, &str)> = prefixes.iter().map(|(path, prefix)| (*path, prefix.as_str())).collect();
+    zip_dirs(&sources, &archive_path).map_err(to_io_error)?;
+    Ok(Some(archive_path))
+}
+
+pub fn apply_sync(entries: &[SyncEntry]) -> io::Result<()> {
+    for entry in entries {
+        match &entry.action {
+            SyncAction::Copy => copy_dir(&entry.source, &entry.destination)?,
+            SyncAction::Overwrite => {
+                fs::remove_dir_all(&entry.destination)?;
+                copy_dir(&entry.source, &entry.destination)?;
+            }
+            SyncAction::CopyAs(path) => copy_dir(&entry.source, path)?,
+            SyncAction::Skip(_) => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::files::create_temp_dir;
+
+    fn add_world(com_mojang: &Path, id: &str) {
+        let world_dir = com_mojang.join("minecraftWorlds").join(id);
+        fs::create_dir_all(&world_dir).unwrap();
+        fs::write(world_dir.join("level.dat"), b"").unwrap();
+    }
+
+    #[test]
+    fn renamed_worlds_get_distinct_ids() {
+        let root = create_temp_dir("bbl-sync").unwrap();
+        let (source, destination) = (root.join("source"), root.join("destination"));
+        for id in ["AAAAAAAAAAA=", "BBBBBBBBBBB=", "CCCCCCCCCCC="] {
+            add_world(&source, id);
+            add_world(&destination, id);
+        }
+
+        let entries = plan_sync(&source, &destination, &SyncSelection::All, &SyncSelection::None, ConflictPolicy::Rename).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        let targets: HashSet<&Path> = entries.iter().map(|entry| entry.target()).collect();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(targets.len(), 3);
+        assert!(entries.iter().all(|entry| matches!(entry.action, SyncAction::CopyAs(_))));
+    }
+
+    #[test]
+    fn rename_is_rejected_for_packs() {
+        let root = create_temp_dir("bbl-sync").unwrap();
+        let result = plan_sync(&root, &root, &SyncSelection::All, &SyncSelection::All, ConflictPolicy::Rename);
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn parses_selections() {
+        assert_eq!(SyncSelection::parse(None), SyncSelection::None);
+        assert_eq!(SyncSelection::parse(Some("ALL")), SyncSelection::All);
+        assert_eq!(SyncSelection::parse(Some("a, b,,")), SyncSelection::Only(vec!["a".to_string(), "b".to_string()]));
+    }
+}
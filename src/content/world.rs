@@ -0,0 +1,160 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, TimeZone};
+use serde_json::Value;
+
+use super::import::read_level_name;
+use super::manifest::{read_json, PackVersion};
+use super::ContentKind;
+use crate::nbt::{LevelDat, Tag};
+use crate::utils::files::dir_size;
+
+// experiments 里这两项是记录用的标记，不是实验性玩法本身；开启任意实验性玩法时游戏会同时写入
+pub(crate) const EXPERIMENT_MARKERS: [&str; 2] = ["experiments_ever_used", "saved_with_toggled_experiments"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorldPackReference {
+    pub pack_id: String,
+    pub version: Option<PackVersion>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WorldInfo {
+    pub id: String,
+    pub path: PathBuf,
+    pub name: String,
+    pub game_mode: Option<i64>,
+    pub last_played: Option<DateTime<Local>>,
+    pub last_opened_with_version: Option<String>,
+    pub size: u64,
+    pub experiments: Vec<String>,
+    // level.dat 读取失败时的原因
+    pub error: Option<String>,
+}
+
+pub fn game_mode_name(game_mode: i64) -> &'static str {
+    match game_mode {
+        0 => "生存",
+        1 => "创造",
+        2 => "冒险",
+        5 => "默认",
+        6 => "旁观",
+        _ => "未知",
+    }
+}
+
+// [1, 20, 80, 5, 0] -> 1.20.80.5，末尾的 0 是预留位
+pub fn version_from_tag(tag: &Tag) -> Option<String> {
+    let parts: Vec<i64> = tag.as_list()?.iter().filter_map(|part| part.as_i64()).collect();
+    if parts.is_empty() {
+        return None;
+    }
+    let len = parts.len().min(4);
+    Some(parts[..len].iter().map(|part| part.to_string()).collect::<Vec<_>>().join("."))
+}
+
+pub fn enabled_experiments(level: &LevelDat) -> Vec<String> {
+    level.get("experiments")
+        .and_then(|experiments| experiments.as_compound())
+        .map(|entries| {
+            entries.iter()
+                .filter(|(name, value)| !EXPERIMENT_MARKERS.contains(&name.as_str()) && value.as_i64() == Some(1))
+                .map(|(name, _)| name.clone())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn read_world(world_dir: &Path) -> WorldInfo {
+    let id = world_dir.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut info = WorldInfo {
+        id,
+        path: world_dir.to_path_buf(),
+        name: read_level_name(world_dir).unwrap_or_default(),
+        game_mode: None,
+        last_played: None,
+        last_opened_with_version: None,
+        size: dir_size(world_dir),
+        experiments: Vec::new(),
+        error: None,
+    };
+
+    match LevelDat::from_file(&world_dir.join("level.dat")) {
+        Ok(level) => {
+            if info.name.is_empty() {
+                info.name = level.get("LevelName").and_then(|name| name.as_str()).unwrap_or_default().to_string();
+            }
+            info.game_mode = level.get("GameType").and_then(|mode| mode.as_i64());
+            info.last_played = level.get("LastPlayed")
+                .and_then(|time| time.as_i64())
+                .and_then(|seconds| Local.timestamp_opt(seconds, 0).single());
+            info.last_opened_with_version = level.get("lastOpenedWithVersion").and_then(version_from_tag);
+            info.experiments = enabled_experiments(&level);
+        }
+        Err(err) => info.error = Some(err.to_string()),
+    }
+    info
+}
+
+// 最近游玩的排在前面
+pub fn list_worlds(com_mojang: &Path) -> io::Result<Vec<WorldInfo>> {
+    let worlds_dir = com_mojang.join(ContentKind::World.folder_name());
+    if !worlds_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut worlds: Vec<WorldInfo> = fs::read_dir(&worlds_dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.join("level.dat").is_file())
+        .map(|path| read_world(&path))
+        .collect();
+    worlds.sort_by_key(|world| std::cmp::Reverse(world.last_played));
+    Ok(worlds)
+}
+
+pub fn find_world(com_mojang: &Path, id: &str) -> Option<PathBuf> {
+    let world_dir = com_mojang.join(ContentKind::World.folder_name()).join(id);
+    world_dir.join("level.dat").is_file().then_some(world_dir)
+}
+
+pub fn world_pack_list_file(kind: ContentKind) -> Option<&'static str> {
+    match kind {
+        ContentKind::ResourcePack => Some("world_resource_packs.json"),
+        ContentKind::BehaviorPack => Some("world_behavior_packs.json"),
+        _ => None,
+    }
+}
+
+// world_resource_packs.json / world_behavior_packs.json 的原始条目，文件不存在时为空
+pub fn read_world_pack_list(world_dir: &Path, kind: ContentKind) -> io::Result<Vec<Value>> {
+    let Some(file_name) = world_pack_list_file(kind) else {
+        return Ok(Vec::new());
+    };
+    let path = world_dir.join(file_name);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(read_json(&path)?.as_array().cloned().unwrap_or_default())
+}
+
+pub fn write_world_pack_list(world_dir: &Path, kind: ContentKind, entries: &[Value]) -> io::Result<()> {
+    let file_name = world_pack_list_file(kind)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("{}不能在世界中启用", kind.name())))?;
+    let json = serde_json::to_string_pretty(entries).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    fs::write(world_dir.join(file_name), json)
+}
+
+fn pack_reference_of(entry: &Value) -> Option<WorldPackReference> {
+    Some(WorldPackReference {
+        pack_id: entry.get("pack_id").and_then(Value::as_str)?.to_lowercase(),
+        version: entry.get("version").and_then(PackVersion::from_json),
+    })
+}
+
+// 世界启用的包，按文件中的顺序 (靠前的优先级高)
+pub fn world_pack_references(world_dir: &Path, kind: ContentKind) -> io::Result<Vec<WorldPackReference>> {
+    Ok(read_world_pack_list(world_dir, kind)?.iter().filter_map(pack_reference_of).collect())
+}
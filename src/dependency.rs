@@ -0,0 +1,176 @@
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use zip::ZipArchive;
+
+use crate::manifest::{AppxManifest, PackageDependency, PackageIdentity};
+use crate::utils::paths::app_dir;
+use crate::version::PackageVersion;
+use crate::{debug, warning};
+
+#[derive(Debug, Clone)]
+pub struct CachedPackage {
+    pub path: PathBuf,
+    pub identity: PackageIdentity,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DependencyStatus {
+    Installed(PackageVersion),
+    Cached(PathBuf),
+    Missing,
+}
+
+#[derive(Debug, Clone)]
+pub struct DependencyCheck {
+    pub dependency: PackageDependency,
+    pub status: DependencyStatus,
+}
+
+pub fn default_dependency_cache_dir() -> PathBuf {
+    app_dir().join("dependencies")
+}
+
+pub fn read_package_manifest(package_path: &Path) -> io::Result<AppxManifest> {
+    let mut archive = ZipArchive::new(File::open(package_path)?)?;
+    let mut xml_data = String::new();
+    archive.by_name("AppxManifest.xml")?.read_to_string(&mut xml_data)?;
+    AppxManifest::parse(&xml_data)
+}
+
+// 依赖缓存目录中的 .appx/.msix 通过包内清单识别，文件名无所谓
+pub fn scan_dependency_cache(cache_dir: &Path) -> io::Result<Vec<CachedPackage>> {
+    let mut packages = Vec::new();
+    if !cache_dir.exists() {
+        return Ok(packages);
+    }
+
+    for entry in fs::read_dir(cache_dir)? {
+        let path = entry?.path();
+        let is_package = path.extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| matches!(extension.to_lowercase().as_str(), "appx" | "msix"))
+            .unwrap_or(false);
+        if !is_package {
+            continue;
+        }
+
+        match read_package_manifest(&path) {
+            Ok(manifest) => {
+                debug!("依赖缓存: {} {}", manifest.identity.name, manifest.identity.version);
+                packages.push(CachedPackage { path, identity: manifest.identity });
+            }
+            Err(err) => warning!("无法读取依赖包清单 {}: {}", path.display(), err),
+        }
+    }
+    Ok(packages)
+}
+
+pub fn architecture_compatible(target: Option<&str>, candidate: Option<&str>) -> bool {
+    match (target, candidate) {
+        (_, None) | (None, _) => true,
+        (Some(target), Some(candidate)) => {
+            candidate.eq_ignore_ascii_case("neutral")
+                || target.eq_ignore_ascii_case("neutral")
+                || target.eq_ignore_ascii_case(candidate)
+        }
+    }
+}
+
+// 没有 MinVersion 时任何版本都满足；无法解析时不能确定，按不满足处理
+pub fn satisfies(dependency: &PackageDependency, version: PackageVersion) -> bool {
+    if dependency.min_version.trim().is_empty() {
+        return true;
+    }
+    match dependency.min_version.parse::<PackageVersion>() {
+        Ok(min_version) => version >= min_version,
+        Err(err) => {
+            warning!("依赖 {} 的 MinVersion 无法解析 ({})，视为不满足", dependency.name, err);
+            false
+        }
+    }
+}
+
+pub fn select_cached<'a>(cache: &'a [CachedPackage], dependency: &PackageDependency, architecture: Option<&str>) -> Option<&'a CachedPackage> {
+    cache.iter()
+        .filter(|package| package.identity.name.eq_ignore_ascii_case(&dependency.name))
+        .filter(|package| architecture_compatible(architecture, package.identity.processor_architecture.as_deref()))
+        .filter_map(|package| package.identity.version.parse::<PackageVersion>().ok().map(|version| (version, package)))
+        .filter(|(version, _)| satisfies(dependency, *version))
+        .max_by_key(|(version, _)| *version)
+        .map(|(_, package)| package)
+}
+
+pub fn missing_dependencies(checks: &[DependencyCheck]) -> Vec<&PackageDependency> {
+    checks.iter()
+        .filter(|check| check.status == DependencyStatus::Missing)
+        .map(|check| &check.dependency)
+        .collect()
+}
+
+pub fn cached_dependency_paths(checks: &[DependencyCheck]) -> Vec<PathBuf> {
+    checks.iter()
+        .filter_map(|check| match &check.status {
+            DependencyStatus::Cached(path) => Some(path.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependency(min_version: &str) -> PackageDependency {
+        PackageDependency { name: "Microsoft.VCLibs.140.00.UWPDesktop".to_string(), publisher: "CN=Microsoft Corporation".to_string(), min_version: min_version.to_string() }
+    }
+
+    fn cached(file_name: &str, name: &str, version: &str, architecture: &str) -> CachedPackage {
+        CachedPackage {
+            path: PathBuf::from(file_name),
+            identity: PackageIdentity { name: name.to_string(), publisher: "CN=Microsoft Corporation".to_string(), version: version.to_string(), processor_architecture: Some(architecture.to_string()) },
+        }
+    }
+
+    fn cache() -> Vec<CachedPackage> {
+        vec![
+            cached("old.appx", "Microsoft.VCLibs.140.00.UWPDesktop", "14.0.30000.0", "x64"),
+            cached("new.appx", "microsoft.vclibs.140.00.uwpdesktop", "14.0.33519.0", "x64"),
+            cached("newest-arm.appx", "Microsoft.VCLibs.140.00.UWPDesktop", "14.0.34000.0", "arm64"),
+            cached("broken.appx", "Microsoft.VCLibs.140.00.UWPDesktop", "14.x", "x64"),
+            cached("other.appx", "Microsoft.UI.Xaml.2.8", "8.2310.30001.0", "x64"),
+        ]
+    }
+
+    fn selected<'a>(cache: &'a [CachedPackage], min_version: &str, architecture: Option<&str>) -> Option<&'a str> {
+        select_cached(cache, &dependency(min_version), architecture).and_then(|package| package.path.to_str())
+    }
+
+    #[test]
+    fn selects_newest_compatible_package() {
+        let cache = cache();
+        assert_eq!(selected(&cache, "14.0.30035.0", Some("x64")), Some("new.appx"));
+        assert_eq!(selected(&cache, "14.0.30035.0", Some("arm64")), Some("newest-arm.appx"));
+        assert_eq!(selected(&cache, "14.0.30035.0", None), Some("newest-arm.appx"));
+        assert_eq!(selected(&cache, "14.0.33600.0", Some("x64")), None);
+    }
+
+    #[test]
+    fn neutral_packages_match_any_architecture() {
+        let cache = vec![cached("neutral.appx", "Microsoft.VCLibs.140.00.UWPDesktop", "14.0.33519.0", "neutral")];
+        assert_eq!(selected(&cache, "14.0.0.0", Some("x86")), Some("neutral.appx"));
+        assert!(architecture_compatible(Some("neutral"), Some("x64")));
+        assert!(!architecture_compatible(Some("x86"), Some("x64")));
+    }
+
+    #[test]
+    fn unparseable_min_version_is_not_satisfied() {
+        let version = "14.0.33519.0".parse().unwrap();
+        assert!(satisfies(&dependency(""), version));
+        assert!(satisfies(&dependency("14.0.30035.0"), version));
+        assert!(!satisfies(&dependency("14.0.40000.0"), version));
+        assert!(!satisfies(&dependency("14.0.x"), version));
+        assert_eq!(selected(&cache(), "latest", Some("x64")), None);
+    }
+}
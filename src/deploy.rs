@@ -0,0 +1,158 @@
+use std::path::PathBuf;
+
+use windows::core::{Result, HSTRING};
+use windows::ApplicationModel::Package;
+use windows::Foundation::Collections::IIterable;
+use windows::Foundation::{IAsyncOperationWithProgress, Uri};
+use windows::Management::Deployment::{DeploymentOptions, DeploymentProgress, DeploymentResult, PackageManager, RemovalOptions};
+use windows::System::ProcessorArchitecture;
+
+use crate::dependency::{architecture_compatible, satisfies, select_cached, CachedPackage, DependencyCheck, DependencyStatus};
+use crate::diagnostics::DeploymentError;
+use crate::manifest::AppxManifest;
+use crate::version::PackageVersion;
+use crate::info;
+
+pub fn find_package(package_family_name: &str) -> Result<Option<Package>> {
+    let package_manager = PackageManager::new()?;
+    let packages = package_manager.FindPackagesByUserSecurityIdPackageFamilyName(&HSTRING::new(), &HSTRING::from(package_family_name))?;
+    Ok(packages.into_iter().next())
+}
+
+pub fn find_package_by_identity(name: &str, publisher: &str) -> Result<Option<Package>> {
+    let package_manager = PackageManager::new()?;
+    let packages = package_manager.FindPackagesByUserSecurityIdNamePublisher(&HSTRING::new(), &HSTRING::from(name), &HSTRING::from(publisher))?;
+    Ok(packages.into_iter().next())
+}
+
+pub fn package_version(package: &Package) -> Result<String> {
+    let version = package.Id()?.Version()?;
+    Ok(format!("{}.{}.{}.{}", version.Major, version.Minor, version.Build, version.Revision))
+}
+
+pub fn package_installed_path(package: &Package) -> Result<PathBuf> {
+    Ok(PathBuf::from(package.InstalledPath()?.to_string()))
+}
+
+// 开发模式注册的包来自松散目录，移除后目录还在；商店和 MSIX 安装的目录会随包删除
+pub fn package_is_development_mode(package: &Package) -> Result<bool> {
+    package.IsDevelopmentMode()
+}
+
+pub fn package_architecture(package: &Package) -> Result<&'static str> {
+    Ok(match package.Id()?.Architecture()? {
+        ProcessorArchitecture::X86 => "x86",
+        ProcessorArchitecture::X64 => "x64",
+        ProcessorArchitecture::Arm => "arm",
+        ProcessorArchitecture::Arm64 => "arm64",
+        _ => "neutral",
+    })
+}
+
+pub fn package_full_name(package: &Package) -> Result<String> {
+    Ok(package.Id()?.FullName()?.to_string())
+}
+
+pub fn package_family_name(package: &Package) -> Result<String> {
+    Ok(package.Id()?.FamilyName()?.to_string())
+}
+
+pub fn installed_version(name: &str, publisher: &str, architecture: Option<&str>) -> Result<Option<PackageVersion>> {
+    let package_manager = PackageManager::new()?;
+    let mut best = None;
+
+    for package in package_manager.FindPackagesByUserSecurityIdNamePublisher(&HSTRING::new(), &HSTRING::from(name), &HSTRING::from(publisher))? {
+        if !architecture_compatible(architecture, Some(package_architecture(&package)?)) {
+            continue;
+        }
+        if let Ok(version) = package_version(&package)?.parse::<PackageVersion>() {
+            best = best.max(Some(version));
+        }
+    }
+    Ok(best)
+}
+
+pub fn check_dependencies(manifest: &AppxManifest, cache: &[CachedPackage]) -> Result<Vec<DependencyCheck>> {
+    let architecture = manifest.identity.processor_architecture.as_deref();
+    let mut checks = Vec::new();
+
+    for dependency in &manifest.dependencies {
+        let status = match installed_version(&dependency.name, &dependency.publisher, architecture)? {
+            Some(version) if satisfies(dependency, version) => DependencyStatus::Installed(version),
+            _ => match select_cached(cache, dependency, architecture) {
+                Some(cached) => DependencyStatus::Cached(cached.path.clone()),
+                None => DependencyStatus::Missing,
+            },
+        };
+        checks.push(DependencyCheck { dependency: dependency.clone(), status });
+    }
+    Ok(checks)
+}
+
+fn check_deployment_result(result: &DeploymentResult) -> std::result::Result<(), DeploymentError> {
+    let extended_error_code = result.ExtendedErrorCode()?;
+    if extended_error_code.is_err() {
+        let error_text = result.ErrorText().map(|text| text.to_string()).unwrap_or_default();
+        return Err(DeploymentError::new(extended_error_code.0 as u32, error_text));
+    }
+    Ok(())
+}
+
+// 部署失败时 get() 只返回 HRESULT，ErrorText 要从 GetResults() 里取
+fn wait_deployment(operation: IAsyncOperationWithProgress<DeploymentResult, DeploymentProgress>) -> std::result::Result<DeploymentResult, DeploymentError> {
+    match operation.get() {
+        Ok(result) => {
+            check_deployment_result(&result)?;
+            Ok(result)
+        }
+        Err(err) => {
+            let result = operation.GetResults().ok();
+            let code = result.as_ref()
+                .and_then(|result| result.ExtendedErrorCode().ok())
+                .filter(|code| code.is_err())
+                .or_else(|| operation.ErrorCode().ok().filter(|code| code.is_err()))
+                .unwrap_or(err.code());
+            let error_text = result.and_then(|result| result.ErrorText().ok())
+                .map(|text| text.to_string())
+                .filter(|text| !text.trim().is_empty())
+                .unwrap_or_else(|| err.message());
+            Err(DeploymentError::new(code.0 as u32, error_text))
+        }
+    }
+}
+
+pub async fn register_package(appx_manifest_path: &str, dependency_package_paths: &[PathBuf], force_shutdown: bool) -> std::result::Result<DeploymentResult, DeploymentError> {
+    info!("注册 APPX：{}", appx_manifest_path);
+    let package_manager = PackageManager::new()?;
+    let uri = Uri::CreateUri(&HSTRING::from(appx_manifest_path))?;
+
+    let dependency_uris = dependency_package_paths.iter()
+        .map(|path| {
+            info!("依赖包：{}", path.display());
+            Uri::CreateUri(&HSTRING::from(path.as_path())).map(Some)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let dependency_uris = IIterable::<Uri>::try_from(dependency_uris)?;
+
+    let deployment_options = if force_shutdown {
+        DeploymentOptions::DevelopmentMode | DeploymentOptions::ForceTargetApplicationShutdown
+    } else {
+        DeploymentOptions::DevelopmentMode
+    };
+
+    let result = wait_deployment(package_manager.RegisterPackageAsync(&uri, &dependency_uris, deployment_options)?)?;
+
+    info!("APPX 注册成功");
+    Ok(result)
+}
+
+pub async fn remove_package(package_full_name: &str, preserve_data: bool) -> std::result::Result<(), DeploymentError> {
+    info!("移除包: {}", package_full_name);
+    let package_manager = PackageManager::new()?;
+    let removal_options = if preserve_data { RemovalOptions::PreserveApplicationData } else { RemovalOptions::None };
+
+    wait_deployment(package_manager.RemovePackageWithOptionsAsync(&HSTRING::from(package_full_name), removal_options)?)?;
+
+    info!("包已成功移除");
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use std::fmt;
+
+pub struct DeploymentDiagnostic {
+    pub code: u32,
+    pub name: &'static str,
+    pub explanation: &'static str,
+    pub suggestion: &'static str,
+}
+
+const fn diagnostic(code: u32, name: &'static str, explanation: &'static str, suggestion: &'static str) -> DeploymentDiagnostic {
+    DeploymentDiagnostic { code, name, explanation, suggestion }
+}
+
+pub static DEPLOYMENT_DIAGNOSTICS: &[DeploymentDiagnostic] = &[
+    diagnostic(0x80070005, "E_ACCESSDENIED", "拒绝访问，包目录或应用数据不可读写", "检查目录权限，不要把包解压到受保护的系统目录"),
+    diagnostic(0x80073CF0, "ERROR_INSTALL_OPEN_PACKAGE_FAILED", "无法打开包", "确认包文件完整，重新下载或重新解压"),
+    diagnostic(0x80073CF1, "ERROR_INSTALL_PACKAGE_NOT_FOUND", "找不到要操作的包", "用 check 命令确认路径和清单是否正确"),
+    diagnostic(0x80073CF2, "ERROR_INSTALL_INVALID_PACKAGE", "包数据无效", "重新解压，确认解压过程没有出错"),
+    diagnostic(0x80073CF3, "ERROR_INSTALL_RESOLVE_DEPENDENCY_FAILED", "依赖的框架包 (如 VCLibs) 缺失或版本过低", "将对应架构的框架包放入依赖缓存目录 (regpack -deps)"),
+    diagnostic(0x80073CF4, "ERROR_INSTALL_OUT_OF_DISK_SPACE", "磁盘空间不足", "清理磁盘空间后重试"),
+    diagnostic(0x80073CF6, "ERROR_INSTALL_REGISTRATION_FAILURE", "注册失败，常见原因是包目录中的文件被占用或损坏", "关闭游戏，确认已删除 AppxSignature.p7x，必要时重新解压"),
+    diagnostic(0x80073CF7, "ERROR_INSTALL_DEREGISTRATION_FAILURE", "移除旧注册失败", "关闭游戏后重试，或先执行 unregister"),
+    diagnostic(0x80073CF9, "ERROR_INSTALL_FAILED", "安装失败", "查看下面的系统信息，或在事件查看器 AppXDeployment-Server 日志中查找详情"),
+    diagnostic(0x80073CFA, "ERROR_REMOVE_FAILED", "移除包失败", "关闭游戏后重试"),
+    diagnostic(0x80073CFB, "ERROR_PACKAGE_ALREADY_EXISTS", "已存在相同标识但内容不同的包 (例如商店安装的同版本)", "先执行 unregister 移除已安装的版本再注册"),
+    diagnostic(0x80073CFF, "ERROR_INSTALL_POLICY_FAILURE", "系统不允许旁加载应用", "在 设置 > 开发者选项 中开启开发者模式"),
+    diagnostic(0x80073D00, "ERROR_PACKAGE_UPDATING", "该包正在被其他操作更新", "等待商店或其他安装任务完成后重试"),
+    diagnostic(0x80073D01, "ERROR_DEPLOYMENT_BLOCKED_BY_POLICY", "部署被组策略阻止", "联系管理员或检查 AllowAllTrustedApps/AllowDevelopmentWithoutDevLicense 策略"),
+    diagnostic(0x80073D02, "ERROR_PACKAGES_IN_USE", "应用正在运行，无法修改", "关闭游戏后重试"),
+    diagnostic(0x80073D06, "ERROR_INSTALL_PACKAGE_DOWNGRADE", "已安装更高版本，不允许直接降级", "先执行 unregister 移除高版本 (默认保留数据)"),
+    diagnostic(0x80073D10, "ERROR_INSTALL_WRONG_PROCESSOR_ARCHITECTURE", "包架构与系统不兼容", "下载与系统架构一致的包"),
+    diagnostic(0x80073D19, "ERROR_DEPLOYMENT_BLOCKED_BY_USER_LOG_OFF", "部署被阻止，以开发模式注册时通常是未开启开发者模式", "在 设置 > 开发者选项 中开启开发者模式后重试"),
+];
+
+pub fn lookup_diagnostic(code: u32) -> Option<&'static DeploymentDiagnostic> {
+    DEPLOYMENT_DIAGNOSTICS.iter().find(|diagnostic| diagnostic.code == code)
+}
+
+#[derive(Debug, Clone)]
+pub struct DeploymentError {
+    pub code: u32,
+    pub error_text: String,
+}
+
+impl DeploymentError {
+    pub fn new(code: u32, error_text: impl Into<String>) -> Self {
+        DeploymentError { code, error_text: error_text.into() }
+    }
+
+    pub fn diagnostic(&self) -> Option<&'static DeploymentDiagnostic> {
+        lookup_diagnostic(self.code)
+    }
+}
+
+impl fmt::Display for DeploymentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.diagnostic() {
+            Some(diagnostic) => {
+                write!(f, "0x{:08X} {}: {}", self.code, diagnostic.name, diagnostic.explanation)?;
+                write!(f, "\n  建议: {}", diagnostic.suggestion)?;
+            }
+            None => write!(f, "0x{:08X}", self.code)?,
+        }
+        if !self.error_text.is_empty() {
+            write!(f, "\n  系统信息: {}", self.error_text.trim())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DeploymentError {}
+
+#[cfg(windows)]
+impl From<windows::core::Error> for DeploymentError {
+    fn from(err: windows::core::Error) -> Self {
+        DeploymentError::new(err.code().0 as u32, err.message())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_up_known_codes() {
+        let diagnostic = lookup_diagnostic(0x80073CF3).unwrap();
+        assert_eq!(diagnostic.name, "ERROR_INSTALL_RESOLVE_DEPENDENCY_FAILED");
+        assert!(lookup_diagnostic(0x80004005).is_none());
+    }
+
+    #[test]
+    fn codes_are_unique() {
+        for (i, diagnostic) in DEPLOYMENT_DIAGNOSTICS.iter().enumerate() {
+            assert!(DEPLOYMENT_DIAGNOSTICS[i + 1..].iter().all(|other| other.code != diagnostic.code), "0x{:08X}", diagnostic.code);
+        }
+    }
+
+    #[test]
+    fn display_includes_diagnosis_and_error_text() {
+        let error = DeploymentError::new(0x80073D06, "  Windows cannot install package because a higher version is installed.\r\n");
+        assert_eq!(
+            error.to_string(),
+            "0x80073D06 ERROR_INSTALL_PACKAGE_DOWNGRADE: 已安装更高版本，不允许直接降级\n  建议: 先执行 unregister 移除高版本 (默认保留数据)\n  系统信息: Windows cannot install package because a higher version is installed."
+        );
+    }
+
+    #[test]
+    fn display_unknown_code_without_text() {
+        assert_eq!(DeploymentError::new(0x80004005, "").to_string(), "0x80004005");
+        assert_eq!(DeploymentError::new(0x80004005, "Unspecified error").to_string(), "0x80004005\n  系统信息: Unspecified error");
+    }
+}
@@ -0,0 +1,129 @@
+use std::env;
+use std::io;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::utils::hash::sha256;
+
+pub const MICROSOFT_PUBLISHER_ID: &str = "8wekyb3d8bbwe";
+const PUBLISHER_ID_ALPHABET: &[u8; 32] = b"0123456789abcdefghjkmnpqrstvwxyz";
+
+// 清单 Publisher 的 UTF-16LE 编码做 SHA-256，取前 8 字节按 Crockford Base32 编码成 13 个字符
+pub fn publisher_id(publisher: &str) -> String {
+    let utf16: Vec<u8> = publisher.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+    let digest = sha256(&utf16);
+    // 64 位后补一个 0 位，凑成 65 位
+    let bits = u128::from(u64::from_be_bytes(digest[..8].try_into().unwrap())) << 1;
+    (0..13).rev().map(|i| PUBLISHER_ID_ALPHABET[((bits >> (i * 5)) & 0x1F) as usize] as char).collect()
+}
+
+pub fn package_family_name_for(identity_name: &str, publisher: &str) -> String {
+    format!("{}_{}", identity_name, publisher_id(publisher))
+}
+
+fn microsoft_publisher_id() -> String {
+    MICROSOFT_PUBLISHER_ID.to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Edition {
+    pub identity_name: String,
+    pub app_id: String,
+    pub protocol_scheme: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    // 包家族名的后半部分，由清单的 Publisher 决定；旧配置中没有时按微软发布的包处理
+    #[serde(default = "microsoft_publisher_id")]
+    pub publisher_id: String,
+}
+
+impl Edition {
+    fn builtin(identity_name: &str, app_id: &str, protocol_scheme: &str, aliases: &[&str]) -> Self {
+        Edition {
+            identity_name: identity_name.to_string(),
+            app_id: app_id.to_string(),
+            protocol_scheme: protocol_scheme.to_string(),
+            aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+            publisher_id: microsoft_publisher_id(),
+        }
+    }
+
+    pub fn package_family_name(&self) -> String {
+        format!("{}_{}", self.identity_name, self.publisher_id)
+    }
+
+    pub fn app_user_model_id(&self) -> String {
+        format!("{}!{}", self.package_family_name(), self.app_id)
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        self.identity_name.eq_ignore_ascii_case(name)
+            || self.aliases.iter().any(|alias| alias.eq_ignore_ascii_case(name))
+    }
+
+    pub fn package_data_dir(&self) -> Option<PathBuf> {
+        package_data_dir(&self.package_family_name())
+    }
+}
+
+// %LOCALAPPDATA%\Packages\<包家族名>
+pub fn package_data_dir(package_family_name: &str) -> Option<PathBuf> {
+    env::var_os("LOCALAPPDATA").map(|local_app_data| {
+        PathBuf::from(local_app_data)
+            .join("Packages")
+            .join(package_family_name)
+    })
+}
+
+pub fn builtin_editions() -> Vec<Edition> {
+    vec![
+        Edition::builtin("Microsoft.MinecraftUWP", "App", "minecraft", &["release", "uwp"]),
+        Edition::builtin("Microsoft.MinecraftWindowsBeta", "App", "minecraft-preview", &["beta", "preview"]),
+        Edition::builtin("Microsoft.MinecraftEducationEdition", "Microsoft.MinecraftEducationEdition", "minecraftedu", &["education", "edu"]),
+        Edition::builtin("Microsoft.MinecraftEducationPreview", "Microsoft.MinecraftEducationEdition", "minecraftedu", &["education-preview", "edu-preview"]),
+    ]
+}
+
+// 内置版本加上改写包名后登记在配置文件中的自定义版本
+pub fn registered_editions(custom_editions: &[Edition]) -> Vec<Edition> {
+    let mut editions = builtin_editions();
+    editions.extend(custom_editions.iter().cloned());
+    editions
+}
+
+pub fn find_edition(editions: &[Edition], name: &str) -> Option<Edition> {
+    editions.iter().find(|edition| edition.matches(name)).cloned()
+}
+
+pub fn register_custom_edition(edition: Edition) -> io::Result<()> {
+    let mut config = Config::load()?;
+    config.custom_editions.retain(|custom| !custom.identity_name.eq_ignore_ascii_case(&edition.identity_name));
+    config.custom_editions.push(edition);
+    config.save()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_publisher_id_from_publisher() {
+        assert_eq!(publisher_id("CN=Microsoft Corporation, O=Microsoft Corporation, L=Redmond, S=Washington, C=US"), MICROSOFT_PUBLISHER_ID);
+        assert_eq!(package_family_name_for("Microsoft.MinecraftUWP", "CN=Microsoft Corporation, O=Microsoft Corporation, L=Redmond, S=Washington, C=US"), "Microsoft.MinecraftUWP_8wekyb3d8bbwe");
+        let other = publisher_id("CN=Example");
+        assert_eq!(other.len(), 13);
+        assert_ne!(other, MICROSOFT_PUBLISHER_ID);
+    }
+
+    #[test]
+    fn custom_edition_uses_its_publisher_id() {
+        let edition: Edition = serde_json::from_str(r#"{"identity_name":"Minecraft.Side","app_id":"App","protocol_scheme":"minecraft"}"#).unwrap();
+        assert_eq!(edition.package_family_name(), "Minecraft.Side_8wekyb3d8bbwe");
+
+        let edition = Edition { publisher_id: publisher_id("CN=Example"), ..edition };
+        assert_eq!(edition.package_family_name(), package_family_name_for("Minecraft.Side", "CN=Example"));
+        assert_eq!(edition.app_user_model_id(), format!("{}!App", package_family_name_for("Minecraft.Side", "CN=Example")));
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::launch::session::SessionReport;
+use crate::utils::paths::app_dir;
+use crate::warning;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub edition: String,
+    pub version: String,
+    pub package_family_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub started_at: DateTime<Local>,
+    pub ended_at: DateTime<Local>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<u32>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub partial: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+impl SessionRecord {
+    pub fn from_report(report: &SessionReport, edition: &str, version: &str, package_family_name: &str, profile: Option<&str>) -> Self {
+        SessionRecord {
+            edition: edition.to_string(),
+            version: version.to_string(),
+            package_family_name: package_family_name.to_string(),
+            profile: profile.map(|profile| profile.to_string()),
+            started_at: report.started_at,
+            ended_at: report.ended_at,
+            exit_code: report.exit_code,
+            partial: report.partial,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        (self.ended_at - self.started_at).max(Duration::zero())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PlaytimeSummary {
+    pub edition: String,
+    pub version: String,
+    pub sessions: usize,
+    pub total: Duration,
+    pub recent: Duration,
+    pub last_played: DateTime<Local>,
+}
+
+// 每行一条 JSON 记录，追加写入，不用每次重写整个文件
+pub fn default_history_path() -> PathBuf {
+    app_dir().join("playtime.jsonl")
+}
+
+pub fn append_record(path: &Path, record: &SessionRecord) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let line = serde_json::to_string(record).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+// 文件不存在时返回空记录，无法解析的行跳过
+pub fn load_history(path: &Path) -> io::Result<Vec<SessionRecord>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let data = fs::read_to_string(path)?;
+    Ok(data.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(i, line)| match serde_json::from_str(line) {
+            Ok(record) => Some(record),
+            Err(err) => {
+                warning!("跳过第 {} 行无法解析的记录: {}", i + 1, err);
+                None
+            }
+        })
+        .collect())
+}
+
+// 最近 days 天的起点，天数超出 chrono 可表示的范围时返回 None
+pub fn recent_since(now: DateTime<Local>, days: i64) -> Option<DateTime<Local>> {
+    Duration::try_days(days).and_then(|days| now.checked_sub_signed(days))
+}
+
+// 按版本汇总，最近玩过的排在前面
+pub fn summarize(records: &[SessionRecord], recent_since: DateTime<Local>) -> Vec<PlaytimeSummary> {
+    let mut summaries: HashMap<(String, String), PlaytimeSummary> = HashMap::new();
+    for record in records {
+        let summary = summaries.entry((record.edition.clone(), record.version.clone()))
+            .or_insert_with(|| PlaytimeSummary {
+                edition: record.edition.clone(),
+                version: record.version.clone(),
+                sessions: 0,
+                total: Duration::zero(),
+                recent: Duration::zero(),
+                last_played: record.started_at,
+            });
+        summary.sessions += 1;
+        summary.total += record.duration();
+        if record.started_at >= recent_since {
+            summary.recent += record.duration();
+        }
+        summary.last_played = summary.last_played.max(record.started_at);
+    }
+
+    let mut summaries: Vec<PlaytimeSummary> = summaries.into_values().collect();
+    summaries.sort_by_key(|summary| std::cmp::Reverse(summary.last_played));
+    summaries
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn export_csv(records: &[SessionRecord]) -> String {
+    let mut csv = String::from("edition,version,package_family_name,profile,started_at,ended_at,duration_seconds,exit_code,partial\n");
+    for record in records {
+        let fields = [
+            csv_field(&record.edition),
+            csv_field(&record.version),
+            csv_field(&record.package_family_name),
+            csv_field(record.profile.as_deref().unwrap_or("")),
+            record.started_at.to_rfc3339(),
+            record.ended_at.to_rfc3339(),
+            record.duration().num_seconds().to_string(),
+            record.exit_code.map(|code| code.to_string()).unwrap_or_default(),
+            record.partial.to_string(),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+pub fn export_json(records: &[SessionRecord]) -> io::Result<String> {
+    serde_json::to_string_pretty(records).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+// 按扩展名选择格式，不是 .csv 的都导出为 JSON
+pub fn export_history(records: &[SessionRecord], path: &Path) -> io::Result<()> {
+    let is_csv = path.extension().map(|extension| extension.eq_ignore_ascii_case("csv")).unwrap_or(false);
+    let data = if is_csv { export_csv(records) } else { export_json(records)? };
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn time(day: u32, hour: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap()
+    }
+
+    fn record(version: &str, day: u32, hours: u32) -> SessionRecord {
+        SessionRecord {
+            edition: "Microsoft.MinecraftUWP".to_string(),
+            version: version.to_string(),
+            package_family_name: "Microsoft.MinecraftUWP_8wekyb3d8bbwe".to_string(),
+            profile: None,
+            started_at: time(day, 10),
+            ended_at: time(day, 10 + hours),
+            exit_code: Some(0),
+            partial: false,
+        }
+    }
+
+    #[test]
+    fn summarizes_totals_and_recent_window() {
+        let mut backwards = record("1.20.80", 2, 0);
+        backwards.ended_at = time(1, 10);
+        let records = [record("1.20.80", 1, 2), record("1.21.0", 5, 1), record("1.20.80", 10, 3), backwards];
+
+        let summaries = summarize(&records, time(5, 0));
+        assert_eq!(summaries.len(), 2);
+
+        assert_eq!(summaries[0].version, "1.20.80");
+        assert_eq!(summaries[0].sessions, 3);
+        assert_eq!(summaries[0].total, Duration::hours(5));
+        assert_eq!(summaries[0].recent, Duration::hours(3));
+        assert_eq!(summaries[0].last_played, time(10, 10));
+
+        assert_eq!(summaries[1].version, "1.21.0");
+        assert_eq!(summaries[1].total, Duration::hours(1));
+        assert_eq!(summaries[1].recent, Duration::hours(1));
+    }
+
+    #[test]
+    fn recent_since_rejects_out_of_range_days() {
+        assert_eq!(recent_since(time(10, 0), 7), Some(time(3, 0)));
+        assert_eq!(recent_since(time(10, 0), 99_999_999_999_999), None);
+        assert_eq!(recent_since(time(10, 0), i64::MAX), None);
+    }
+
+    #[test]
+    fn csv_quotes_and_escapes_fields() {
+        let mut first = record("1.20.80", 1, 2);
+        first.profile = Some("a,\"b\"\nc".to_string());
+        first.exit_code = None;
+        first.partial = true;
+        let csv = export_csv(&[first, record("1.21.0", 2, 1)]);
+
+        let lines: Vec<&str> = csv.split('\n').collect();
+        assert_eq!(lines[0], "edition,version,package_family_name,profile,started_at,ended_at,duration_seconds,exit_code,partial");
+        assert_eq!(lines[1], "Microsoft.MinecraftUWP,1.20.80,Microsoft.MinecraftUWP_8wekyb3d8bbwe,\"a,\"\"b\"\"");
+        assert_eq!(lines[2], format!("c\",{},{},7200,,true", time(1, 10).to_rfc3339(), time(1, 12).to_rfc3339()));
+        assert_eq!(lines[3], format!("Microsoft.MinecraftUWP,1.21.0,Microsoft.MinecraftUWP_8wekyb3d8bbwe,,{},{},3600,0,false", time(2, 10).to_rfc3339(), time(2, 11).to_rfc3339()));
+        assert_eq!(lines[4], "");
+    }
+
+    #[test]
+    fn history_round_trips_and_skips_bad_lines() {
+        let dir = crate::utils::files::create_temp_dir("bbl-history").unwrap();
+        let path = dir.join("playtime.jsonl");
+        let mut partial = record("1.21.0", 2, 1);
+        partial.partial = true;
+        append_record(&path, &record("1.20.80", 1, 2)).unwrap();
+        fs::write(&path, format!("{}not json\n\n", fs::read_to_string(&path).unwrap())).unwrap();
+        append_record(&path, &partial).unwrap();
+
+        let records = load_history(&path).unwrap();
+        let json = export_json(&records).unwrap();
+        let missing = load_history(&dir.join("missing.jsonl")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert!(!records[0].partial && records[1].partial);
+        assert_eq!(records[1].duration(), Duration::hours(1));
+        let exported: Vec<SessionRecord> = serde_json::from_str(&json).unwrap();
+        assert_eq!(exported.len(), 2);
+        assert_eq!(exported[0].started_at, records[0].started_at);
+        assert!(missing.is_empty());
+    }
+}
@@ -0,0 +1,268 @@
+use std::io;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use serde::{Deserialize, Serialize};
+
+use crate::edition::{find_edition, Edition};
+use crate::{error, info, warning};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookStage {
+    PreRegister,
+    PreLaunch,
+    PostExit,
+}
+
+impl HookStage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HookStage::PreRegister => "pre_register",
+            HookStage::PreLaunch => "pre_launch",
+            HookStage::PostExit => "post_exit",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    #[default]
+    Abort,
+    Continue,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookConfig {
+    pub stage: HookStage,
+    pub command: String,
+    // 包名或别名，不填则对所有版本生效
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edition: Option<String>,
+    // 完整版本号，或以 * 结尾的前缀，如 "1.20.*"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub on_failure: FailurePolicy,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HookContext {
+    pub edition: String,
+    pub version: String,
+    pub package_family_name: String,
+    pub install_path: String,
+    pub data_path: String,
+}
+
+impl HookContext {
+    fn variables(&self) -> [(&'static str, &str); 5] {
+        [
+            ("edition", &self.edition),
+            ("version", &self.version),
+            ("package_family_name", &self.package_family_name),
+            ("install_path", &self.install_path),
+            ("data_path", &self.data_path),
+        ]
+    }
+}
+
+// 变量通过环境变量传给钩子，如 {install_path} 对应 BBL_INSTALL_PATH
+fn variable_env_name(name: &str) -> String {
+    format!("BBL_{}", name.to_uppercase())
+}
+
+fn hook_env(stage: HookStage, context: &HookContext) -> Vec<(String, String)> {
+    std::iter::once(("stage", stage.name()))
+        .chain(context.variables())
+        .map(|(name, value)| (variable_env_name(name), value.to_string()))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Cmd,
+    Sh,
+}
+
+impl Shell {
+    pub fn current() -> Self {
+        if cfg!(windows) { Shell::Cmd } else { Shell::Sh }
+    }
+
+    // 引用环境变量而不是直接写入值，值中的 & | ; 空格等不会被 shell 解释；quote 是变量所在位置已经打开的引号
+    fn reference(&self, env_name: &str, quote: Option<char>) -> String {
+        match (self, quote) {
+            (Shell::Cmd, Some(_)) => format!("%{}%", env_name),
+            (Shell::Cmd, None) => format!("\"%{}%\"", env_name),
+            (Shell::Sh, Some('"')) => format!("${{{}}}", env_name),
+            (Shell::Sh, Some(_)) => format!("'\"${{{}}}\"'", env_name),
+            (Shell::Sh, None) => format!("\"${{{}}}\"", env_name),
+        }
+    }
+
+    fn update_quote(&self, quote: Option<char>, c: char) -> Option<char> {
+        match (self, quote, c) {
+            (Shell::Cmd, None, '"') | (Shell::Sh, None, '"' | '\'') => Some(c),
+            (_, Some(open), _) if open == c => None,
+            _ => quote,
+        }
+    }
+}
+impl HookConfig {
+    // editions 用来把配置里的别名 (如 preview) 对应到包名
+    pub fn matches(&self, stage: HookStage, context: &HookContext, editions: &[Edition]) -> bool {
+        if self.stage != stage {
+            return false;
+        }
+
+        let edition_matches = self.edition.as_deref()
+            .map(|edition| {
+                edition.eq_ignore_ascii_case(&context.edition)
+                    || find_edition(editions, edition).map(|edition| edition.identity_name == context.edition).unwrap_or(false)
+            })
+            .unwrap_or(true);
+        let version_matches = self.version.as_deref()
+            .map(|version| match version.strip_suffix('*') {
+                Some(prefix) => context.version.starts_with(prefix),
+                None => version == context.version,
+            })
+            .unwrap_or(true);
+
+        edition_matches && version_matches
+    }
+}
+
+// {version} 之类的变量替换为对应环境变量的引用，已在引号中的不再加引号；空值直接写成空字符串
+pub fn render_command(template: &str, stage: HookStage, context: &HookContext, shell: Shell) -> String {
+    let variables: Vec<(&str, &str)> = std::iter::once(("stage", stage.name())).chain(context.variables()).collect();
+    let mut command = String::new();
+    let mut quote = None;
+    let mut chars = template.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let variable = (c == '{')
+            .then(|| template[i + 1..].find('}').map(|end| &template[i + 1..i + 1 + end]))
+            .flatten()
+            .and_then(|name| variables.iter().find(|(variable, _)| *variable == name));
+        if let Some((name, value)) = variable {
+            match (value.is_empty(), quote) {
+                (true, Some(_)) => {}
+                (true, None) => command.push_str("\"\""),
+                (false, _) => command.push_str(&shell.reference(&variable_env_name(name), quote)),
+            }
+            for _ in 0..name.len() + 1 {
+                chars.next();
+            }
+            continue;
+        }
+
+        command.push(c);
+        // sh 中引号外和双引号中的 \ 转义下一个字符
+        if shell == Shell::Sh && c == '\\' && quote != Some('\'') {
+            if let Some((_, escaped)) = chars.next() {
+                command.push(escaped);
+            }
+            continue;
+        }
+        quote = shell.update_quote(quote, c);
+    }
+    command
+}
+
+fn run_shell(command: &str, env: &[(String, String)], working_dir: Option<&Path>) -> io::Result<Output> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    };
+    shell.envs(env.iter().map(|(name, value)| (name, value)));
+    if let Some(working_dir) = working_dir.filter(|dir| dir.exists()) {
+        shell.current_dir(working_dir);
+    }
+    shell.output()
+}
+
+fn log_output(stage: HookStage, output: &[u8], is_stderr: bool) {
+    for line in String::from_utf8_lossy(output).lines().filter(|line| !line.trim().is_empty()) {
+        if is_stderr {
+            warning!("[{}] {}", stage.name(), line);
+        } else {
+            info!("[{}] {}", stage.name(), line);
+        }
+    }
+}
+
+pub fn run_hooks(hooks: &[HookConfig], editions: &[Edition], stage: HookStage, context: &HookContext) -> Result<(), String> {
+    for hook in hooks.iter().filter(|hook| hook.matches(stage, context, editions)) {
+        let command = render_command(&hook.command, stage, context, Shell::current());
+        info!("执行 {} 钩子: {}", stage.name(), hook.command);
+
+        let failure = match run_shell(&command, &hook_env(stage, context), Some(Path::new(&context.install_path))) {
+            Ok(output) => {
+                log_output(stage, &output.stdout, false);
+                log_output(stage, &output.stderr, true);
+                (!output.status.success()).then(|| format!("钩子退出码: {}", output.status.code().unwrap_or(-1)))
+            }
+            Err(err) => Some(format!("无法执行钩子: {}", err)),
+        };
+
+        if let Some(failure) = failure {
+            match hook.on_failure {
+                FailurePolicy::Abort => {
+                    error!("{} 钩子失败: {}", stage.name(), failure);
+                    return Err(format!("{} 钩子失败: {}", stage.name(), hook.command));
+                }
+                FailurePolicy::Continue => warning!("{} 钩子失败，继续执行: {}", stage.name(), failure),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> HookContext {
+        HookContext {
+            edition: "Microsoft.MinecraftUWP".to_string(),
+            version: "1.20.8001.0".to_string(),
+            package_family_name: "Microsoft.MinecraftUWP_8wekyb3d8bbwe".to_string(),
+            install_path: "d:/games/a & b; echo pwned".to_string(),
+            data_path: String::new(),
+        }
+    }
+
+    #[test]
+    fn quotes_variables_for_cmd() {
+        let command = render_command("copy /Y a.txt \"{install_path}\\a.txt\" & echo {version} {data_path} {unknown}", HookStage::PreLaunch, &context(), Shell::Cmd);
+        assert_eq!(command, "copy /Y a.txt \"%BBL_INSTALL_PATH%\\a.txt\" & echo \"%BBL_VERSION%\" \"\" {unknown}");
+    }
+
+    #[test]
+    fn quotes_variables_for_sh() {
+        let command = render_command("echo {stage} '{edition}' \"{install_path}/x\" \\{version}", HookStage::PostExit, &context(), Shell::Sh);
+        assert_eq!(command, "echo \"${BBL_STAGE}\" ''\"${BBL_EDITION}\"'' \"${BBL_INSTALL_PATH}/x\" \\{version}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn values_are_not_interpreted_by_the_shell() {
+        let context = context();
+        let command = render_command("printf '%s|%s' {install_path} {data_path}", HookStage::PreLaunch, &context, Shell::Sh);
+        let output = run_shell(&command, &hook_env(HookStage::PreLaunch, &context), None).unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "d:/games/a & b; echo pwned|");
+    }
+
+    #[test]
+    fn matches_version_prefix() {
+        let hook = HookConfig { stage: HookStage::PreLaunch, command: String::new(), edition: None, version: Some("1.20.*".to_string()), on_failure: FailurePolicy::Abort };
+        assert!(hook.matches(HookStage::PreLaunch, &context(), &[]));
+        assert!(!hook.matches(HookStage::PostExit, &context(), &[]));
+    }
+}
@@ -0,0 +1,227 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::edition::{find_edition, publisher_id, register_custom_edition, Edition};
+use crate::manifest::AppxManifest;
+
+// 包名只允许字母、数字、'.'、'-'，长度 3-50
+pub fn validate_identity_name(name: &str) -> Result<(), String> {
+    if name.len() < 3 || name.len() > 50 {
+        return Err(format!("包名长度必须为 3-50 个字符: {}", name));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-') {
+        return Err(format!("包名只能包含字母、数字、'.' 和 '-' ('_' 是包全名的分隔符，可改用 '-'): {}", name));
+    }
+    Ok(())
+}
+
+fn escape_xml(value: &str) -> String {
+    value.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// 开始标签结束的 '>'，跳过引号中的内容
+fn tag_end(xml: &str, start: usize) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in xml[start..].char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, '>') => return Some(start + i),
+            _ => {}
+        }
+    }
+    None
+}
+
+// 查找 <tag 或 <prefix:tag 开头的第一个开始标签，跳过注释、CDATA、处理指令和结束标签，返回 '<' 和 '>' 的位置
+fn find_tag(xml: &str, local_name: &str) -> Option<(usize, usize)> {
+    let mut offset = 0;
+    loop {
+        let start = offset + xml[offset..].find('<')?;
+        let rest = &xml[start..];
+        let skip_past = |terminator: &str| rest.find(terminator).map(|i| start + i + terminator.len());
+        offset = match rest {
+            _ if rest.starts_with("<!--") => skip_past("-->")?,
+            _ if rest.starts_with("<![CDATA[") => skip_past("]]>")?,
+            _ if rest.starts_with("<?") => skip_past("?>")?,
+            _ if rest.starts_with("<!") || rest.starts_with("</") => skip_past(">")?,
+            _ => {
+                let end = tag_end(xml, start)?;
+                let tag_name = xml[start + 1..end].split(|c: char| c.is_whitespace() || c == '/').next()?;
+                if tag_name == local_name || tag_name.rsplit_once(':').is_some_and(|(_, name)| name == local_name) {
+                    return Some((start, end));
+                }
+                end
+            }
+        };
+    }
+}
+
+// 标签内属性值的起止位置 (不含引号)，允许 '=' 两边有空白和单引号
+fn find_attribute(tag: &str, attribute: &str) -> Option<(usize, usize)> {
+    let bytes = tag.as_bytes();
+    let mut i = tag.find(|c: char| c.is_whitespace())?;
+    loop {
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if i >= bytes.len() || bytes[i] == b'/' || bytes[i] == b'>' {
+            return None;
+        }
+        let name_start = i;
+        while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'=' {
+            i += 1;
+        }
+        let name = &tag[name_start..i];
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if bytes.get(i) != Some(&b'=') {
+            return None;
+        }
+        i += 1;
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        let quote = *bytes.get(i).filter(|quote| matches!(quote, b'"' | b'\''))?;
+        let value_start = i + 1;
+        let value_end = value_start + tag[value_start..].find(quote as char)?;
+        if name == attribute {
+            return Some((value_start, value_end));
+        }
+        i = value_end + 1;
+    }
+}
+
+pub fn replace_attribute(xml: &str, local_name: &str, attribute: &str, value: &str) -> Option<String> {
+    let (start, end) = find_tag(xml, local_name)?;
+    let (value_start, value_end) = find_attribute(&xml[start..end], attribute)?;
+    Some(format!("{}{}{}", &xml[..start + value_start], escape_xml(value), &xml[start + value_end..]))
+}
+
+pub fn replace_element_text(xml: &str, local_name: &str, value: &str) -> Option<String> {
+    let (start, end) = find_tag(xml, local_name)?;
+    let tag_name = xml[start + 1..end].split(|c: char| c.is_whitespace() || c == '/').next()?;
+    // <DisplayName/> 改成带内容的元素
+    if xml[..end].ends_with('/') {
+        return Some(format!("{}>{}</{}>{}", xml[..end - 1].trim_end(), escape_xml(value), tag_name, &xml[end + 1..]));
+    }
+    let close_tag = format!("</{}", tag_name);
+    let text_end = xml[end..].find(&close_tag).map(|i| end + i)?;
+
+    Some(format!("{}{}{}", &xml[..end + 1], escape_xml(value), &xml[text_end..]))
+}
+
+pub fn rewrite_identity(xml: &str, identity_name: &str, display_name: &str) -> io::Result<String> {
+    let invalid = |what: &str| io::Error::new(io::ErrorKind::InvalidData, format!("清单中找不到 {}", what));
+
+    let xml = replace_attribute(xml, "Identity", "Name", identity_name).ok_or_else(|| invalid("Identity Name"))?;
+    let xml = replace_element_text(&xml, "DisplayName", display_name).ok_or_else(|| invalid("Properties/DisplayName"))?;
+    // 开始菜单和任务栏显示的名称，没有也不影响注册
+    let xml = replace_attribute(&xml, "VisualElements", "DisplayName", display_name).unwrap_or(xml);
+
+    // 改写后重新解析，确认仍是有效的清单再写入
+    let rewritten = AppxManifest::parse(&xml)?;
+    if rewritten.identity.name != identity_name {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("改写后的包名不正确: {}", rewritten.identity.name)));
+    }
+    Ok(xml)
+}
+
+// 改写解压目录中的清单，并把新的包名登记为自定义版本
+pub fn apply_custom_identity(package_dir: &Path, identity_name: &str, display_name: Option<&str>, editions: &[Edition]) -> io::Result<Edition> {
+    validate_identity_name(identity_name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let manifest_path = package_dir.join("AppxManifest.xml");
+    let xml_data = fs::read_to_string(&manifest_path)?;
+    let original = AppxManifest::parse(&xml_data)?;
+
+    let display_name = display_name.map(|name| name.to_string()).unwrap_or_else(|| identity_name.to_string());
+    let rewritten = rewrite_identity(&xml_data, identity_name, &display_name)?;
+    fs::write(&manifest_path, rewritten)?;
+
+    let base_edition = find_edition(editions, &original.identity.name);
+    let edition = Edition {
+        identity_name: identity_name.to_string(),
+        app_id: original.app_id().unwrap_or("App").to_string(),
+        protocol_scheme: base_edition.map(|edition| edition.protocol_scheme).unwrap_or_else(|| "minecraft".to_string()),
+        aliases: Vec::new(),
+        // 只改了包名，发布者不变
+        publisher_id: publisher_id(&original.identity.publisher),
+    };
+    register_custom_edition(edition.clone())?;
+    Ok(edition)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<!-- <Identity Name="Commented.Out" /> -->
+<Package xmlns="http://schemas.microsoft.com/appx/manifest/foundation/windows10" xmlns:uap="http://schemas.microsoft.com/appx/manifest/uap/windows10">
+  <Identity ProcessorArchitecture="x64" Version="1.20.8001.0" Publisher="CN=Microsoft Corporation, O=Microsoft Corporation, L=Redmond, S=Washington, C=US" Name="Microsoft.MinecraftUWP" />
+  <Properties>
+    <DisplayName>Minecraft &amp; Friends</DisplayName>
+    <PublisherDisplayName>Microsoft Studios</PublisherDisplayName>
+    <Logo>AppStoreLogo.png</Logo>
+  </Properties>
+  <Applications>
+    <Application Id="App" Executable="Minecraft.Windows.exe" EntryPoint="Minecraft_Win10.App">
+      <uap:VisualElements Square44x44Logo="Assets/Square44x44Logo.png" DisplayName="Minecraft" Description="a > b" BackgroundColor="transparent">
+        <uap:DefaultTile ShortName="Minecraft" />
+      </uap:VisualElements>
+    </Application>
+  </Applications>
+</Package>
+"#;
+
+    #[test]
+    fn rewrites_identity_and_display_names() {
+        let xml = rewrite_identity(MANIFEST, "Minecraft.Side-1", "Side & \"Test\"").unwrap();
+        let manifest = AppxManifest::parse(&xml).unwrap();
+        assert_eq!(manifest.identity.name, "Minecraft.Side-1");
+        assert_eq!(manifest.identity.version, "1.20.8001.0");
+        assert_eq!(manifest.identity.processor_architecture.as_deref(), Some("x64"));
+        assert!(xml.contains("<!-- <Identity Name=\"Commented.Out\" /> -->"));
+        assert!(xml.contains("<DisplayName>Side &amp; &quot;Test&quot;</DisplayName>"));
+        assert!(xml.contains(r#"<uap:VisualElements Square44x44Logo="Assets/Square44x44Logo.png" DisplayName="Side &amp; &quot;Test&quot;" Description="a > b""#));
+        assert!(xml.contains(r#"<uap:DefaultTile ShortName="Minecraft" />"#));
+    }
+
+    #[test]
+    fn only_touches_the_named_attribute() {
+        let xml = r#"<Identity ResourceName="keep" Name = 'Old.Name' Version="1.0.0.0"/>"#;
+        assert_eq!(replace_attribute(xml, "Identity", "Name", "New's").unwrap(), r#"<Identity ResourceName="keep" Name = 'New&apos;s' Version="1.0.0.0"/>"#);
+        assert_eq!(replace_attribute(xml, "Identity", "Publisher", "x"), None);
+    }
+
+    #[test]
+    fn skips_comments_and_closing_tags() {
+        let xml = r#"<a><!-- <b x="1"> --></b><![CDATA[<b x="2">]]><b x="3"/></a>"#;
+        assert_eq!(replace_attribute(xml, "b", "x", "4").unwrap(), r#"<a><!-- <b x="1"> --></b><![CDATA[<b x="2">]]><b x="4"/></a>"#);
+    }
+
+    #[test]
+    fn fills_self_closing_and_namespaced_elements() {
+        assert_eq!(replace_element_text("<p><DisplayName /></p>", "DisplayName", "A<B").unwrap(), "<p><DisplayName>A&lt;B</DisplayName></p>");
+        assert_eq!(replace_element_text("<p><uap:DisplayName>x</uap:DisplayName></p>", "DisplayName", "y").unwrap(), "<p><uap:DisplayName>y</uap:DisplayName></p>");
+    }
+
+    #[test]
+    fn missing_identity_is_an_error() {
+        assert!(rewrite_identity("<Package><Properties><DisplayName>x</DisplayName></Properties></Package>", "A.B.C", "x").is_err());
+    }
+
+    #[test]
+    fn validates_identity_names() {
+        assert!(validate_identity_name("Minecraft.Side-1").is_ok());
+        assert!(validate_identity_name("ab").is_err());
+        assert!(validate_identity_name("Minecraft_Side").is_err());
+    }
+}
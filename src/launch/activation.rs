@@ -0,0 +1,42 @@
+use windows::core::HSTRING;
+use windows::Foundation::Uri;
+use windows::System::{Launcher as SystemLauncher, LauncherOptions};
+use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_LOCAL_SERVER, COINIT_MULTITHREADED};
+use windows::Win32::UI::Shell::{ApplicationActivationManager, IApplicationActivationManager, AO_NONE};
+
+use super::Launcher;
+
+pub struct ActivationManagerLauncher;
+
+fn format_error(err: &windows::core::Error) -> String {
+    format!("0x{:08X} {}", err.code().0 as u32, err.message())
+}
+
+fn launch_uri_for_package(package_family_name: &str, uri: &str) -> windows::core::Result<bool> {
+    let options = LauncherOptions::new()?;
+    options.SetTargetApplicationPackageFamilyName(&HSTRING::from(package_family_name))?;
+    SystemLauncher::LaunchUriWithOptionsAsync(&Uri::CreateUri(&HSTRING::from(uri))?, &options)?.get()
+}
+
+fn activate(app_user_model_id: &str, arguments: Option<&str>) -> windows::core::Result<u32> {
+    unsafe {
+        // 已经初始化过时返回 S_FALSE，不影响后续调用
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+        let manager: IApplicationActivationManager = CoCreateInstance(&ApplicationActivationManager, None, CLSCTX_LOCAL_SERVER)?;
+        manager.ActivateApplication(&HSTRING::from(app_user_model_id), &HSTRING::from(arguments.unwrap_or("")), AO_NONE)
+    }
+}
+
+impl Launcher for ActivationManagerLauncher {
+    fn activate(&self, app_user_model_id: &str, arguments: Option<&str>) -> Result<u32, String> {
+        activate(app_user_model_id, arguments).map_err(|err| format_error(&err))
+    }
+
+    fn activate_uri(&self, package_family_name: &str, uri: &str) -> Result<(), String> {
+        match launch_uri_for_package(package_family_name, uri) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(format!("系统拒绝打开 {}", uri)),
+            Err(err) => Err(format_error(&err)),
+        }
+    }
+}
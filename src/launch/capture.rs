@@ -0,0 +1,353 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use super::session::{format_duration, SessionReport};
+use crate::edition::package_data_dir;
+use crate::utils::paths::app_dir;
+use crate::{error, info, warning};
+
+const CONTENT_LOG_PREFIX: &str = "contentlog";
+const GAME_EXECUTABLE: &str = "minecraft.windows.exe";
+const TAIL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone)]
+pub struct CaptureLocations {
+    pub content_log_dir: PathBuf,
+    pub crash_dirs: Vec<PathBuf>,
+    // 所有程序共用的转储目录，只收集本次游戏进程的 Minecraft.Windows.exe.<PID>.dmp
+    pub shared_crash_dirs: Vec<PathBuf>,
+}
+
+impl CaptureLocations {
+    // LocalState\logs 下是 ContentLog 和游戏自己写的 dmp，AC\Temp 和 %LOCALAPPDATA%\CrashDumps 是系统写的 dmp
+    pub fn for_data_dir(data_dir: &Path) -> Self {
+        let content_log_dir = data_dir.join("LocalState").join("logs");
+        let crash_dirs = vec![content_log_dir.clone(), data_dir.join("AC").join("Temp")];
+        let shared_crash_dirs = env::var_os("LOCALAPPDATA")
+            .map(|local_app_data| vec![PathBuf::from(local_app_data).join("CrashDumps")])
+            .unwrap_or_default();
+        CaptureLocations { content_log_dir, crash_dirs, shared_crash_dirs }
+    }
+
+    pub fn for_package(package_family_name: &str) -> Option<Self> {
+        package_data_dir(package_family_name).map(|data_dir| Self::for_data_dir(&data_dir))
+    }
+
+    fn content_logs(&self) -> Vec<PathBuf> {
+        list_files(&self.content_log_dir, is_content_log)
+    }
+
+    // pid 为 None 时收集共享目录里所有游戏进程的转储
+    fn crash_files(&self, pid: Option<u32>) -> Vec<PathBuf> {
+        self.crash_dirs.iter()
+            .flat_map(|dir| list_files(dir, is_crash_file))
+            .chain(self.shared_crash_dirs.iter().flat_map(|dir| list_files(dir, |path| is_game_crash_file(path, pid))))
+            .collect()
+    }
+}
+
+fn list_files(dir: &Path, filter: impl Fn(&Path) -> bool) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries.filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file() && filter(path))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn is_content_log(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .map(|name| name.starts_with(CONTENT_LOG_PREFIX) && name.ends_with(".txt"))
+        .unwrap_or(false)
+}
+
+fn is_crash_file(path: &Path) -> bool {
+    path.extension()
+        .map(|extension| matches!(extension.to_string_lossy().to_lowercase().as_str(), "dmp" | "mdmp"))
+        .unwrap_or(false)
+}
+
+// 系统转储的文件名为 <程序名>.<PID>.dmp，其它版本或实例的游戏也会写到同一个目录
+fn game_crash_pid(path: &Path) -> Option<u32> {
+    let name = path.file_stem()?.to_string_lossy().to_lowercase();
+    name.strip_prefix(GAME_EXECUTABLE)?.strip_prefix('.')?.parse().ok()
+}
+
+fn is_game_crash_file(path: &Path, pid: Option<u32>) -> bool {
+    is_crash_file(path)
+        && match (game_crash_pid(path), pid) {
+            (Some(dump_pid), Some(pid)) => dump_pid == pid,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+}
+
+fn file_len(path: &Path) -> u64 {
+    fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0)
+}
+
+// 启动前记下已有文件的大小，之后只处理新增或变长的部分
+pub struct LogCapture {
+    pub package_family_name: String,
+    pub locations: CaptureLocations,
+    existing: HashMap<PathBuf, u64>,
+}
+
+impl LogCapture {
+    pub fn start(package_family_name: &str) -> Option<Self> {
+        CaptureLocations::for_package(package_family_name).map(|locations| Self::with_locations(package_family_name, locations))
+    }
+
+    pub fn with_locations(package_family_name: &str, locations: CaptureLocations) -> Self {
+        let existing = locations.content_logs()
+            .into_iter()
+            .chain(locations.crash_files(None))
+            .map(|path| {
+                let len = file_len(&path);
+                (path, len)
+            })
+            .collect();
+        LogCapture { package_family_name: package_family_name.to_string(), locations, existing }
+    }
+
+    fn changed(&self, path: &Path) -> bool {
+        self.existing.get(path).map(|len| file_len(path) != *len).unwrap_or(true)
+    }
+
+    pub fn new_content_logs(&self) -> Vec<PathBuf> {
+        self.locations.content_logs().into_iter().filter(|path| self.changed(path)).collect()
+    }
+
+    // 共享目录里只算本次会话 pid 的转储
+    pub fn new_crash_files(&self, pid: u32) -> Vec<PathBuf> {
+        self.locations.crash_files(Some(pid)).into_iter().filter(|path| self.changed(path)).collect()
+    }
+
+    pub fn tail(&self) -> ContentLogTail {
+        let mut tailer = ContentLogTailer {
+            dir: self.locations.content_log_dir.clone(),
+            offsets: self.existing.iter()
+                .filter(|(path, _)| is_content_log(path))
+                .map(|(path, len)| (path.clone(), *len))
+                .collect(),
+        };
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let handle = thread::spawn(move || {
+            while !thread_stop.load(Ordering::Relaxed) {
+                tailer.log_new_lines();
+                thread::sleep(TAIL_INTERVAL);
+            }
+            // 退出前最后写入的内容
+            tailer.log_new_lines();
+        });
+        ContentLogTail { stop, handle: Some(handle) }
+    }
+}
+
+struct ContentLogTailer {
+    dir: PathBuf,
+    offsets: HashMap<PathBuf, u64>,
+}
+
+impl ContentLogTailer {
+    fn read_new_lines(&mut self, path: &Path) -> io::Result<Vec<String>> {
+        let len = file_len(path);
+        let offset = self.offsets.get(path).copied().filter(|offset| *offset <= len).unwrap_or(0);
+        if offset == len {
+            return Ok(Vec::new());
+        }
+
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut data = Vec::new();
+        file.take(len - offset).read_to_end(&mut data)?;
+
+        // 只处理完整的行，没写完的留到下一次
+        let Some(line_end) = data.iter().rposition(|byte| *byte == b'\n') else {
+            return Ok(Vec::new());
+        };
+        self.offsets.insert(path.to_path_buf(), offset + line_end as u64 + 1);
+        Ok(String::from_utf8_lossy(&data[..line_end])
+            .lines()
+            .map(|line| line.trim_end().to_string())
+            .filter(|line| !line.is_empty())
+            .collect())
+    }
+
+    fn log_new_lines(&mut self) {
+        for path in list_files(&self.dir, is_content_log) {
+            match self.read_new_lines(&path) {
+                Ok(lines) => {
+                    for line in lines {
+                        if line.to_lowercase().contains("[error]") {
+                            warning!("[ContentLog] {}", line);
+                        } else {
+                            info!("[ContentLog] {}", line);
+                        }
+                    }
+                }
+                Err(err) => error!("无法读取内容日志 {}: {}", path.display(), err),
+            }
+        }
+    }
+}
+
+pub struct ContentLogTail {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ContentLogTail {
+    pub fn stop(mut self) {
+        self.finish();
+    }
+
+    fn finish(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for ContentLogTail {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+pub fn default_crash_report_dir() -> PathBuf {
+    app_dir().join("crash_reports")
+}
+
+pub fn is_crash(report: &SessionReport, crash_files: &[PathBuf]) -> bool {
+    !report.exited_normally() || !crash_files.is_empty()
+}
+
+fn session_summary(package_family_name: &str, report: &SessionReport, files: &[PathBuf]) -> String {
+    let exit_code = report.exit_code
+        .map(|code| format!("{} (0x{:08X})", code, code))
+        .unwrap_or_else(|| "未知".to_string());
+    let mut summary = format!(
+        "包家族名: {}\nPID: {}\n启动时间: {}\n退出时间: {}\n游玩时长: {}\n退出码: {}\n\n收集的文件:\n",
+        package_family_name,
+        report.pid,
+        report.started_at.format("%Y-%m-%d %H:%M:%S"),
+        report.ended_at.format("%Y-%m-%d %H:%M:%S"),
+        format_duration(report.duration()),
+        exit_code,
+    );
+    for file in files {
+        summary.push_str(&format!("  {}\n", file.display()));
+    }
+    summary
+}
+
+// 在 report_root 下建立 <包家族名>-<时间> 目录，复制日志和转储文件并写入摘要
+pub fn bundle_crash_report(report_root: &Path, capture: &LogCapture, report: &SessionReport) -> io::Result<PathBuf> {
+    let report_dir = report_root.join(format!("{}-{}", capture.package_family_name, report.ended_at.format("%Y%m%d-%H%M%S")));
+    fs::create_dir_all(&report_dir)?;
+
+    let files: Vec<PathBuf> = capture.new_content_logs().into_iter().chain(capture.new_crash_files(report.pid)).collect();
+    for (i, file) in files.iter().enumerate() {
+        let Some(file_name) = file.file_name() else {
+            continue;
+        };
+        let mut destination = report_dir.join(file_name);
+        if destination.exists() {
+            destination = report_dir.join(format!("{}-{}", i, file_name.to_string_lossy()));
+        }
+        if let Err(err) = fs::copy(file, &destination) {
+            warning!("无法复制 {}: {}", file.display(), err);
+        }
+    }
+
+    fs::write(report_dir.join("summary.txt"), session_summary(&capture.package_family_name, report, &files))?;
+    Ok(report_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::files::create_temp_dir;
+
+    #[test]
+    fn shared_dump_dir_only_reports_game_dumps() {
+        let root = create_temp_dir("bbl-capture").unwrap();
+        let locations = CaptureLocations {
+            content_log_dir: root.join("logs"),
+            crash_dirs: vec![root.join("logs")],
+            shared_crash_dirs: vec![root.join("CrashDumps")],
+        };
+        fs::create_dir_all(root.join("logs")).unwrap();
+        fs::create_dir_all(root.join("CrashDumps")).unwrap();
+        fs::write(root.join("CrashDumps").join("explorer.exe.100.dmp"), b"old").unwrap();
+        let capture = LogCapture::with_locations("Microsoft.MinecraftUWP_8wekyb3d8bbwe", locations);
+
+        fs::write(root.join("CrashDumps").join("Minecraft.Windows.exe.4242.dmp"), b"dump").unwrap();
+        // 同一时间另一个版本的游戏崩溃
+        fs::write(root.join("CrashDumps").join("Minecraft.Windows.exe.5555.dmp"), b"dump").unwrap();
+        fs::write(root.join("CrashDumps").join("Minecraft.Windows.exe.dmp"), b"dump").unwrap();
+        fs::write(root.join("CrashDumps").join("chrome.exe.77.dmp"), b"dump").unwrap();
+        fs::write(root.join("logs").join("minidump.dmp"), b"dump").unwrap();
+        let mut crash_files = capture.new_crash_files(4242);
+        crash_files.sort();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(crash_files, vec![root.join("CrashDumps").join("Minecraft.Windows.exe.4242.dmp"), root.join("logs").join("minidump.dmp")]);
+    }
+
+    #[test]
+    fn parses_pid_from_dump_name() {
+        assert_eq!(game_crash_pid(Path::new("Minecraft.Windows.exe.4242.dmp")), Some(4242));
+        assert_eq!(game_crash_pid(Path::new("minecraft.windows.exe.17.mdmp")), Some(17));
+        assert_eq!(game_crash_pid(Path::new("Minecraft.Windows.exe.dmp")), None);
+        assert_eq!(game_crash_pid(Path::new("Minecraft.Windows.exe.abc.dmp")), None);
+        assert_eq!(game_crash_pid(Path::new("Minecraft.Windows.exe2.1.dmp")), None);
+        assert!(is_game_crash_file(Path::new("Minecraft.Windows.exe.1.dmp"), None));
+        assert!(!is_game_crash_file(Path::new("Minecraft.Windows.exe.1.txt"), None));
+    }
+
+    #[test]
+    fn tail_keeps_partial_lines_for_next_read() {
+        let dir = create_temp_dir("bbl-capture").unwrap();
+        let path = dir.join("ContentLog__Sat_May_4_2024.txt");
+        fs::write(&path, "old line\n").unwrap();
+        let mut tailer = ContentLogTailer { dir: dir.clone(), offsets: HashMap::from([(path.clone(), file_len(&path))]) };
+
+        let append = |text: &str| {
+            use std::io::Write;
+            fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(text.as_bytes()).unwrap();
+        };
+        let unchanged = tailer.read_new_lines(&path).unwrap();
+        append("[Error] first\r\nsec");
+        let first = tailer.read_new_lines(&path).unwrap();
+        let partial = tailer.read_new_lines(&path).unwrap();
+        append("ond\n\nthird");
+        let second = tailer.read_new_lines(&path).unwrap();
+        append("\n");
+        let third = tailer.read_new_lines(&path).unwrap();
+        // 游戏重新创建日志后从头读取
+        fs::write(&path, "new\n").unwrap();
+        let recreated = tailer.read_new_lines(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(unchanged.is_empty());
+        assert_eq!(first, ["[Error] first"]);
+        assert!(partial.is_empty());
+        assert_eq!(second, ["second"]);
+        assert_eq!(third, ["third"]);
+        assert_eq!(recreated, ["new"]);
+    }
+}
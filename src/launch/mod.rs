@@ -0,0 +1,235 @@
+#[cfg(windows)]
+mod activation;
+pub mod capture;
+pub mod protocol;
+pub mod session;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::process::{package_processes, ProcessInfo, ProcessQuery};
+
+#[cfg(windows)]
+pub use activation::ActivationManagerLauncher;
+
+const PROCESS_DISCOVERY_TIMEOUT: Duration = Duration::from_secs(15);
+const PROCESS_DISCOVERY_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LaunchResult {
+    Activated { pid: u32 },
+    AlreadyRunning { pid: u32 },
+    Failed(String),
+}
+
+impl LaunchResult {
+    pub fn pid(&self) -> Option<u32> {
+        match self {
+            LaunchResult::Activated { pid } | LaunchResult::AlreadyRunning { pid } => Some(*pid),
+            LaunchResult::Failed(_) => None,
+        }
+    }
+}
+
+// 只负责向系统发出激活请求，是否已在运行由 launch_package 判断
+pub trait Launcher {
+    // 返回激活得到的 PID
+    fn activate(&self, app_user_model_id: &str, arguments: Option<&str>) -> Result<u32, String>;
+
+    // 协议激活，由指定包处理 uri，拿不到 PID
+    fn activate_uri(&self, package_family_name: &str, uri: &str) -> Result<(), String>;
+}
+
+// AUMID 的格式为 <包家族名>!<应用 Id>
+pub fn package_family_name_of(app_user_model_id: &str) -> &str {
+    app_user_model_id.split('!').next().unwrap_or(app_user_model_id)
+}
+
+// 至少查询一次，超时仍没有进程时返回 None
+fn discover_pid(processes: &dyn ProcessQuery, package_family_name: &str, timeout: Duration) -> Option<u32> {
+    let started = Instant::now();
+    loop {
+        if let Some(process) = package_processes(processes, package_family_name).ok()?.first() {
+            return Some(process.pid);
+        }
+        if started.elapsed() >= timeout {
+            return None;
+        }
+        thread::sleep(PROCESS_DISCOVERY_INTERVAL);
+    }
+}
+
+fn launch_with_timeout(launcher: &dyn Launcher, processes: &dyn ProcessQuery, app_user_model_id: &str, uri: Option<&str>, discovery_timeout: Duration) -> LaunchResult {
+    let package_family_name = package_family_name_of(app_user_model_id);
+    // 激活前记录已有的进程，激活后系统会把请求交给它
+    let running: Option<ProcessInfo> = package_processes(processes, package_family_name).unwrap_or_default().into_iter().next();
+
+    match uri {
+        Some(uri) => match launcher.activate_uri(package_family_name, uri) {
+            Ok(()) => match running {
+                Some(process) => LaunchResult::AlreadyRunning { pid: process.pid },
+                None => match discover_pid(processes, package_family_name, discovery_timeout) {
+                    Some(pid) => LaunchResult::Activated { pid },
+                    None => LaunchResult::Failed(format!("已发送 {}，但没有检测到游戏进程", uri)),
+                },
+            },
+            Err(err) => LaunchResult::Failed(err),
+        },
+        None => match launcher.activate(app_user_model_id, None) {
+            Ok(pid) => match running {
+                Some(process) => LaunchResult::AlreadyRunning { pid: process.pid },
+                None => LaunchResult::Activated { pid },
+            },
+            Err(err) => LaunchResult::Failed(err),
+        },
+    }
+}
+
+// 有 uri 时交给包的协议处理，否则直接启动应用
+pub fn launch_package(launcher: &dyn Launcher, processes: &dyn ProcessQuery, app_user_model_id: &str, uri: Option<&str>) -> LaunchResult {
+    launch_with_timeout(launcher, processes, app_user_model_id, uri, PROCESS_DISCOVERY_TIMEOUT)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+
+    use chrono::Local;
+
+    use super::session::wait_for_session;
+    use super::*;
+    use crate::process::FakeProcessQuery;
+
+    const AUMID: &str = "Microsoft.MinecraftUWP_8wekyb3d8bbwe!App";
+    const FAMILY: &str = "Microsoft.MinecraftUWP_8wekyb3d8bbwe";
+
+    // 和系统一样，每次激活都创建一个新进程，已在运行时也不例外
+    struct FakeLauncher<'a> {
+        processes: &'a FakeProcessQuery,
+        next_pid: Cell<u32>,
+        failure: Option<String>,
+        // 协议激活后不出现进程
+        silent_uri: bool,
+        uris: RefCell<Vec<String>>,
+    }
+
+    impl<'a> FakeLauncher<'a> {
+        fn new(processes: &'a FakeProcessQuery) -> Self {
+            FakeLauncher { processes, next_pid: Cell::new(1000), failure: None, silent_uri: false, uris: RefCell::default() }
+        }
+
+        fn failing(mut self, message: &str) -> Self {
+            self.failure = Some(message.to_string());
+            self
+        }
+
+        fn spawn(&self, package_family_name: &str) -> u32 {
+            let pid = self.next_pid.get();
+            self.next_pid.set(pid + 1);
+            self.processes.spawn(ProcessInfo {
+                pid,
+                name: "Minecraft.Windows.exe".to_string(),
+                package_family_name: Some(package_family_name.to_string()),
+            });
+            pid
+        }
+    }
+
+    impl Launcher for FakeLauncher<'_> {
+        fn activate(&self, app_user_model_id: &str, _arguments: Option<&str>) -> Result<u32, String> {
+            match &self.failure {
+                Some(message) => Err(message.clone()),
+                None => Ok(self.spawn(package_family_name_of(app_user_model_id))),
+            }
+        }
+
+        fn activate_uri(&self, package_family_name: &str, uri: &str) -> Result<(), String> {
+            if let Some(message) = &self.failure {
+                return Err(message.clone());
+            }
+            self.uris.borrow_mut().push(uri.to_string());
+            if !self.silent_uri {
+                self.spawn(package_family_name);
+            }
+            Ok(())
+        }
+    }
+
+    fn launch(launcher: &FakeLauncher, uri: Option<&str>) -> LaunchResult {
+        launch_with_timeout(launcher, launcher.processes, AUMID, uri, Duration::ZERO)
+    }
+
+    #[test]
+    fn first_launch_activates_and_session_ends_on_exit() {
+        let processes = FakeProcessQuery::default();
+        let launcher = FakeLauncher::new(&processes);
+
+        let result = launch(&launcher, None);
+        assert_eq!(result, LaunchResult::Activated { pid: 1000 });
+        assert_eq!(package_processes(&processes, FAMILY).unwrap().len(), 1);
+
+        let report = wait_for_session(&processes, result.pid().unwrap(), Local::now()).unwrap();
+        assert_eq!(report.pid, 1000);
+        assert!(report.exited_normally());
+        assert!(package_processes(&processes, FAMILY).unwrap().is_empty());
+    }
+
+    #[test]
+    fn launch_reports_process_that_was_already_running() {
+        let processes = FakeProcessQuery::default();
+        let launcher = FakeLauncher::new(&processes);
+
+        launch(&launcher, None);
+        assert_eq!(launch(&launcher, None), LaunchResult::AlreadyRunning { pid: 1000 });
+    }
+
+    #[test]
+    fn uri_launch_discovers_new_process() {
+        let processes = FakeProcessQuery::default();
+        let launcher = FakeLauncher::new(&processes);
+
+        let result = launch(&launcher, Some("minecraft://"));
+        assert_eq!(result, LaunchResult::Activated { pid: 1000 });
+    }
+
+    #[test]
+    fn uri_launch_reports_running_process() {
+        let processes = FakeProcessQuery::default();
+        let launcher = FakeLauncher::new(&processes);
+
+        launch(&launcher, None);
+        let result = launch(&launcher, Some("minecraft://?addExternalServer=a|b:19132"));
+        assert_eq!(result, LaunchResult::AlreadyRunning { pid: 1000 });
+        assert_eq!(launcher.uris.borrow().as_slice(), ["minecraft://?addExternalServer=a|b:19132"]);
+    }
+
+    #[test]
+    fn uri_launch_without_process_fails() {
+        let processes = FakeProcessQuery::default();
+        let mut launcher = FakeLauncher::new(&processes);
+        launcher.silent_uri = true;
+
+        let result = launch(&launcher, Some("minecraft://"));
+        assert_eq!(result, LaunchResult::Failed("已发送 minecraft://，但没有检测到游戏进程".to_string()));
+    }
+
+    #[test]
+    fn other_packages_do_not_count_as_running() {
+        let processes = FakeProcessQuery::default();
+        let launcher = FakeLauncher::new(&processes);
+        launcher.spawn("Microsoft.MinecraftWindowsBeta_8wekyb3d8bbwe");
+
+        assert_eq!(launch(&launcher, None), LaunchResult::Activated { pid: 1001 });
+    }
+
+    #[test]
+    fn failed_launch_has_no_pid() {
+        let processes = FakeProcessQuery::default();
+        let launcher = FakeLauncher::new(&processes).failing("0x80270254 无法激活");
+
+        let result = launch(&launcher, None);
+        assert_eq!(result, LaunchResult::Failed("0x80270254 无法激活".to_string()));
+        assert_eq!(result.pid(), None);
+        assert!(processes.processes().unwrap().is_empty());
+    }
+}
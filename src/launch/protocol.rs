@@ -0,0 +1,147 @@
+use std::fmt;
+
+pub const DEFAULT_SERVER_PORT: u16 = 19132;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinecraftUri {
+    scheme: String,
+    path: String,
+    query: Vec<(String, String)>,
+}
+
+impl MinecraftUri {
+    pub fn new(scheme: &str) -> Self {
+        MinecraftUri { scheme: scheme.to_string(), path: String::new(), query: Vec::new() }
+    }
+
+    // minecraft://?addExternalServer=Name|host:port，游戏解码后按 '|' 拆分，名称里不能有 '|'
+    pub fn add_external_server(scheme: &str, name: &str, host: &str, port: u16) -> Result<Self, String> {
+        if name.is_empty() || name.contains('|') {
+            return Err(format!("无效的服务器名称: \"{}\" (不能为空或包含 '|')", name));
+        }
+        Ok(MinecraftUri::new(scheme).param("addExternalServer", &format!("{}|{}:{}", name, host, port)))
+    }
+
+    // minecraft://connect/?serverUrl=host&serverPort=port
+    pub fn connect(scheme: &str, host: &str, port: u16) -> Self {
+        MinecraftUri::new(scheme)
+            .path("connect/")
+            .param("serverUrl", host)
+            .param("serverPort", &port.to_string())
+    }
+
+    // minecraft://?Editor=true
+    pub fn editor(scheme: &str) -> Self {
+        MinecraftUri::new(scheme).param("Editor", "true")
+    }
+
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = path.trim_start_matches('/').to_string();
+        self
+    }
+
+    pub fn param(mut self, key: &str, value: &str) -> Self {
+        self.query.push((key.to_string(), value.to_string()));
+        self
+    }
+}
+
+impl fmt::Display for MinecraftUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}://{}", self.scheme, self.path)?;
+        for (i, (key, value)) in self.query.iter().enumerate() {
+            let separator = if i == 0 { '?' } else { '&' };
+            write!(f, "{}{}={}", separator, encode_query_component(key), encode_query_component(value))?;
+        }
+        Ok(())
+    }
+}
+
+// 除 RFC 3986 的非保留字符外全部转义，游戏解码后再按 '|' 和 ':' 拆分
+pub fn encode_query_component(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+// host、host:port、[IPv6]、[IPv6]:port；不带方括号的 IPv6 地址 (如 ::1) 使用默认端口
+pub fn parse_server_address(address: &str, default_port: u16) -> Result<(String, u16), String> {
+    let address = address.trim();
+    let parse_port = |port: &str| port.parse::<u16>().map_err(|_| format!("无效的端口: {}", address));
+    if let Some(rest) = address.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']').ok_or_else(|| format!("无效的服务器地址: {}", address))?;
+        if host.is_empty() {
+            return Err(format!("无效的服务器地址: {}", address));
+        }
+        return match rest {
+            "" => Ok((host.to_string(), default_port)),
+            _ => match rest.strip_prefix(':') {
+                Some(port) => Ok((host.to_string(), parse_port(port)?)),
+                None => Err(format!("无效的服务器地址: {}", address)),
+            },
+        };
+    }
+
+    match address.split_once(':') {
+        _ if address.is_empty() => Err("服务器地址不能为空".to_string()),
+        None => Ok((address.to_string(), default_port)),
+        // 多个冒号只可能是 IPv6 地址
+        Some((_, rest)) if rest.contains(':') => Ok((address.to_string(), default_port)),
+        Some(("", _)) => Err(format!("无效的服务器地址: {}", address)),
+        Some((host, port)) => Ok((host.to_string(), parse_port(port)?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_external_server_uri() {
+        let uri = MinecraftUri::add_external_server("minecraft", "My Server", "play.example.com", 19133).unwrap();
+        assert_eq!(uri.to_string(), "minecraft://?addExternalServer=My%20Server%7Cplay.example.com%3A19133");
+    }
+
+    #[test]
+    fn rejects_pipe_in_server_name() {
+        assert!(MinecraftUri::add_external_server("minecraft", "a|b", "host", 1).is_err());
+        assert!(MinecraftUri::add_external_server("minecraft", "", "host", 1).is_err());
+    }
+
+    #[test]
+    fn builds_connect_and_editor_uris() {
+        assert_eq!(MinecraftUri::connect("minecraft", "::1", 19132).to_string(), "minecraft://connect/?serverUrl=%3A%3A1&serverPort=19132");
+        assert_eq!(MinecraftUri::editor("minecraft-preview").to_string(), "minecraft-preview://?Editor=true");
+    }
+
+    #[test]
+    fn escapes_reserved_and_non_ascii_characters() {
+        assert_eq!(encode_query_component("a&b=c#d?|:/"), "a%26b%3Dc%23d%3F%7C%3A%2F");
+        assert_eq!(encode_query_component("服"), "%E6%9C%8D");
+        assert_eq!(encode_query_component("A-z_0.9~"), "A-z_0.9~");
+    }
+
+    #[test]
+    fn parses_host_and_port() {
+        assert_eq!(parse_server_address("example.com", 19132), Ok(("example.com".to_string(), 19132)));
+        assert_eq!(parse_server_address("example.com:25565", 19132), Ok(("example.com".to_string(), 25565)));
+        assert!(parse_server_address("example.com:abc", 19132).is_err());
+        assert!(parse_server_address(":19132", 19132).is_err());
+        assert!(parse_server_address("", 19132).is_err());
+    }
+
+    #[test]
+    fn parses_ipv6_addresses() {
+        assert_eq!(parse_server_address("::1", 19132), Ok(("::1".to_string(), 19132)));
+        assert_eq!(parse_server_address("[::1]", 19132), Ok(("::1".to_string(), 19132)));
+        assert_eq!(parse_server_address("[2001:db8::1]:19133", 19132), Ok(("2001:db8::1".to_string(), 19133)));
+        assert!(parse_server_address("[::1]19133", 19132).is_err());
+        assert!(parse_server_address("[::1", 19132).is_err());
+        assert!(parse_server_address("[]:1", 19132).is_err());
+    }
+}
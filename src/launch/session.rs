@@ -0,0 +1,59 @@
+use std::io;
+
+use chrono::{DateTime, Duration, Local};
+
+use crate::process::ProcessQuery;
+
+#[derive(Debug, Clone)]
+pub struct SessionReport {
+    pub pid: u32,
+    pub started_at: DateTime<Local>,
+    pub ended_at: DateTime<Local>,
+    pub exit_code: Option<u32>,
+    // 游戏在本次启动前已在运行，started_at 只是本次启动的时间
+    pub partial: bool,
+}
+
+impl SessionReport {
+    pub fn duration(&self) -> Duration {
+        self.ended_at - self.started_at
+    }
+
+    // 取不到退出码时不知道游戏是怎么结束的，不算正常退出
+    pub fn exited_normally(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+pub fn wait_for_session(query: &dyn ProcessQuery, pid: u32, started_at: DateTime<Local>) -> io::Result<SessionReport> {
+    let exit_code = query.wait_for_exit(pid)?;
+    Ok(SessionReport { pid, started_at, ended_at: Local::now(), exit_code, partial: false })
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    format!("{}小时{:02}分{:02}秒", seconds / 3600, seconds % 3600 / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(exit_code: Option<u32>) -> SessionReport {
+        let now = Local::now();
+        SessionReport { pid: 1, started_at: now, ended_at: now, exit_code, partial: false }
+    }
+
+    #[test]
+    fn only_zero_exit_code_is_normal() {
+        assert!(report(Some(0)).exited_normally());
+        assert!(!report(Some(0xC0000005)).exited_normally());
+        assert!(!report(None).exited_normally());
+    }
+
+    #[test]
+    fn formats_duration() {
+        assert_eq!(format_duration(Duration::seconds(3 * 3600 + 5 * 60 + 7)), "3小时05分07秒");
+        assert_eq!(format_duration(Duration::seconds(-5)), "0小时00分00秒");
+    }
+}
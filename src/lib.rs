@@ -1 +1,20 @@
-pub mod utils;
\ No newline at end of file
+pub mod utils;
+pub mod edition;
+pub mod manifest;
+pub mod nbt;
+pub mod version;
+pub mod dependency;
+pub mod validation;
+pub mod diagnostics;
+pub mod process;
+pub mod launch;
+pub mod hooks;
+pub mod config;
+pub mod content;
+pub mod history;
+pub mod profile;
+pub mod identity;
+pub mod shortcut;
+pub mod server;
+#[cfg(windows)]
+pub mod deploy;
\ No newline at end of file
@@ -1,16 +1,46 @@
 use std::fs::{self, File};
-use std::io::{self, Read};
+use std::io;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::time::Duration;
 
 
+use chrono::{DateTime, Local};
 use tokio::main;
-use windows::core::{Error, HSTRING, Result};
-use windows::Foundation::{IAsyncOperationWithProgress, Uri};
-use windows::Management::Deployment::{DeploymentOptions, DeploymentProgress, DeploymentResult, PackageManager, RemovalOptions};
-use xml::reader::{EventReader, XmlEvent};
 use zip::ZipArchive;
-use BetterBedrockLauncherCore::{debug, error, info};
+use BetterBedrockLauncherCore::content::export::{default_world_archive_name, export_pack, export_world};
+use BetterBedrockLauncherCore::content::compat::{check_compatibility, game_version_from_package};
+use BetterBedrockLauncherCore::content::{all_installed_packs, com_mojang_dir, edition_com_mojang_dir};
+use BetterBedrockLauncherCore::content::import::{import_content, ImportAction};
+use BetterBedrockLauncherCore::content::sync::{apply_sync, backup_destination, default_sync_backup_dir, plan_sync, ConflictPolicy, SyncAction, SyncSelection};
+use BetterBedrockLauncherCore::content::packs::{check_world_packs, disable_pack, enable_pack, installed_missing_dependencies, MissingDependency};
+use BetterBedrockLauncherCore::content::level::{apply_level_setting, parse_assignment, save_level_dat};
+use BetterBedrockLauncherCore::content::world::{find_world, game_mode_name, list_worlds};
+use BetterBedrockLauncherCore::dependency::{cached_dependency_paths, default_dependency_cache_dir, missing_dependencies, scan_dependency_cache, DependencyStatus};
+use BetterBedrockLauncherCore::config::Config;
+use BetterBedrockLauncherCore::edition::{find_edition, package_data_dir, package_family_name_for, Edition};
+use BetterBedrockLauncherCore::identity::apply_custom_identity;
+use BetterBedrockLauncherCore::history::{append_record, default_history_path, export_history, load_history, recent_since, summarize, SessionRecord};
+use BetterBedrockLauncherCore::hooks::{run_hooks, HookConfig, HookContext, HookStage};
+use BetterBedrockLauncherCore::launch::capture::{bundle_crash_report, default_crash_report_dir, is_crash, LogCapture};
+use BetterBedrockLauncherCore::launch::protocol::{parse_server_address, MinecraftUri, DEFAULT_SERVER_PORT};
+use BetterBedrockLauncherCore::launch::session::{format_duration, wait_for_session, SessionReport};
+use BetterBedrockLauncherCore::launch::{launch_package, package_family_name_of, ActivationManagerLauncher, LaunchResult};
+use BetterBedrockLauncherCore::manifest::AppxManifest;
+use BetterBedrockLauncherCore::nbt::LevelDat;
+use BetterBedrockLauncherCore::process::tuning::{apply_tuning, parse_affinity_mask};
+use BetterBedrockLauncherCore::process::{resolve_running, PriorityClass, ProcessTuning, RunningPolicy, RunningResolution, WindowsProcessQuery};
+use BetterBedrockLauncherCore::profile::LaunchProfile;
+use BetterBedrockLauncherCore::server::install::{append_changelog, install_server, update_server, FileChange};
+use BetterBedrockLauncherCore::server::properties::{find_spec, unknown_key_message, PropertyChange, ServerProperties};
+use BetterBedrockLauncherCore::server::run::{default_server_backup_dir, run_server, send_control_command, RunOptions};
+use BetterBedrockLauncherCore::server::{list_servers, server_dir, DEFAULT_SERVER_NAME};
+use BetterBedrockLauncherCore::shortcut::{create_shortcut, default_output_dir, default_shortcut_name, prepare_icon, Shortcut, ShortcutKind};
+use BetterBedrockLauncherCore::utils::archive::zip_dir;
+use BetterBedrockLauncherCore::utils::files::format_size;
+use BetterBedrockLauncherCore::version::PackageVersion;
+use BetterBedrockLauncherCore::validation::{has_errors, validate_package, Severity, ValidationContext};
+use BetterBedrockLauncherCore::utils::console::confirm;
+use BetterBedrockLauncherCore::{debug, deploy, error, info, warning};
 
 
 fn create_parent_directories(file_path: &Path) -> io::Result<()> {
@@ -63,189 +93,603 @@ fn extract_zip(file: File, destination_path: &str, force_replace: bool, delete_s
     Ok(())
 }
 
-async fn register_appx_package_async(appx_manifest_path: &str) -> Result<DeploymentResult> {
-    info!("注册 APPX：{}", appx_manifest_path);
-    let package_manager = PackageManager::new()?;
-    let relative_uri = HSTRING::from(appx_manifest_path);
-    let uri = Uri::CreateUri(&relative_uri)?;
+fn resolve_dependencies(manifest: &AppxManifest, dependency_cache_dir: &Path) -> Option<Vec<PathBuf>> {
+    if manifest.dependencies.is_empty() {
+        return Some(Vec::new());
+    }
 
-    let result_async: IAsyncOperationWithProgress<DeploymentResult, DeploymentProgress> =
-        package_manager.RegisterPackageAsync(&uri, None, DeploymentOptions::DevelopmentMode)?;
+    let cache = scan_dependency_cache(dependency_cache_dir).unwrap_or_else(|err| {
+        warning!("无法读取依赖缓存目录 {}: {}", dependency_cache_dir.display(), err);
+        Vec::new()
+    });
+    let checks = match deploy::check_dependencies(manifest, &cache) {
+        Ok(checks) => checks,
+        Err(err) => {
+            error!("检查依赖失败: {:?}", err);
+            return None;
+        }
+    };
 
-    let result: DeploymentResult = result_async.get()?;
+    for check in &checks {
+        let dependency = &check.dependency;
+        match &check.status {
+            DependencyStatus::Installed(version) => debug!("依赖 {} 已安装: {}", dependency.name, version),
+            DependencyStatus::Cached(path) => info!("依赖 {} 未安装，将从缓存安装: {}", dependency.name, path.display()),
+            DependencyStatus::Missing => error!("缺少依赖 {} (MinVersion {})，请将其放入依赖缓存目录: {}", dependency.name, dependency.min_version, dependency_cache_dir.display()),
+        }
+    }
 
-    info!("APPX 注册成功");
-    Ok(result)
+    if !missing_dependencies(&checks).is_empty() {
+        return None;
+    }
+    Some(cached_dependency_paths(&checks))
 }
 
-async fn handle_remove_package_register_start(packagefullname: &str, manifest_path: &str, _package_path: &str, auto_start: bool, edition: &str) {
-    let hstring_packagefullname = HSTRING::from(packagefullname);
-    let package_manager = PackageManager::new().expect("无法创建 PackageManager");
+fn report_validation(package_dir: &Path, manifest: &AppxManifest) -> bool {
+    let issues = validate_package(package_dir, manifest, &ValidationContext::current());
+    for issue in &issues {
+        match issue.severity {
+            Severity::Warning => warning!("{}", issue.message),
+            Severity::Error => error!("{}", issue.message),
+        }
+    }
+    !has_errors(&issues)
+}
 
-    match package_manager.RemovePackageWithOptionsAsync(&hstring_packagefullname, RemovalOptions::PreserveApplicationData) {
-        Ok(async_op) => {
-            let async_result = async_op.get().expect("等待异步操作失败");
+const RUNNING_TIMEOUT: Duration = Duration::from_secs(120);
+const RUNNING_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
-            if let Ok(error_text) = async_result.ErrorText() {
-                error!("移除包失败。错误文本: {:?}", error_text);
-            }
+struct LaunchOptions {
+    add_server: Option<(String, String)>,
+    connect: Option<String>,
+    editor: bool,
+}
 
-            if let Ok(extended_error_code) = async_result.ExtendedErrorCode() {
-                error!("扩展错误代码: {:?}", extended_error_code);
-            }
+impl LaunchOptions {
+    fn uri(&self, scheme: &str) -> Result<Option<MinecraftUri>, String> {
+        if let Some((name, address)) = &self.add_server {
+            let (host, port) = parse_server_address(address, DEFAULT_SERVER_PORT)?;
+            return MinecraftUri::add_external_server(scheme, name, &host, port).map(Some);
+        }
+        if let Some(address) = &self.connect {
+            let (host, port) = parse_server_address(address, DEFAULT_SERVER_PORT)?;
+            return Ok(Some(MinecraftUri::connect(scheme, &host, port)));
+        }
+        if self.editor {
+            return Ok(Some(MinecraftUri::editor(scheme)));
+        }
+        Ok(None)
+    }
+}
 
-            if let Ok(is_registered) = async_result.IsRegistered() {
-                if is_registered {
-                    info!("包已成功移除");
+struct WaitOptions {
+    wait: bool,
+    backup_dir: Option<PathBuf>,
+    restore: bool,
+    crash_dir: PathBuf,
+    profile: Option<String>,
+}
 
-                    match register_appx_package_async(manifest_path).await {
-                        Ok(result) => {
-                            info!("Appx 包成功注册: {:?}", result);
-                        },
-                        Err(err) => {
-                            error!("注册 Appx 包失败: {}", err);
-                        }
-                    }
+struct RegisterOptions {
+    dependency_cache_dir: PathBuf,
+    running_policy: RunningPolicy,
+    auto_start: bool,
+    launch: LaunchOptions,
+    wait: WaitOptions,
+    hooks: Vec<HookConfig>,
+    editions: Vec<Edition>,
+    tuning: ProcessTuning,
+    block_incompatible: bool,
+}
 
-                    if auto_start {
-                        match edition {
-                            "Microsoft.MinecraftUWP" => start_minecraft_release(),
-                            "Microsoft.MinecraftWindowsBeta" => start_minecraft_beta(),
-                            "Microsoft.MinecraftEducationEdition" => start_minecraft_education(),
-                            "Microsoft.MinecraftEducationPreview" => start_minecraft_education_preview(),
-                            _ => Ok(()),
-                        }.unwrap_or_else(|err| error!("启动失败: {}", err));
-                    }
-                } else {
-                    error!("包移除失败");
-                }
+fn option_value<'a>(args: &'a [String], name: &str) -> Option<&'a String> {
+    args.iter().position(|arg| arg == name).and_then(|i| args.get(i + 1))
+}
+
+fn parse_launch_options(args: &[String]) -> LaunchOptions {
+    let add_server = args.iter()
+        .position(|arg| arg == "--add-server")
+        .and_then(|i| Some((args.get(i + 1)?.clone(), args.get(i + 2)?.clone())));
+    LaunchOptions {
+        add_server,
+        connect: option_value(args, "--connect").cloned(),
+        editor: args.contains(&"--editor".to_string()),
+    }
+}
+
+// 自定义包没有对应版本时按正式版的协议处理
+fn protocol_scheme(editions: &[Edition], identity_name: &str) -> String {
+    find_edition(editions, identity_name)
+        .map(|edition| edition.protocol_scheme)
+        .unwrap_or_else(|| "minecraft".to_string())
+}
+
+fn parse_wait_options(args: &[String]) -> WaitOptions {
+    WaitOptions {
+        wait: args.contains(&"--wait".to_string()),
+        backup_dir: option_value(args, "--backup-dir").map(PathBuf::from),
+        restore: args.contains(&"--restore".to_string()),
+        crash_dir: option_value(args, "--crash-dir").map(PathBuf::from).unwrap_or_else(default_crash_report_dir),
+        profile: option_value(args, "--profile").cloned(),
+    }
+}
+
+fn parse_running_policy(args: &[String]) -> Option<RunningPolicy> {
+    match option_value(args, "--running").map(|value| value.parse::<RunningPolicy>()) {
+        Some(Ok(policy)) => Some(policy),
+        Some(Err(err)) => {
+            error!("{}", err);
+            None
+        }
+        None => Some(RunningPolicy::default()),
+    }
+}
+
+// 命令行的 --priority/--affinity 覆盖配置中的同名 profile，并保存回配置
+fn parse_process_tuning(args: &[String], config: &Config) -> Option<ProcessTuning> {
+    let profile_name = option_value(args, "--profile");
+    let mut profile = profile_name
+        .and_then(|name| config.profile(name).cloned())
+        .unwrap_or_else(|| LaunchProfile::new(profile_name.map(|name| name.as_str()).unwrap_or_default()));
+
+    let priority = option_value(args, "--priority");
+    let affinity = option_value(args, "--affinity");
+    if let Some(priority) = priority {
+        match priority.parse::<PriorityClass>() {
+            Ok(priority) => profile.priority = Some(priority),
+            Err(err) => {
+                error!("{}", err);
+                return None;
             }
-        },
+        }
+    }
+    if let Some(affinity) = affinity {
+        if let Err(err) = parse_affinity_mask(affinity) {
+            error!("{}", err);
+            return None;
+        }
+        profile.affinity = Some(affinity.clone());
+    }
+
+    let tuning = match profile.tuning() {
+        Ok(tuning) => tuning,
         Err(err) => {
-            error!("调用 RemovePackageAsync 出错: {:?}", err);
+            error!("配置 {} 无效: {}", profile.name, err);
+            return None;
+        }
+    };
+    if profile_name.is_some() && (priority.is_some() || affinity.is_some()) {
+        let name = profile.name.clone();
+        // 配置文件读取失败时不写回，以免用默认配置覆盖用户原有的配置
+        match Config::load() {
+            Ok(mut saved) => {
+                saved.set_profile(profile);
+                match saved.save() {
+                    Ok(_) => info!("已保存到配置: {}", name),
+                    Err(err) => warning!("无法保存配置 {}: {}", name, err),
+                }
+            }
+            Err(err) => error!("无法读取配置文件，未保存配置 {}: {}", name, err),
         }
     }
+    Some(tuning)
 }
 
+// 返回 None 表示游戏仍在运行，不能继续
+fn stop_running_game(package_family_name: &str, policy: RunningPolicy) -> Option<RunningResolution> {
+    match resolve_running(&WindowsProcessQuery, package_family_name, policy, RUNNING_TIMEOUT, RUNNING_POLL_INTERVAL) {
+        Ok(RunningResolution::Refused(processes)) => {
+            for process in &processes {
+                error!("游戏正在运行: {} (PID {})", process.name, process.pid);
+            }
+            error!("请先关闭游戏，或使用 --running wait|close|force");
+            None
+        }
+        Ok(resolution) => {
+            if resolution != RunningResolution::NotRunning {
+                info!("游戏已关闭");
+            }
+            Some(resolution)
+        }
+        Err(err) => {
+            error!("检测运行中的进程失败: {}", err);
+            None
+        }
+    }
+}
 
-async fn register_start(manifest_path: &str, auto_start: bool, edition: &str) {
-    match register_appx_package_async(manifest_path).await {
+async fn register(manifest_path: &str, dependency_paths: &[PathBuf], force_shutdown: bool) -> bool {
+    match deploy::register_package(manifest_path, dependency_paths, force_shutdown).await {
         Ok(result) => {
             info!("Appx 包成功注册: {:?}", result);
-        },
+            true
+        }
         Err(err) => {
             error!("注册 Appx 包失败: {}", err);
+            false
         }
     }
+}
 
-    if auto_start {
-        match edition {
-            "Microsoft.MinecraftUWP" => start_minecraft_release(),
-            "Microsoft.MinecraftWindowsBeta" => start_minecraft_beta(),
-            "Microsoft.MinecraftEducationEdition" => start_minecraft_education(),
-            "Microsoft.MinecraftEducationPreview" => start_minecraft_education_preview(),
-            _ => Ok(()),
-        }.unwrap_or_else(|err| error!("启动失败: {}", err));
+fn launch_app(app_user_model_id: &str, uri: Option<&MinecraftUri>, tuning: &ProcessTuning) -> LaunchResult {
+    match uri {
+        Some(uri) => info!("启动: {} ({})", app_user_model_id, uri),
+        None => info!("启动: {}", app_user_model_id),
+    }
+    let uri = uri.map(|uri| uri.to_string());
+    let result = launch_package(&ActivationManagerLauncher, &WindowsProcessQuery, app_user_model_id, uri.as_deref());
+    match &result {
+        LaunchResult::Activated { pid } => info!("游戏已启动，PID: {}", pid),
+        LaunchResult::AlreadyRunning { pid } => info!("游戏已在运行，PID: {}", pid),
+        LaunchResult::Failed(err) => error!("启动失败: {}", err),
+    }
+
+    if let Some(pid) = result.pid().filter(|_| !tuning.is_empty()) {
+        let errors = apply_tuning(&WindowsProcessQuery, pid, tuning);
+        for err in &errors {
+            warning!("{}", err);
+        }
+        if errors.is_empty() {
+            info!(
+                "已设置进程优先级: {}，亲和性: {}",
+                tuning.priority.map(|priority| priority.name()).unwrap_or("不变"),
+                tuning.affinity.map(|affinity| format!("0x{:X}", affinity)).unwrap_or_else(|| "不变".to_string()),
+            );
+        }
     }
+    result
 }
 
-fn start_minecraft_release() -> io::Result<()> {
-    let output = Command::new("explorer.exe")
-        .arg("shell:appsFolder\\Microsoft.MinecraftUWP_8wekyb3d8bbwe!App")
-        .output()?;
+fn package_app_user_model_id(manifest: &AppxManifest) -> Option<String> {
+    let Some(app_id) = manifest.app_id() else {
+        error!("清单中没有 Application，无法启动");
+        return None;
+    };
 
-    info!("{:?}", output);
-    Ok(())
+    let identity = &manifest.identity;
+    let package = match deploy::find_package_by_identity(&identity.name, &identity.publisher) {
+        Ok(Some(package)) => package,
+        Ok(None) => {
+            error!("{} 没有注册，无法启动", identity.name);
+            return None;
+        }
+        Err(err) => {
+            error!("查询包失败: {:?}", err);
+            return None;
+        }
+    };
+
+    match deploy::package_family_name(&package) {
+        Ok(package_family_name) => Some(format!("{}!{}", package_family_name, app_id)),
+        Err(err) => {
+            error!("无法获取包家族名称: {:?}", err);
+            None
+        }
+    }
 }
 
-fn start_minecraft_beta() -> io::Result<()> {
-    let output = Command::new("explorer.exe")
-        .arg("shell:appsFolder\\Microsoft.MinecraftWindowsBeta_8wekyb3d8bbwe!App")
-        .output()?;
+// 成功时把新登记的版本加入 editions
+fn rewrite_package_identity(package_dir: &Path, identity_name: &str, display_name: Option<&String>, editions: &mut Vec<Edition>) -> bool {
+    match apply_custom_identity(package_dir, identity_name, display_name.map(|name| name.as_str()), editions) {
+        Ok(edition) => {
+            info!("包名已改写为: {}", edition.identity_name);
+            info!("已登记自定义版本，可通过 {} 启动", edition.app_user_model_id());
+            editions.retain(|existing| !existing.identity_name.eq_ignore_ascii_case(&edition.identity_name));
+            editions.push(edition);
+            true
+        }
+        Err(err) => {
+            error!("改写包名失败: {}", err);
+            false
+        }
+    }
+}
 
-    info!("{:?}", output);
-    Ok(())
+fn hook_context(identity_name: &str, version: &str, package_family_name: &str, install_path: &Path) -> HookContext {
+    HookContext {
+        edition: identity_name.to_string(),
+        version: version.to_string(),
+        package_family_name: package_family_name.to_string(),
+        install_path: install_path.display().to_string(),
+        data_path: package_data_dir(package_family_name).map(|path| path.display().to_string()).unwrap_or_default(),
+    }
 }
 
-fn start_minecraft_education() -> io::Result<()> {
-    let output = Command::new("explorer.exe")
-        .arg("shell:appsFolder\\Microsoft.MinecraftEducationEdition_8wekyb3d8bbwe!Microsoft.MinecraftEducationEdition")
-        .output()?;
+fn installed_hook_context(edition: &Edition) -> HookContext {
+    let package_family_name = edition.package_family_name();
+    let package = deploy::find_package(&package_family_name).ok().flatten();
+    let version = package.as_ref().and_then(|package| deploy::package_version(package).ok()).unwrap_or_default();
+    let install_path = package.as_ref().and_then(|package| deploy::package_installed_path(package).ok()).unwrap_or_default();
+    hook_context(&edition.identity_name, &version, &package_family_name, &install_path)
+}
 
-    info!("{:?}", output);
-    Ok(())
+// 世界或包比游戏版本新时给出警告，block 时取消启动
+fn check_launch_compatibility(context: &HookContext, block: bool) -> bool {
+    let Ok(package_version) = context.version.parse::<PackageVersion>() else {
+        return true;
+    };
+    let Some(data_dir) = package_data_dir(&context.package_family_name) else {
+        return true;
+    };
+    let game_version = game_version_from_package(&package_version);
+    let issues = check_compatibility(&com_mojang_dir(&data_dir), &game_version);
+    for issue in &issues {
+        match issue.severity {
+            Severity::Warning => warning!("{}", issue.message),
+            Severity::Error => error!("{}", issue.message),
+        }
+    }
+    if !issues.is_empty() && block {
+        error!("有 {} 项内容与游戏版本 {} 不兼容，已取消启动", issues.len(), game_version);
+        return false;
+    }
+    true
 }
 
-fn start_minecraft_education_preview() -> io::Result<()> {
-    let output = Command::new("explorer.exe")
-        .arg("shell:appsFolder\\Microsoft.MinecraftEducationPreview_8wekyb3d8bbwe!Microsoft.MinecraftEducationEdition")
-        .output()?;
+fn backup_package_data(package_family_name: &str, backup_dir: &Path) {
+    let Some(data_dir) = package_data_dir(package_family_name).filter(|data_dir| data_dir.exists()) else {
+        info!("没有可备份的应用数据");
+        return;
+    };
 
-    info!("{:?}", output);
-    Ok(())
+    let archive_path = backup_dir.join(format!("{}-{}.zip", package_family_name, Local::now().format("%Y%m%d-%H%M%S")));
+    info!("备份应用数据到: {}", archive_path.display());
+    match zip_dir(&data_dir, &archive_path) {
+        Ok(_) => info!("应用数据备份成功"),
+        Err(err) => error!("备份应用数据失败: {:?}", err),
+    }
 }
 
-fn get_package_info(app_user_model_id: &str) -> Result<Option<(String, String, String)>> {
-    match windows::ApplicationModel::AppInfo::GetFromAppUserModelId(&app_user_model_id.into()) {
-        Ok(app_info) => {
-            match app_info.Package() {
-                Ok(package) => {
-                    let version = if let Ok(version) = package.Id().and_then(|id| id.Version()) {
-                        Some(format!("{}.{}.{}.{}", version.Major, version.Minor, version.Build, version.Revision))
-                    } else {
-                        None
-                    };
+// 启动前记录日志目录的状态，--wait 模式下才需要
+fn start_log_capture(app_user_model_id: &str, options: &WaitOptions) -> Option<LogCapture> {
+    options.wait.then(|| LogCapture::start(package_family_name_of(app_user_model_id))).flatten()
+}
 
-                    let package_family_name = if let Ok(package_family_name) = package.Id().and_then(|id| id.FamilyName()) {
-                        Some(package_family_name)
-                    } else {
-                        return Err(Error::from(io::Error::new(io::ErrorKind::Other, "无法获取包家族名称")));
-                    };
+fn record_session(report: &SessionReport, context: &HookContext, profile: Option<&str>) {
+    let record = SessionRecord::from_report(report, &context.edition, &context.version, &context.package_family_name, profile);
+    if let Err(err) = append_record(&default_history_path(), &record) {
+        warning!("无法记录游玩时长: {}", err);
+    }
+}
 
-                    let package_full_name = if let Ok(package_full_name) = package.Id().and_then(|id| id.FullName()) {
-                        Some(package_full_name.to_string())
-                    } else {
-                        return Err(Error::from(io::Error::new(io::ErrorKind::Other, "无法获取包全名")));
-                    };
+fn wait_for_game(app_user_model_id: &str, result: &LaunchResult, started_at: DateTime<Local>, options: &WaitOptions, capture: Option<LogCapture>, context: &HookContext) -> Option<SessionReport> {
+    let pid = result.pid()?;
+    info!("等待游戏退出 (PID {})", pid);
+
+    let tail = capture.as_ref().map(|capture| capture.tail());
+    let report = wait_for_session(&WindowsProcessQuery, pid, started_at);
+    if let Some(tail) = tail {
+        tail.stop();
+    }
+    let report = match report {
+        Ok(report) => SessionReport { partial: matches!(result, LaunchResult::AlreadyRunning { .. }), ..report },
+        Err(err) => {
+            error!("等待游戏退出失败: {}", err);
+            return None;
+        }
+    };
+
+    info!("游戏已退出，本次游玩时长: {}", format_duration(report.duration()));
+    if report.partial {
+        warning!("游戏在启动前已在运行，时长只从本次启动算起，记录已标记为不完整");
+    }
+    match report.exit_code {
+        Some(exit_code) if !report.exited_normally() => warning!("退出码: {} (0x{:08X})", exit_code, exit_code),
+        Some(exit_code) => info!("退出码: {}", exit_code),
+        None => warning!("无法获取退出码，不能确定游戏是否正常退出"),
+    }
+    record_session(&report, context, options.profile.as_deref());
 
-                    Ok(Some((version.unwrap(), package_family_name.unwrap().to_string(), package_full_name.unwrap())))
+    if let Some(capture) = &capture {
+        if is_crash(&report, &capture.new_crash_files(report.pid)) {
+            warning!("游戏可能已崩溃，正在收集日志");
+            match bundle_crash_report(&options.crash_dir, capture, &report) {
+                Ok(report_dir) => warning!("崩溃报告已保存到: {}", report_dir.display()),
+                Err(err) => error!("无法保存崩溃报告: {}", err),
+            }
+        }
+    }
+
+    if let Some(backup_dir) = &options.backup_dir {
+        backup_package_data(package_family_name_of(app_user_model_id), backup_dir);
+    }
+    Some(report)
+}
+
+async fn restore_previous_version(manifest: &AppxManifest, previous_manifest_path: &Path) {
+    info!("恢复之前的版本: {}", previous_manifest_path.display());
+    let identity = &manifest.identity;
+    match deploy::find_package_by_identity(&identity.name, &identity.publisher) {
+        Ok(Some(package)) => {
+            let package_full_name = match deploy::package_full_name(&package) {
+                Ok(package_full_name) => package_full_name,
+                Err(err) => {
+                    error!("无法获取包全名: {:?}", err);
+                    return;
                 }
-                Err(err) => Err(err.into()),
+            };
+            if let Err(err) = deploy::remove_package(&package_full_name, true).await {
+                error!("移除包失败: {}", err);
+                return;
             }
         }
-        Err(err) => Err(err.into()),
+        Ok(None) => {}
+        Err(err) => {
+            error!("查询包失败: {:?}", err);
+            return;
+        }
     }
+    register(&previous_manifest_path.to_string_lossy(), &[], false).await;
 }
 
 #[main]
 async fn main() {
     let args: Vec<String> = std::env::args().collect();
 
+    let config = Config::load().unwrap_or_else(|err| {
+        warning!("无法读取配置文件，使用默认配置: {}", err);
+        Config::default()
+    });
+    // 只在这里读取一次配置，改写包名后新登记的版本也加入这里
+    let mut editions = config.editions();
+
     let command = &args[1];
     match command.to_lowercase().as_str() {
         "help" => {
             println!("用法:");
-            println!("  unpack <source_path> <destination_path> [-f] [-dsign] [-dappx]");
+            println!("  unpack <source_path> <destination_path> [-f] [-dsign] [-dappx] [--identity <name>] [--display-name <name>]");
             println!("    解压指定的 appx 文件。");
             println!("    <source_path>: appx 文件的路径。");
             println!("    <destination_path>: 解压内容的目标目录。");
             println!("    -f: 强制替换已存在的文件。");
             println!("    -dsign: 解压后删除签名文件。");
             println!("    -dappx: 解压后删除源 appx 文件。");
+            println!("    --identity: 将清单中的包名改写为自定义包名，以便多个版本同时注册和运行。");
+            println!("    --display-name: 改写后显示的名称，默认同包名。");
             println!("    例子: unpack c:/p/mc.appx d:/a -f -dsign -dappx");
             println!();
-            println!("  regpack <package_path> [-start]");
-            println!("    注册指定路径的 appx 包。");
+            println!("  regpack <package_path> [-start] [-deps <dependency_dir>] [--running <policy>] [--wait] [--backup-dir <dir>] [--restore] [--crash-dir <dir>] [--profile <name>] [--priority <class>] [--affinity <mask>] [--block-incompatible]");
+            println!("    注册指定路径的 appx 包，支持任意 Appx/MSIX 包。");
             println!("    <package_path>: 包含 AppxManifest.xml 的目录路径。");
             println!("    -start: 注册后自动启动应用。");
+            println!("    -deps: 依赖缓存目录，缺少的框架包 (如 VCLibs) 从这里安装，默认为程序目录下的 dependencies。");
+            println!("    --running: 需要替换的版本正在运行时的处理方式: refuse (默认，拒绝), wait (等待退出), close (请求关闭，无法关闭或超时后强制结束), force (强制结束)。");
+            println!("    --wait: 启动后等待游戏退出，并报告游玩时长和退出码。");
+            println!("    --backup-dir: 游戏退出后将应用数据备份到该目录。");
+            println!("    --restore: 游戏正常退出后恢复被替换前的版本，只支持开发模式注册的版本 (商店安装的目录会随包删除)。");
+            println!("    --profile: 使用 config.json 中的同名配置，游玩时长也按配置名称记录。");
+            println!("    --priority: 游戏进程优先级: idle, below_normal, normal, above_normal, high, realtime。");
+            println!("    --affinity: 游戏进程的 CPU 亲和性，十六进制掩码 (0xF) 或 CPU 编号列表 (0-3,6)。");
+            println!("      与 --profile 同时使用时保存到该配置，之后只需 --profile 即可。");
+            println!("    --crash-dir: 等待期间会把内容日志 (ContentLog) 写入本程序日志，游戏崩溃时将日志和转储文件收集到该目录，默认为程序目录下的 crash_reports。");
+            println!("    --identity, --display-name: 注册前改写包名，同 unpack。");
+            println!("    启动前会检查被更新版本打开过的世界和 min_engine_version 更高的包并给出警告，--block-incompatible 时发现问题就取消启动。");
             println!("    例子: regpack D:/Downloads/MC -start");
             println!();
+            println!("  launch <edition> [--wait] [--backup-dir <dir>] [--crash-dir <dir>] [--profile <name>] [--priority <class>] [--affinity <mask>] [--block-incompatible] [--add-server <name> <host[:port]> | --connect <host[:port]> | --editor]");
+            println!("    启动已注册的版本，--wait、--backup-dir、--crash-dir、--profile、--priority、--affinity 和 --block-incompatible 含义同 regpack。");
+            println!("    --add-server: 启动并添加外部服务器 (minecraft://?addExternalServer=...)。");
+            println!("    --connect: 启动并直接连接服务器，默认端口 19132。");
+            println!("    地址可以是 host、host:port 或 IPv6 的 [addr]:port，不带端口的 IPv6 地址 (如 ::1) 使用默认端口；服务器名称不能包含 '|'。");
+            println!("    --editor: 以编辑器模式启动。");
+            println!("    regpack -start 同样支持这三个参数。");
+            println!("    例子: launch release --wait --backup-dir d:/backup");
+            println!();
+            println!("  check <package_path>");
+            println!("    检查解压后的包能否在本机注册（架构、系统版本、签名文件、可执行文件）。");
+            println!("    例子: check D:/Downloads/MC");
+            println!();
+            println!("  unregister <edition> [--export <archive>] [--wipe] [--yes] [--running <policy>]");
+            println!("    移除指定版本的注册，默认保留应用数据。");
+            println!("    <edition>: 包名或别名 (release, beta, education, education-preview)。");
+            println!("    --export: 移除前将应用数据导出到备份压缩包，随后删除数据。");
+            println!("    --wipe: 同时删除应用数据。");
+            println!("    --yes: 跳过确认。");
+            println!("    --running: 游戏正在运行时的处理方式，同 regpack。");
+            println!("    例子: unregister beta --export d:/backup/beta.zip");
+            println!();
+            println!("  shortcut create <package_path> [--name <name>] [--type <lnk|url|script>] [--output <dir>]");
+            println!("    为解压好的版本创建快捷方式，打开时注册 (切换到) 该版本并启动，图标取自包内的 Assets。");
+            println!("    --name: 快捷方式名称，默认如 \"Minecraft 1.20.8001.0 release\"。");
+            println!("    --type: lnk (Windows 默认), url, script (其他系统默认)。");
+            println!("    --output: 保存目录，默认为桌面。");
+            println!("    例子: shortcut create D:/MC/1.20.80 --name \"1.20.80 release\"");
+            println!();
+            println!("  import <file> [--edition <edition>] [--force]");
+            println!("    导入 .mcworld .mcpack .mcaddon .mctemplate 到指定版本的 com.mojang 目录，默认为 release。");
+            println!("    已安装相同 UUID 的包时，只有版本更新才会替换，--force 强制替换。");
+            println!("    例子: import d:/downloads/addon.mcaddon --edition beta");
+            println!();
+            println!("  worlds list [--edition <edition>]");
+            println!("    列出指定版本的世界: 目录 ID、名称、游戏模式、最后游玩时间、最后打开的游戏版本、大小和启用的实验性玩法。");
+            println!("    例子: worlds list --edition beta");
+            println!();
+            println!("  worlds export <id> [--output <file>] [--with-packs] [--edition <edition>]");
+            println!("    将世界导出为 .mcworld，默认以世界名称命名。");
+            println!("    --with-packs: 同时打包世界启用的资源包和行为包，导入时无需另外安装。");
+            println!();
+            println!("  worlds set <id> <key=value>... [--edition <edition>]");
+            println!("    修改 level.dat，写入前自动备份为 level.dat.<时间>.bak，其它字段原样保留。");
+            println!("    gamemode: survival, creative, adventure, spectator, default 或数字。");
+            println!("    cheats: true/false。experiments.<名称>: true/false，开关实验性玩法。");
+            println!("    MinimumCompatibleClientVersion: 如 1.20.80。其它已有字段按原类型写入，嵌套字段用 . 分隔。");
+            println!("    例子: worlds set 2dWxZeyXAAA= gamemode=creative cheats=true experiments.gametest=true");
+            println!();
+            println!("  packs list [--edition <edition>]");
+            println!("    列出已安装的资源包、行为包和皮肤包，并提示缺少的依赖。");
+            println!();
+            println!("  packs enable <uuid> --world <id> [--force] [--edition <edition>]");
+            println!("    在世界中启用包，并一起启用 manifest.json 中声明的依赖 (写入 world_resource_packs.json / world_behavior_packs.json)。");
+            println!("    缺少依赖或依赖版本过低时不会启用，--force 忽略。");
+            println!();
+            println!("  packs disable <uuid> --world <id> [--edition <edition>]");
+            println!("    在世界中停用包，仍依赖它的包会给出提示。");
+            println!();
+            println!("  packs check --world <id> [--edition <edition>]");
+            println!("    检查世界启用的包是否已安装、依赖是否完整并已启用。");
+            println!();
+            println!("  packs export <uuid> [--output <file>] [--edition <edition>]");
+            println!("    将包导出为 .mcpack，默认以包名命名。");
+            println!();
+            println!("  sync <from> <to> [--worlds <id,id|all>] [--packs <uuid,uuid|all>] [--conflict <policy>] [--dry-run] [--backup-dir <dir>]");
+            println!("    在两个版本之间复制世界和包，<from> <to> 为版本名或 com.mojang 目录。");
+            println!("    --conflict: 目标已存在时的处理方式: skip (默认，跳过), overwrite (覆盖), newer (世界比最后游玩时间、包比版本号，源更新才覆盖), rename (世界另存为新 ID，不能与 --packs 同时使用)。");
+            println!("    --dry-run: 只列出将要进行的操作。");
+            println!("    --backup-dir: 覆盖前把目标中被覆盖的内容打包到该目录，默认为程序目录下的 backups。");
+            println!("    例子: sync release preview --worlds 2dWxZeyXAAA= --packs all --conflict newer");
+            println!();
+            println!("  server install <zip> [--name <name>]");
+            println!("    将 Bedrock Dedicated Server 压缩包 (Windows 或 Linux 版) 安装到程序目录下的 servers/<name>，默认名称为 default。");
+            println!();
+            println!("  server update <zip> [--name <name>]");
+            println!("    原地更新服务器，保留 server.properties、allowlist.json、permissions.json、worlds、config 和自定义的包。");
+            println!("    保留、替换和新增的文件记录在服务器目录下的 update.log。更新前请先停止服务器。");
+            println!();
+            println!("  server list");
+            println!("    列出已安装的服务器和版本。");
+            println!();
+            println!("  server run [--name <name>] [--control-port <port>] [--backup-interval <minutes>] [--backup-dir <dir>] [--keep <n>]");
+            println!("    运行服务器并转发控制台，输入的内容发给服务器；stop 正常停止，backup 立即备份。");
+            println!("    --control-port: 在 127.0.0.1 上监听该端口接收命令，第一行为 bbl_control.token 中的令牌，之后每行一条，可用 server send 发送。");
+            println!("      注意: 端口没有认证，本机的任何用户或程序都能连接并以服务器控制台的权限执行命令 (包括 op)，只在信任的机器上使用。");
+            println!("    --backup-interval: 定时用 save hold / save query / save resume 备份世界，默认备份到程序目录下的 backups/server-<name>。");
+            println!("    --keep: 只保留最新的几份备份，默认全部保留；只会删除本程序写出的 <时间>.zip，目录中的其他文件不受影响。");
+            println!();
+            println!("  server send <command> [--name <name>]");
+            println!("    通过控制端口向运行中的服务器发送命令，如 server send say hello。");
+            println!();
+            println!("  server config get [key] [--name <name>]");
+            println!("    显示 server.properties 中的字段，指定字段时同时显示类型、可选值和默认值。");
+            println!();
+            println!("  server config set <key=value>... [--name <name>] [--dry-run] [--force]");
+            println!("    修改 server.properties，保留注释和字段顺序，显示修改前后的差异。");
+            println!("    值不符合类型或可选值时拒绝修改；未知字段会被服务器忽略，需要 --force 才会写入。");
+            println!("    --dry-run: 只显示差异，不写入。");
+            println!("    例子: server config set max-players=20 difficulty=normal");
+            println!();
+            println!("  server config check [--name <name>]");
+            println!("    检查 server.properties 中的未知字段和无效的值。");
+            println!();
+            println!("  server config diff [--name <name>]");
+            println!("    列出与官方默认值不同的字段。");
+            println!();
+            println!("  stats [--edition <edition>] [--days <n>] [--export <file.csv|file.json>]");
+            println!("    按版本汇总游玩时长，--wait 启动的每次游戏都会记录到程序目录下的 playtime.jsonl。");
+            println!("    --edition: 只统计指定版本。");
+            println!("    --days: \"最近\" 的天数，默认 7。");
+            println!("    --export: 导出全部记录，扩展名为 .csv 时导出 CSV，否则导出 JSON。");
+            println!("    例子: stats --edition release --export d:/playtime.csv");
+            println!();
+            println!("  钩子: 在程序目录的 config.json 中配置 hooks，阶段为 pre_register、pre_launch、post_exit，");
+            println!("    命令中可使用 {{edition}} {{version}} {{package_family_name}} {{install_path}} {{data_path}} {{stage}}，");
+            println!("    替换时会自动加引号，也可以直接使用环境变量 BBL_EDITION、BBL_INSTALL_PATH 等。");
+            println!();
             println!("  help");
             println!("    显示此帮助信息。");
         }
         "unpack" => {
             if args.len() < 4 {
-                println!("用法: unpack 所在文件目标路径 解压后的路径 [-f] [-dsign] [-dappx]");
+                println!("用法: unpack 所在文件目标路径 解压后的路径 [-f] [-dsign] [-dappx] [--identity 包名] [--display-name 名称]");
                 println!("例子: unpack c:/p/mc.appx d:/a -f -dsign -dappx");
             } else {
                 let source_path = &args[2];
@@ -270,6 +714,9 @@ async fn main() {
                 match extract_zip(file, destination_path, force_replace, delete_signature) {
                     Ok(_) => {
                         info!("解压完成");
+                        if let Some(identity_name) = option_value(&args, "--identity") {
+                            rewrite_package_identity(Path::new(destination_path), identity_name, option_value(&args, "--display-name"), &mut editions);
+                        }
                         if delete_source {
                             if let Err(e) = fs::remove_file(source_path) {
                                 error!("无法删除源文件: {}", e);
@@ -284,187 +731,1127 @@ async fn main() {
         }
         "regpack" => {
             if args.len() < 3 {
-                println!("用法: regpack 路径 [-start]");
+                println!("用法: regpack 路径 [-start] [-deps 依赖缓存目录] [--identity 包名]");
                 println!("例子: regpack D:/Downloads/MC -start");
             } else {
                 let package_path = &args[2];
-                let auto_start = args.contains(&"-start".to_string());
+                let Some(running_policy) = parse_running_policy(&args) else {
+                    return;
+                };
+                let Some(tuning) = parse_process_tuning(&args, &config) else {
+                    return;
+                };
+                if let Some(identity_name) = option_value(&args, "--identity") {
+                    if !rewrite_package_identity(Path::new(package_path), identity_name, option_value(&args, "--display-name"), &mut editions) {
+                        return;
+                    }
+                }
+                let options = RegisterOptions {
+                    dependency_cache_dir: option_value(&args, "-deps").map(PathBuf::from).unwrap_or_else(default_dependency_cache_dir),
+                    running_policy,
+                    auto_start: args.contains(&"-start".to_string()),
+                    launch: parse_launch_options(&args),
+                    wait: parse_wait_options(&args),
+                    hooks: config.hooks.clone(),
+                    editions: editions.clone(),
+                    tuning,
+                    block_incompatible: args.contains(&"--block-incompatible".to_string()),
+                };
 
                 let manifest_path = format!("{}/AppxManifest.xml", package_path.replace("\\", "/"));
-                let mut manifest = File::open(&manifest_path).expect("无法打开文件");
-
-                let mut xml_data = String::new();
-                manifest.read_to_string(&mut xml_data).expect("无法读取文件");
-
-                let parser = EventReader::from_str(&xml_data);
-                let mut identity_name = None;
-                let mut identity_version = None;
-
-                for e in parser {
-                    match e {
-                        Ok(XmlEvent::StartElement { name, attributes, .. }) => {
-                            if name.local_name == "Identity" {
-                                for attr in attributes {
-                                    if attr.name.local_name == "Name" {
-                                        identity_name = Some(attr.value);
-                                    } else if attr.name.local_name == "Version" {
-                                        identity_version = Some(attr.value);
-                                    }
-                                }
+                match AppxManifest::from_file(Path::new(&manifest_path)) {
+                    Ok(manifest) => {
+                        if report_validation(Path::new(package_path), &manifest) {
+                            handle_regpack(&manifest, &manifest_path, &options).await;
+                        } else {
+                            error!("包检查未通过，已取消注册");
+                        }
+                    }
+                    Err(err) => error!("无法读取清单 {}: {}", manifest_path, err),
+                }
+            }
+        }
+        "check" => {
+            if args.len() < 3 {
+                println!("用法: check 路径");
+                println!("例子: check D:/Downloads/MC");
+            } else {
+                let package_path = Path::new(&args[2]);
+                match AppxManifest::from_file(&package_path.join("AppxManifest.xml")) {
+                    Ok(manifest) => {
+                        info!("包名: {}", manifest.identity.name);
+                        info!("包版本: {}", manifest.identity.version);
+                        if report_validation(package_path, &manifest) {
+                            info!("包检查通过");
+                        }
+                    }
+                    Err(err) => error!("无法读取清单: {}", err),
+                }
+            }
+        }
+        "launch" => {
+            if args.len() < 3 {
+                println!("用法: launch 版本 [--wait] [--backup-dir 备份目录] [--add-server 名称 地址 | --connect 地址 | --editor]");
+                println!("例子: launch release --wait --backup-dir d:/backup");
+            } else {
+                match find_edition(&editions, &args[2]) {
+                    Some(edition) => {
+                        let wait_options = parse_wait_options(&args);
+                        let Some(tuning) = parse_process_tuning(&args, &config) else {
+                            return;
+                        };
+                        let uri = match parse_launch_options(&args).uri(&edition.protocol_scheme) {
+                            Ok(uri) => uri,
+                            Err(err) => {
+                                error!("{}", err);
+                                return;
+                            }
+                        };
+                        let context = installed_hook_context(&edition);
+                        if !check_launch_compatibility(&context, args.contains(&"--block-incompatible".to_string())) {
+                            return;
+                        }
+                        if let Err(err) = run_hooks(&config.hooks, &editions, HookStage::PreLaunch, &context) {
+                            error!("{}，已取消启动", err);
+                            return;
+                        }
+
+                        let app_user_model_id = edition.app_user_model_id();
+                        let capture = start_log_capture(&app_user_model_id, &wait_options);
+                        let started_at = Local::now();
+                        let result = launch_app(&app_user_model_id, uri.as_ref(), &tuning);
+                        if wait_options.wait && wait_for_game(&app_user_model_id, &result, started_at, &wait_options, capture, &context).is_some() {
+                            if let Err(err) = run_hooks(&config.hooks, &editions, HookStage::PostExit, &context) {
+                                error!("{}", err);
                             }
                         }
-                        _ => {}
                     }
+                    None => error!("未知版本: {}", args[2]),
                 }
+            }
+        }
+        "unregister" => {
+            if args.len() < 3 {
+                println!("用法: unregister 版本 [--export 备份路径] [--wipe] [--yes]");
+                println!("例子: unregister beta --export d:/backup/beta.zip");
+            } else {
+                let export_path = option_value(&args, "--export");
+                let wipe_data = args.contains(&"--wipe".to_string());
+                let assume_yes = args.contains(&"--yes".to_string());
+                let Some(running_policy) = parse_running_policy(&args) else {
+                    return;
+                };
 
-                match identity_name.as_deref() {
-                    Some("Microsoft.MinecraftWindowsBeta") => handle_minecraft_beta(identity_version, &manifest_path, package_path, auto_start).await,
-                    Some("Microsoft.MinecraftUWP") => handle_minecraft_uwp(identity_version, &manifest_path, package_path, auto_start).await,
-                    Some("Microsoft.MinecraftEducationEdition") => handle_minecraft_education(identity_version, &manifest_path, package_path, auto_start).await,
-                    Some("Microsoft.MinecraftEducationPreview") => handle_minecraft_education_preview(identity_version, &manifest_path, package_path, auto_start).await,
-                    Some(name) => info!("未知包名: {}", name),
-                    None => info!("未知包名"),
+                match find_edition(&editions, &args[2]) {
+                    Some(edition) => handle_unregister(&edition, export_path.map(|path| path.as_str()), wipe_data, assume_yes, running_policy).await,
+                    None => error!("未知版本: {}", args[2]),
+                }
+            }
+        }
+        "shortcut" => {
+            if args.len() < 4 || args[2] != "create" {
+                println!("用法: shortcut create 路径 [--name 名称] [--type lnk|url|script] [--output 保存目录]");
+                println!("例子: shortcut create D:/MC/1.20.80 --name \"1.20.80 release\"");
+            } else {
+                let package_path = Path::new(&args[3]);
+                let kind = match option_value(&args, "--type").map(|kind| kind.parse::<ShortcutKind>()) {
+                    Some(Ok(kind)) => kind,
+                    Some(Err(err)) => {
+                        error!("{}", err);
+                        return;
+                    }
+                    None => ShortcutKind::platform_default(),
+                };
+                let output_dir = option_value(&args, "--output").map(PathBuf::from).unwrap_or_else(default_output_dir);
+                handle_shortcut_create(package_path, option_value(&args, "--name").map(|name| name.as_str()), kind, &output_dir, &editions);
+            }
+        }
+        "import" => {
+            if args.len() < 3 {
+                println!("用法: import 文件 [--edition 版本] [--force]");
+                println!("例子: import d:/downloads/addon.mcaddon --edition beta");
+            } else {
+                let edition_name = option_value(&args, "--edition").map(|edition| edition.as_str()).unwrap_or("release");
+                match find_edition(&editions, edition_name) {
+                    Some(edition) => handle_import(Path::new(&args[2]), &edition, args.contains(&"--force".to_string())),
+                    None => error!("未知版本: {}", edition_name),
                 }
             }
         }
+        "sync" => {
+            let worlds = SyncSelection::parse(option_value(&args, "--worlds").map(|worlds| worlds.as_str()));
+            let packs = SyncSelection::parse(option_value(&args, "--packs").map(|packs| packs.as_str()));
+            if args.len() < 4 || (worlds == SyncSelection::None && packs == SyncSelection::None) {
+                println!("用法: sync 源版本 目标版本 [--worlds ID,ID|all] [--packs UUID,UUID|all] [--conflict skip|overwrite|newer|rename] [--dry-run] [--backup-dir 目录]");
+                println!("例子: sync release preview --worlds 2dWxZeyXAAA= --dry-run");
+            } else {
+                let policy = match option_value(&args, "--conflict").map(|policy| policy.parse::<ConflictPolicy>()) {
+                    Some(Ok(policy)) => policy,
+                    Some(Err(err)) => {
+                        error!("{}", err);
+                        return;
+                    }
+                    None => ConflictPolicy::default(),
+                };
+                let options = SyncOptions {
+                    worlds,
+                    packs,
+                    policy,
+                    dry_run: args.contains(&"--dry-run".to_string()),
+                    backup_dir: option_value(&args, "--backup-dir").map(PathBuf::from).unwrap_or_else(default_sync_backup_dir),
+                };
+                handle_sync(&editions, &args[2], &args[3], &options);
+            }
+        }
+        "server" => {
+            let name = option_value(&args, "--name").map(|name| name.as_str()).unwrap_or(DEFAULT_SERVER_NAME);
+            match (args.get(2).map(|subcommand| subcommand.as_str()), args.get(3)) {
+                (Some("install"), Some(archive)) => handle_server_install(Path::new(archive), name, false),
+                (Some("update"), Some(archive)) => handle_server_install(Path::new(archive), name, true),
+                (Some("list"), _) => handle_server_list(),
+                (Some("run"), _) => {
+                    let Some(options) = parse_server_run_options(&args, name) else {
+                        return;
+                    };
+                    handle_server_run(name, &options);
+                }
+                (Some("send"), Some(_)) => {
+                    // --name 之外的参数拼成一条命令
+                    let mut words = Vec::new();
+                    let mut rest = args.iter().skip(3);
+                    while let Some(word) = rest.next() {
+                        if word == "--name" {
+                            rest.next();
+                        } else {
+                            words.push(word.as_str());
+                        }
+                    }
+                    match send_control_command(&server_dir(name), &words.join(" ")) {
+                        Ok(()) => info!("已发送: {}", words.join(" ")),
+                        Err(err) => error!("发送失败: {}", err),
+                    }
+                }
+                (Some("config"), _) => match args.get(3).map(|action| action.as_str()) {
+                    Some("get") => handle_server_config_get(name, args.get(4).filter(|key| !key.starts_with("--")).map(|key| key.as_str())),
+                    Some("set") if args.iter().skip(4).any(|arg| arg.contains('=')) => {
+                        let assignments: Vec<&String> = args.iter().skip(4).filter(|arg| arg.contains('=')).collect();
+                        handle_server_config_set(name, &assignments, args.contains(&"--dry-run".to_string()), args.contains(&"--force".to_string()));
+                    }
+                    Some("check") => handle_server_config_check(name),
+                    Some("diff") => handle_server_config_diff(name),
+                    _ => {
+                        println!("用法: server config get [字段] [--name 名称]");
+                        println!("      server config set 字段=值... [--name 名称] [--dry-run] [--force]");
+                        println!("      server config check [--name 名称]");
+                        println!("      server config diff [--name 名称]");
+                        println!("例子: server config set max-players=20 difficulty=normal");
+                    }
+                },
+                _ => {
+                    println!("用法: server install BDS压缩包 [--name 名称]");
+                    println!("      server update BDS压缩包 [--name 名称]");
+                    println!("      server list");
+                    println!("      server run [--name 名称] [--control-port 端口] [--backup-interval 分钟] [--backup-dir 目录] [--keep 份数]");
+                    println!("      server send 命令 [--name 名称]");
+                    println!("      server config get|set|check|diff [--name 名称]");
+                    println!("例子: server install d:/downloads/bedrock-server-1.21.2.02.zip");
+                    println!("      server run --control-port 19140 --backup-interval 30 --keep 48");
+                    println!("      server send say 服务器将在 5 分钟后重启");
+                }
+            }
+        }
+        "worlds" => {
+            let edition_name = option_value(&args, "--edition").map(|edition| edition.as_str()).unwrap_or("release");
+            let Some(edition) = find_edition(&editions, edition_name) else {
+                error!("未知版本: {}", edition_name);
+                return;
+            };
+            let output = option_value(&args, "--output").map(Path::new);
+            match (args.get(2).map(|subcommand| subcommand.as_str()), args.get(3)) {
+                (Some("list"), _) => handle_worlds_list(&edition),
+                (Some("export"), Some(id)) => handle_worlds_export(&edition, id, output, args.contains(&"--with-packs".to_string())),
+                (Some("set"), Some(id)) if args.iter().skip(4).any(|arg| arg.contains('=')) => {
+                    let assignments: Vec<&String> = args.iter().skip(4).filter(|arg| arg.contains('=')).collect();
+                    handle_worlds_set(&edition, id, &assignments);
+                }
+                _ => {
+                    println!("用法: worlds list [--edition 版本]");
+                    println!("      worlds export 世界ID [--output 文件] [--with-packs] [--edition 版本]");
+                    println!("      worlds set 世界ID 字段=值 [字段=值 ...] [--edition 版本]");
+                    println!("例子: worlds export 2dWxZeyXAAA= --with-packs");
+                    println!("      worlds set 2dWxZeyXAAA= gamemode=creative cheats=true experiments.gametest=true MinimumCompatibleClientVersion=1.20.80");
+                }
+            }
+        }
+        "packs" => {
+            let edition_name = option_value(&args, "--edition").map(|edition| edition.as_str()).unwrap_or("release");
+            let Some(edition) = find_edition(&editions, edition_name) else {
+                error!("未知版本: {}", edition_name);
+                return;
+            };
+            let output = option_value(&args, "--output").map(Path::new);
+            let world = option_value(&args, "--world");
+            match (args.get(2).map(|subcommand| subcommand.as_str()), args.get(3), world) {
+                (Some("list"), _, _) => handle_packs_list(&edition),
+                (Some("export"), Some(uuid), _) => handle_packs_export(&edition, uuid, output),
+                (Some("enable"), Some(uuid), Some(world)) => handle_packs_enable(&edition, world, uuid, args.contains(&"--force".to_string())),
+                (Some("disable"), Some(uuid), Some(world)) => handle_packs_disable(&edition, world, uuid),
+                (Some("check"), _, Some(world)) => handle_packs_check(&edition, world),
+                _ => {
+                    println!("用法: packs list [--edition 版本]");
+                    println!("      packs export UUID [--output 文件] [--edition 版本]");
+                    println!("      packs enable UUID --world 世界ID [--force] [--edition 版本]");
+                    println!("      packs disable UUID --world 世界ID [--edition 版本]");
+                    println!("      packs check --world 世界ID [--edition 版本]");
+                    println!("例子: packs export 0fba4063-ba1a-4a05-9f1b-4aba4c3f0d6d --output d:/share/my.mcpack");
+                    println!("      packs enable 0fba4063-ba1a-4a05-9f1b-4aba4c3f0d6d --world 2dWxZeyXAAA=");
+                }
+            }
+        }
+        "stats" => {
+            let recent_days = match option_value(&args, "--days").map(|days| days.parse::<i64>()) {
+                Some(Ok(days)) if days > 0 => days,
+                Some(_) => {
+                    error!("无效的天数: {}", option_value(&args, "--days").map(|days| days.as_str()).unwrap_or_default());
+                    return;
+                }
+                None => 7,
+            };
+            let Some(since) = recent_since(Local::now(), recent_days) else {
+                error!("天数超出范围: {}", recent_days);
+                return;
+            };
+            handle_stats(&editions, option_value(&args, "--edition").map(|edition| edition.as_str()), recent_days, since, option_value(&args, "--export").map(Path::new));
+        }
         _ => {
             println!("未知命令，请输入有效命令或 'help' 获取帮助");
         }
     }
 }
 
-async fn handle_minecraft_beta(identity_version: Option<String>, manifest_path: &str, package_path: &str, auto_start: bool) {
-    let app_user_model_id = "Microsoft.MinecraftWindowsBeta_8wekyb3d8bbwe!App";
-    match get_package_info(app_user_model_id) {
-        Ok(Some((version, package_family_name, package_full_name))) => {
-            debug!("Version: {:?}", version);
-            debug!("Package Family Name: {}", package_family_name);
-            debug!("Package Full Name: {}", package_full_name);
-
-            if let Some(identity_version_str) = identity_version {
-                if version == identity_version_str {
-                    debug!("版本匹配");
-                    if auto_start {
-                        if let Err(err) = start_minecraft_beta() {
-                            error!("启动失败: {}", err);
-                        }
-                    }
-                } else {
-                    info!("版本不匹配");
-                    handle_remove_package_register_start(&package_full_name, manifest_path, package_path, auto_start, "Microsoft.MinecraftWindowsBeta").await;
+fn handle_import(archive_path: &Path, edition: &Edition, force: bool) {
+    let Some(com_mojang) = edition_com_mojang(edition) else {
+        return;
+    };
+    info!("导入 {} 到 {}", archive_path.display(), com_mojang.display());
+
+    match import_content(archive_path, &com_mojang, force) {
+        Ok(items) => {
+            for item in items {
+                let version = item.version.map(|version| format!(" {}", version)).unwrap_or_default();
+                match item.action {
+                    ImportAction::Installed => info!("已安装{}: {}{} -> {}", item.kind.name(), item.name, version, item.destination.display()),
+                    ImportAction::Replaced { previous } => info!("已更新{}: {} {} -> {}", item.kind.name(), item.name, previous, version.trim()),
+                    ImportAction::Skipped(reason) => warning!("跳过{}: {}{} ({}，使用 --force 强制替换)", item.kind.name(), item.name, version, reason),
                 }
             }
         }
-        Ok(None) => {
-            error!("无法获取包信息");
-            register_start(manifest_path, auto_start, "Microsoft.MinecraftWindowsBeta").await;
+        Err(err) => error!("导入失败: {}", err),
+    }
+}
+
+fn handle_server_install(archive_path: &Path, name: &str, update: bool) {
+    let target_dir = server_dir(name);
+    let result = if update {
+        update_server(archive_path, &target_dir)
+    } else {
+        install_server(archive_path, &target_dir)
+    };
+    let changelog = match result {
+        Ok(changelog) => changelog,
+        Err(err) => {
+            error!("{}失败: {}", if update { "更新" } else { "安装" }, err);
+            return;
+        }
+    };
+
+    let version = changelog.version.as_deref().unwrap_or("未知版本");
+    if !update {
+        info!("服务器 {} ({}) 已安装到: {}", name, version, target_dir.display());
+        return;
+    }
+    for (path, change) in &changelog.changes {
+        debug!("{} {}", change.name(), path);
+    }
+    info!(
+        "服务器 {} 已从 {} 更新到 {}: 保留 {} 项，替换 {} 个文件，新增 {} 个文件",
+        name,
+        changelog.previous_version.as_deref().unwrap_or("未知版本"),
+        version,
+        changelog.count(FileChange::Kept),
+        changelog.count(FileChange::Replaced),
+        changelog.count(FileChange::Added),
+    );
+    let stale = changelog.count(FileChange::Stale);
+    if stale > 0 {
+        warning!("有 {} 个文件在新版本中已经没有，已保留，详见更新记录", stale);
+    }
+    match append_changelog(&target_dir, &changelog) {
+        Ok(path) => info!("更新记录已写入: {}", path.display()),
+        Err(err) => warning!("无法写入更新记录: {}", err),
+    }
+}
+
+fn parse_server_run_options(args: &[String], name: &str) -> Option<RunOptions> {
+    let control_port = match option_value(args, "--control-port").map(|port| port.parse::<u16>()) {
+        Some(Ok(port)) => Some(port),
+        Some(Err(_)) => {
+            error!("无效的端口: {}", option_value(args, "--control-port").map(|port| port.as_str()).unwrap_or_default());
+            return None;
         }
+        None => None,
+    };
+    let backup_interval = match option_value(args, "--backup-interval").map(|minutes| minutes.parse::<u64>()) {
+        Some(Ok(minutes)) if minutes > 0 => Some(Duration::from_secs(minutes * 60)),
+        Some(_) => {
+            error!("无效的备份间隔: {}", option_value(args, "--backup-interval").map(|minutes| minutes.as_str()).unwrap_or_default());
+            return None;
+        }
+        None => None,
+    };
+    let keep_backups = match option_value(args, "--keep").map(|keep| keep.parse::<usize>()) {
+        Some(Ok(keep)) => keep,
+        Some(Err(_)) => {
+            error!("无效的保留份数: {}", option_value(args, "--keep").map(|keep| keep.as_str()).unwrap_or_default());
+            return None;
+        }
+        None => 0,
+    };
+    Some(RunOptions {
+        control_port,
+        backup_interval,
+        backup_dir: option_value(args, "--backup-dir").map(PathBuf::from).unwrap_or_else(|| default_server_backup_dir(name)),
+        keep_backups,
+    })
+}
+
+fn handle_server_run(name: &str, options: &RunOptions) {
+    let target_dir = server_dir(name);
+    info!("启动服务器 {}: {}", name, target_dir.display());
+    if let Some(interval) = options.backup_interval {
+        info!("每 {} 分钟备份一次世界到: {}", interval.as_secs() / 60, options.backup_dir.display());
+    }
+    info!("输入 stop 正常停止服务器，输入 backup 立即备份");
+
+    match run_server(&target_dir, options) {
+        Ok(Some(0)) => info!("服务器已停止"),
+        Ok(Some(code)) => warning!("服务器已退出，退出码: {}", code),
+        Ok(None) => warning!("服务器被终止"),
+        Err(err) => error!("运行服务器失败: {}", err),
+    }
+}
+
+fn handle_server_list() {
+    let servers = list_servers();
+    if servers.is_empty() {
+        info!("还没有安装服务器，使用 server install 安装");
+        return;
+    }
+    println!("{:<16} {:<14} {:<20} 目录", "名称", "版本", "更新时间");
+    for (name, path, server_info) in &servers {
+        println!(
+            "{:<16} {:<14} {:<20} {}",
+            name,
+            server_info.as_ref().and_then(|server_info| server_info.version.as_deref()).unwrap_or("未知"),
+            server_info.as_ref().map(|server_info| server_info.updated_at.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default(),
+            path.display(),
+        );
+    }
+}
+
+fn load_server_properties(name: &str) -> Option<(PathBuf, ServerProperties)> {
+    let path = server_dir(name).join("server.properties");
+    match ServerProperties::from_file(&path) {
+        Ok(properties) => Some((path, properties)),
         Err(err) => {
-            error!("{:?}", err);
-            debug!("没有注册过 appx");
-            register_start(manifest_path, auto_start, "Microsoft.MinecraftWindowsBeta").await;
+            error!("无法读取 {}: {}", path.display(), err);
+            None
         }
     }
 }
 
-async fn handle_minecraft_uwp(identity_version: Option<String>, manifest_path: &str, package_path: &str, auto_start: bool) {
-    let app_user_model_id = "Microsoft.MinecraftUWP_8wekyb3d8bbwe!App";
-    match get_package_info(app_user_model_id) {
-        Ok(Some((version, package_family_name, package_full_name))) => {
-            debug!("Version: {:?}", version);
-            debug!("Package Family Name: {}", package_family_name);
-            debug!("Package Full Name: {}", package_full_name);
-
-            if let Some(identity_version_str) = identity_version {
-                if version == identity_version_str {
-                    debug!("版本匹配");
-                    if auto_start {
-                        if let Err(err) = start_minecraft_release() {
-                            error!("启动失败: {}", err);
-                        }
-                    }
-                } else {
-                    debug!("版本不匹配");
-                    handle_remove_package_register_start(&package_full_name, manifest_path, package_path, auto_start, "Microsoft.MinecraftUWP").await;
+fn print_property_changes(changes: &[PropertyChange]) {
+    for change in changes {
+        if let Some(old_value) = &change.old_value {
+            println!("- {}={}", change.key, old_value);
+        }
+        if let Some(new_value) = &change.new_value {
+            println!("+ {}={}", change.key, new_value);
+        }
+    }
+}
+
+fn handle_server_config_get(name: &str, key: Option<&str>) {
+    let Some((_, properties)) = load_server_properties(name) else {
+        return;
+    };
+    let Some(key) = key else {
+        for (key, value) in properties.entries() {
+            match find_spec(key) {
+                Some(_) => println!("{}={}", key, value),
+                None => println!("{}={}    (未知字段)", key, value),
+            }
+        }
+        return;
+    };
+
+    match (properties.get(key), find_spec(key)) {
+        (Some(value), Some(spec)) => println!("{}={}    ({}，默认 {})", key, value, spec.kind.describe(), spec.default),
+        (None, Some(spec)) => println!("{} 未设置，服务器使用默认值 {}    ({})", key, spec.default, spec.kind.describe()),
+        (Some(value), None) => {
+            println!("{}={}", key, value);
+            warning!("{}", unknown_key_message(key));
+        }
+        (None, None) => error!("{}", unknown_key_message(key)),
+    }
+}
+
+fn handle_server_config_set(name: &str, assignments: &[&String], dry_run: bool, force: bool) {
+    let Some((path, properties)) = load_server_properties(name) else {
+        return;
+    };
+
+    // 全部检查通过才写入
+    let mut updated = properties.clone();
+    for assignment in assignments {
+        let Some((key, value)) = parse_assignment(assignment) else {
+            error!("无效的设置: {} (应为 字段=值)", assignment);
+            return;
+        };
+        match find_spec(key) {
+            Some(spec) => {
+                if let Err(err) = spec.kind.validate(value) {
+                    error!("{}: {}", key, err);
+                    return;
                 }
             }
+            None if force => warning!("{}，服务器会忽略它", unknown_key_message(key)),
+            None => {
+                error!("{}，确认无误请加 --force", unknown_key_message(key));
+                return;
+            }
         }
-        Ok(None) => {
-            error!("无法获取包信息");
-            register_start(manifest_path, auto_start, "Microsoft.MinecraftUWP").await;
+        updated.set(key, value);
+    }
+
+    let changes = properties.diff(&updated);
+    if changes.is_empty() {
+        info!("没有需要修改的字段");
+        return;
+    }
+    print_property_changes(&changes);
+    if dry_run {
+        return;
+    }
+    match updated.write_to_file(&path) {
+        Ok(()) => {
+            info!("已修改 {} 个字段: {}", changes.len(), path.display());
+            info!("修改在服务器重启后生效");
+        }
+        Err(err) => error!("写入 server.properties 失败: {}", err),
+    }
+}
+
+fn handle_server_config_check(name: &str) {
+    let Some((path, properties)) = load_server_properties(name) else {
+        return;
+    };
+    let issues = properties.validate();
+    if issues.is_empty() {
+        info!("{} 没有问题", path.display());
+        return;
+    }
+    for issue in &issues {
+        match issue.severity {
+            Severity::Warning => warning!("{}", issue.message),
+            Severity::Error => error!("{}", issue.message),
+        }
+    }
+}
+
+fn handle_server_config_diff(name: &str) {
+    let Some((_, properties)) = load_server_properties(name) else {
+        return;
+    };
+    let changes = properties.changed_from_defaults();
+    if changes.is_empty() {
+        info!("所有已知字段都是默认值");
+        return;
+    }
+    print_property_changes(&changes);
+}
+
+struct SyncOptions {
+    worlds: SyncSelection,
+    packs: SyncSelection,
+    policy: ConflictPolicy,
+    dry_run: bool,
+    backup_dir: PathBuf,
+}
+
+// 版本名，或直接给出 com.mojang 目录
+fn resolve_com_mojang(editions: &[Edition], name: &str) -> Option<PathBuf> {
+    match find_edition(editions, name) {
+        Some(edition) => edition_com_mojang(&edition),
+        None if Path::new(name).is_dir() => Some(PathBuf::from(name)),
+        None => {
+            error!("未知版本: {}", name);
+            None
+        }
+    }
+}
+
+fn handle_sync(editions: &[Edition], source_name: &str, destination_name: &str, options: &SyncOptions) {
+    let (Some(source), Some(destination)) = (resolve_com_mojang(editions, source_name), resolve_com_mojang(editions, destination_name)) else {
+        return;
+    };
+    if source == destination {
+        error!("源和目标是同一个目录");
+        return;
+    }
+
+    let entries = match plan_sync(&source, &destination, &options.worlds, &options.packs, options.policy) {
+        Ok(entries) => entries,
+        Err(err) => {
+            error!("{}", err);
+            return;
+        }
+    };
+    if entries.is_empty() {
+        info!("没有需要同步的内容");
+        return;
+    }
+
+    for entry in &entries {
+        let label = format!("{} {} ({})", entry.kind.name(), entry.name, entry.id);
+        match &entry.action {
+            SyncAction::Copy => info!("复制 {} -> {}", label, entry.target().display()),
+            SyncAction::Overwrite => warning!("覆盖 {} -> {}", label, entry.target().display()),
+            SyncAction::CopyAs(path) => info!("另存 {} -> {}", label, path.display()),
+            SyncAction::Skip(reason) => info!("跳过 {}: {}", label, reason),
         }
+    }
+    if options.dry_run {
+        info!("预览模式，没有修改任何文件");
+        return;
+    }
+
+    match backup_destination(&entries, &destination, &options.backup_dir) {
+        Ok(Some(archive_path)) => info!("已备份将被覆盖的内容到: {}", archive_path.display()),
+        Ok(None) => {}
         Err(err) => {
-            error!("{:?}", err);
-            info!("没有注册过 appx");
-            register_start(manifest_path, auto_start, "Microsoft.MinecraftUWP").await;
+            error!("备份目标失败，已取消同步: {}", err);
+            return;
         }
     }
+    match apply_sync(&entries) {
+        Ok(()) => {
+            let count = entries.iter().filter(|entry| !matches!(entry.action, SyncAction::Skip(_))).count();
+            info!("同步完成，共 {} 项", count);
+        }
+        Err(err) => error!("同步失败: {}", err),
+    }
 }
 
-async fn handle_minecraft_education(identity_version: Option<String>, manifest_path: &str, package_path: &str, auto_start: bool) {
-    let app_user_model_id = "Microsoft.MinecraftEducationEdition_8wekyb3d8bbwe!Microsoft.MinecraftEducationEdition";
-    match get_package_info(app_user_model_id) {
-        Ok(Some((version, package_family_name, package_full_name))) => {
-            debug!("Version: {:?}", version);
-            debug!("Package Family Name: {}", package_family_name);
-            debug!("Package Full Name: {}", package_full_name);
-
-            if let Some(identity_version_str) = identity_version {
-                if version == identity_version_str {
-                    debug!("版本匹配");
-                    if auto_start {
-                        if let Err(err) = start_minecraft_education() {
-                            error!("启动失败: {}", err);
-                        }
-                    }
-                } else {
-                    debug!("版本不匹配");
-                    handle_remove_package_register_start(&package_full_name, manifest_path, package_path, auto_start, "Microsoft.MinecraftEducationEdition").await;
-                }
+fn edition_com_mojang(edition: &Edition) -> Option<PathBuf> {
+    let com_mojang = edition_com_mojang_dir(edition);
+    if com_mojang.is_none() {
+        error!("找不到 {} 的数据目录", edition.identity_name);
+    }
+    com_mojang
+}
+
+fn handle_worlds_list(edition: &Edition) {
+    let Some(com_mojang) = edition_com_mojang(edition) else {
+        return;
+    };
+    let worlds = match list_worlds(&com_mojang) {
+        Ok(worlds) => worlds,
+        Err(err) => {
+            error!("无法读取世界列表: {}", err);
+            return;
+        }
+    };
+    if worlds.is_empty() {
+        info!("{} 没有世界", edition.identity_name);
+        return;
+    }
+
+    println!("{:<14} {:<24} {:<6} {:<18} {:<14} {:>10}  实验性玩法", "ID", "名称", "模式", "最后游玩", "最后打开版本", "大小");
+    for world in &worlds {
+        if let Some(err) = &world.error {
+            println!("{:<14} {:<24} level.dat 读取失败: {}", world.id, world.name, err);
+            continue;
+        }
+        println!(
+            "{:<14} {:<24} {:<6} {:<18} {:<14} {:>10}  {}",
+            world.id,
+            world.name,
+            world.game_mode.map(game_mode_name).unwrap_or("未知"),
+            world.last_played.map(|time| time.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default(),
+            world.last_opened_with_version.as_deref().unwrap_or("未知"),
+            format_size(world.size),
+            if world.experiments.is_empty() { "无".to_string() } else { world.experiments.join(", ") },
+        );
+    }
+    info!("共 {} 个世界", worlds.len());
+}
+
+fn handle_worlds_export(edition: &Edition, id: &str, output: Option<&Path>, include_packs: bool) {
+    let Some(com_mojang) = edition_com_mojang(edition) else {
+        return;
+    };
+    let Some(world_dir) = find_world(&com_mojang, id) else {
+        error!("找不到世界: {}", id);
+        return;
+    };
+
+    let archive_path = output.map(|path| path.to_path_buf()).unwrap_or_else(|| PathBuf::from(default_world_archive_name(&world_dir)));
+    match export_world(&world_dir, &com_mojang, &archive_path, include_packs) {
+        Ok(export) => {
+            for (kind, name) in &export.included_packs {
+                info!("已包含{}: {}", kind.name(), name);
+            }
+            for uuid in &export.missing_packs {
+                warning!("世界引用的包没有安装，未包含: {}", uuid);
             }
+            info!("世界已导出到: {}", export.archive_path.display());
         }
-        Ok(None) => {
-            error!("无法获取包信息");
-            register_start(manifest_path, auto_start, "Microsoft.MinecraftEducationEdition").await;
+        Err(err) => error!("导出世界失败: {}", err),
+    }
+}
+
+fn handle_worlds_set(edition: &Edition, id: &str, assignments: &[&String]) {
+    let Some(com_mojang) = edition_com_mojang(edition) else {
+        return;
+    };
+    let Some(world_dir) = find_world(&com_mojang, id) else {
+        error!("找不到世界: {}", id);
+        return;
+    };
+    let mut level = match LevelDat::from_file(&world_dir.join("level.dat")) {
+        Ok(level) => level,
+        Err(err) => {
+            error!("无法读取 level.dat: {}", err);
+            return;
+        }
+    };
+
+    // 全部解析成功才写入
+    let mut changes = Vec::new();
+    for assignment in assignments {
+        let Some((key, value)) = parse_assignment(assignment) else {
+            error!("无效的设置: {} (应为 字段=值)", assignment);
+            return;
+        };
+        match apply_level_setting(&mut level, key, value) {
+            Ok(applied) => changes.extend(applied),
+            Err(err) => {
+                error!("{}: {}", key, err);
+                return;
+            }
         }
+    }
+
+    match save_level_dat(&world_dir, &level) {
+        Ok(backup_path) => {
+            for change in &changes {
+                info!("{}: {} -> {}", change.key, change.old_value.as_deref().unwrap_or("(无)"), change.new_value);
+            }
+            info!("已备份原 level.dat 到: {}", backup_path.display());
+            warning!("如果游戏正在运行，请先退出世界，否则修改会被覆盖");
+        }
+        Err(err) => error!("写入 level.dat 失败: {}", err),
+    }
+}
+
+fn handle_packs_list(edition: &Edition) {
+    let Some(com_mojang) = edition_com_mojang(edition) else {
+        return;
+    };
+    let packs = all_installed_packs(&com_mojang);
+    if packs.is_empty() {
+        info!("{} 没有安装资源包或行为包", edition.identity_name);
+        return;
+    }
+    println!("{:<8} {:<38} {:<10} {:<12} 名称", "类型", "UUID", "版本", "最低游戏版本");
+    for (kind, _, manifest) in &packs {
+        println!(
+            "{:<8} {:<38} {:<10} {:<12} {}",
+            kind.name(),
+            manifest.uuid,
+            manifest.version.to_string(),
+            manifest.min_engine_version.map(|version| version.to_string()).unwrap_or_default(),
+            manifest.name,
+        );
+    }
+    for missing in installed_missing_dependencies(&com_mojang) {
+        warning!("{} 缺少依赖: {}", missing.required_by, describe_missing_dependency(&missing));
+    }
+}
+
+fn describe_missing_dependency(missing: &MissingDependency) -> String {
+    let required = missing.version.map(|version| format!(" (需要 {})", version)).unwrap_or_default();
+    match missing.installed {
+        Some(installed) => format!("{}{}，已安装的版本 {} 过低", missing.uuid, required, installed),
+        None => format!("{}{}，未安装", missing.uuid, required),
+    }
+}
+
+fn world_and_com_mojang(edition: &Edition, id: &str) -> Option<(PathBuf, PathBuf)> {
+    let com_mojang = edition_com_mojang(edition)?;
+    let Some(world_dir) = find_world(&com_mojang, id) else {
+        error!("找不到世界: {}", id);
+        return None;
+    };
+    Some((world_dir, com_mojang))
+}
+
+fn handle_packs_enable(edition: &Edition, world_id: &str, uuid: &str, force: bool) {
+    let Some((world_dir, com_mojang)) = world_and_com_mojang(edition, world_id) else {
+        return;
+    };
+    let activation = match enable_pack(&world_dir, &com_mojang, uuid, force) {
+        Ok(activation) => activation,
         Err(err) => {
-            error!("{:?}", err);
-            debug!("没有注册过 appx");
-            register_start(manifest_path, auto_start, "Microsoft.MinecraftEducationEdition").await;
+            error!("启用失败: {}", err);
+            return;
         }
+    };
+
+    for missing in &activation.missing {
+        warning!("{} 缺少依赖: {}", missing.required_by, describe_missing_dependency(missing));
+    }
+    if !activation.written {
+        error!("存在缺少的依赖，未启用。使用 --force 忽略");
+        return;
+    }
+    for (kind, name) in &activation.enabled {
+        info!("已启用{}: {}", kind.name(), name);
+    }
+    for (kind, name) in &activation.already_enabled {
+        info!("{}已启用: {}", kind.name(), name);
+    }
+}
+
+fn handle_packs_disable(edition: &Edition, world_id: &str, uuid: &str) {
+    let Some((world_dir, com_mojang)) = world_and_com_mojang(edition, world_id) else {
+        return;
+    };
+    match disable_pack(&world_dir, &com_mojang, uuid) {
+        Ok(deactivation) => {
+            info!("已在世界中停用{}: {}", deactivation.kind.name(), uuid);
+            for name in &deactivation.dependents {
+                warning!("{} 依赖这个包，仍处于启用状态", name);
+            }
+        }
+        Err(err) => error!("停用失败: {}", err),
+    }
+}
+
+fn handle_packs_check(edition: &Edition, world_id: &str) {
+    let Some((world_dir, com_mojang)) = world_and_com_mojang(edition, world_id) else {
+        return;
+    };
+    let statuses = match check_world_packs(&world_dir, &com_mojang) {
+        Ok(statuses) => statuses,
+        Err(err) => {
+            error!("无法读取世界启用的包: {}", err);
+            return;
+        }
+    };
+    if statuses.is_empty() {
+        info!("世界 {} 没有启用资源包或行为包", world_id);
+        return;
+    }
+
+    let mut problems = 0;
+    for status in &statuses {
+        let version = status.reference.version.map(|version| version.to_string()).unwrap_or_default();
+        let Some(manifest) = &status.manifest else {
+            problems += 1;
+            error!("{} {} {}: 未安装", status.kind.name(), status.reference.pack_id, version);
+            continue;
+        };
+        info!("{} {} {}", status.kind.name(), manifest.name, manifest.version);
+        for missing in &status.missing {
+            problems += 1;
+            error!("  缺少依赖: {}", describe_missing_dependency(missing));
+        }
+        for uuid in &status.not_enabled {
+            problems += 1;
+            warning!("  依赖的包已安装但没有在世界中启用: {} (可用 packs enable 启用)", uuid);
+        }
+    }
+    if problems == 0 {
+        info!("共 {} 个包，依赖完整", statuses.len());
     }
 }
 
-async fn handle_minecraft_education_preview(identity_version: Option<String>, manifest_path: &str, package_path: &str, auto_start: bool) {
-    let app_user_model_id = "Microsoft.MinecraftEducationPreview_8wekyb3d8bbwe!Microsoft.MinecraftEducationEdition";
-    match get_package_info(app_user_model_id) {
-        Ok(Some((version, package_family_name, package_full_name))) => {
+fn handle_packs_export(edition: &Edition, uuid: &str, output: Option<&Path>) {
+    let Some(com_mojang) = edition_com_mojang(edition) else {
+        return;
+    };
+    match export_pack(&com_mojang, uuid, output) {
+        Ok((kind, archive_path)) => info!("{}已导出到: {}", kind.name(), archive_path.display()),
+        Err(err) => error!("导出失败: {}", err),
+    }
+}
+
+fn handle_stats(editions: &[Edition], edition: Option<&str>, recent_days: i64, recent_since: DateTime<Local>, export_path: Option<&Path>) {
+    let mut records = match load_history(&default_history_path()) {
+        Ok(records) => records,
+        Err(err) => {
+            error!("无法读取游玩记录: {}", err);
+            return;
+        }
+    };
+    if let Some(edition) = edition {
+        let identity_name = find_edition(editions, edition).map(|edition| edition.identity_name).unwrap_or_else(|| edition.to_string());
+        records.retain(|record| record.edition.eq_ignore_ascii_case(&identity_name));
+    }
+    if records.is_empty() {
+        info!("还没有游玩记录，使用 --wait 启动后会自动记录");
+        return;
+    }
+
+    let summaries = summarize(&records, recent_since);
+    println!("{:<40} {:<16} {:>6} {:>16} {:>16}  最后游玩", "包名", "版本", "次数", "总时长", format!("最近{}天", recent_days));
+    for summary in &summaries {
+        println!(
+            "{:<40} {:<16} {:>6} {:>16} {:>16}  {}",
+            summary.edition,
+            summary.version,
+            summary.sessions,
+            format_duration(summary.total),
+            format_duration(summary.recent),
+            summary.last_played.format("%Y-%m-%d %H:%M"),
+        );
+    }
+    let total = summaries.iter().fold(chrono::Duration::zero(), |total, summary| total + summary.total);
+    let recent = summaries.iter().fold(chrono::Duration::zero(), |recent, summary| recent + summary.recent);
+    println!("合计: {} 次，总时长 {}，最近{}天 {}", records.len(), format_duration(total), recent_days, format_duration(recent));
+
+    if let Some(export_path) = export_path {
+        match export_history(&records, export_path) {
+            Ok(_) => info!("游玩记录已导出到: {}", export_path.display()),
+            Err(err) => error!("导出游玩记录失败: {}", err),
+        }
+    }
+}
+
+fn handle_shortcut_create(package_path: &Path, name: Option<&str>, kind: ShortcutKind, output_dir: &Path, editions: &[Edition]) {
+    let manifest = match AppxManifest::from_file(&package_path.join("AppxManifest.xml")) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            error!("无法读取清单: {}", err);
+            return;
+        }
+    };
+
+    let name = name.map(|name| name.to_string()).unwrap_or_else(|| default_shortcut_name(&manifest, editions));
+    let mut shortcut = match Shortcut::for_package(package_path, &name) {
+        Ok(shortcut) => shortcut,
+        Err(err) => {
+            error!("无法创建快捷方式: {}", err);
+            return;
+        }
+    };
+    match prepare_icon(package_path, &manifest, &name) {
+        Ok(Some(icon)) => shortcut.icon = Some(icon),
+        Ok(None) => warning!("包内没有找到可用的图标，使用默认图标"),
+        Err(err) => warning!("无法生成图标，使用默认图标: {}", err),
+    }
+
+    match create_shortcut(&shortcut, kind, output_dir) {
+        Ok(path) => info!("快捷方式已创建: {}", path.display()),
+        Err(err) => error!("无法创建快捷方式: {}", err),
+    }
+}
+
+async fn handle_regpack(manifest: &AppxManifest, manifest_path: &str, options: &RegisterOptions) {
+    let identity = &manifest.identity;
+    info!("包名: {}", identity.name);
+    info!("包版本: {}", identity.version);
+
+    let Some(dependency_paths) = resolve_dependencies(manifest, &options.dependency_cache_dir) else {
+        error!("依赖不满足，已取消注册");
+        return;
+    };
+
+    let package_family_name = deploy::find_package_by_identity(&identity.name, &identity.publisher)
+        .ok()
+        .flatten()
+        .and_then(|package| deploy::package_family_name(&package).ok())
+        .unwrap_or_else(|| package_family_name_for(&identity.name, &identity.publisher));
+    let package_dir = Path::new(manifest_path).parent().unwrap_or(Path::new("."));
+    let context = hook_context(&identity.name, &identity.version, &package_family_name, package_dir);
+    // 只在真正要注册时执行 PreRegister 钩子
+    let pre_register = || match run_hooks(&options.hooks, &options.editions, HookStage::PreRegister, &context) {
+        Ok(()) => true,
+        Err(err) => {
+            error!("{}，已取消注册", err);
+            false
+        }
+    };
+
+    let mut previous_manifest_path = None;
+    match deploy::find_package_by_identity(&identity.name, &identity.publisher) {
+        Ok(Some(package)) => {
+            let version = deploy::package_version(&package).unwrap_or_default();
             debug!("Version: {:?}", version);
-            debug!("Package Family Name: {}", package_family_name);
-            debug!("Package Full Name: {}", package_full_name);
-
-            if let Some(identity_version_str) = identity_version {
-                if version == identity_version_str {
-                    debug!("版本匹配");
-                    if auto_start {
-                        if let Err(err) = start_minecraft_education_preview() {
-                            error!("启动失败: {}", err);
-                        }
+
+            if version == identity.version {
+                debug!("版本匹配");
+            } else {
+                info!("版本不匹配");
+                let names = deploy::package_full_name(&package)
+                    .and_then(|full_name| deploy::package_family_name(&package).map(|family_name| (full_name, family_name)));
+                let (package_full_name, package_family_name) = match names {
+                    Ok(names) => names,
+                    Err(err) => {
+                        error!("无法获取包名: {:?}", err);
+                        return;
                     }
-                } else {
-                    debug!("版本不匹配");
-                    handle_remove_package_register_start(&package_full_name, manifest_path, package_path, auto_start, "Microsoft.MinecraftEducationPreview").await;
+                };
+                debug!("Package Full Name: {}", package_full_name);
+                previous_manifest_path = deploy::package_installed_path(&package).ok().map(|path| path.join("AppxManifest.xml"));
+                // 商店或 MSIX 安装的目录在移除时会被删除，之后无法从中恢复
+                if options.wait.restore && !deploy::package_is_development_mode(&package).unwrap_or(false) {
+                    error!("当前安装的 {} 不是开发模式注册的，移除后无法恢复，已取消替换 (去掉 --restore 可继续)", version);
+                    return;
+                }
+
+                let Some(resolution) = stop_running_game(&package_family_name, options.running_policy) else {
+                    return;
+                };
+                // 钩子失败时旧版本还在，不能先移除
+                if !pre_register() {
+                    return;
+                }
+                if let Err(err) = deploy::remove_package(&package_full_name, true).await {
+                    error!("移除包失败: {}", err);
+                    return;
+                }
+                if !register(manifest_path, &dependency_paths, resolution == RunningResolution::ForceShutdown).await {
+                    return;
                 }
             }
         }
         Ok(None) => {
-            error!("无法获取包信息");
-            register_start(manifest_path, auto_start, "Microsoft.MinecraftEducationPreview").await;
+            debug!("没有注册过 appx");
+            if !pre_register() || !register(manifest_path, &dependency_paths, false).await {
+                return;
+            }
         }
         Err(err) => {
             error!("{:?}", err);
             debug!("没有注册过 appx");
-            register_start(manifest_path, auto_start, "Microsoft.MinecraftEducationPreview").await;
+            if !pre_register() || !register(manifest_path, &dependency_paths, false).await {
+                return;
+            }
+        }
+    }
+
+    if !options.auto_start {
+        return;
+    }
+    let Some(app_user_model_id) = package_app_user_model_id(manifest) else {
+        return;
+    };
+    let uri = match options.launch.uri(&protocol_scheme(&options.editions, &identity.name)) {
+        Ok(uri) => uri,
+        Err(err) => {
+            error!("{}", err);
+            return;
+        }
+    };
+
+    if !check_launch_compatibility(&context, options.block_incompatible) {
+        return;
+    }
+    if let Err(err) = run_hooks(&options.hooks, &options.editions, HookStage::PreLaunch, &context) {
+        error!("{}，已取消启动", err);
+        return;
+    }
+    let capture = start_log_capture(&app_user_model_id, &options.wait);
+    let started_at = Local::now();
+    let result = launch_app(&app_user_model_id, uri.as_ref(), &options.tuning);
+    if !options.wait.wait {
+        return;
+    }
+    let Some(report) = wait_for_game(&app_user_model_id, &result, started_at, &options.wait, capture, &context) else {
+        return;
+    };
+    if let Err(err) = run_hooks(&options.hooks, &options.editions, HookStage::PostExit, &context) {
+        error!("{}", err);
+        return;
+    }
+    if options.wait.restore {
+        match &previous_manifest_path {
+            // 退出码未知或非 0 时保留当前版本，以便查看问题
+            Some(previous_manifest_path) if !report.exited_normally() => {
+                warning!("游戏没有正常退出，未恢复之前的版本，可手动注册: {}", previous_manifest_path.display());
+            }
+            Some(previous_manifest_path) => restore_previous_version(manifest, previous_manifest_path).await,
+            None => info!("没有替换过版本，无需恢复"),
+        }
+    }
+}
+
+async fn handle_unregister(edition: &Edition, export_path: Option<&str>, wipe_data: bool, assume_yes: bool, running_policy: RunningPolicy) {
+    let package = match deploy::find_package(&edition.package_family_name()) {
+        Ok(Some(package)) => package,
+        Ok(None) => {
+            info!("{} 没有注册", edition.identity_name);
+            return;
+        }
+        Err(err) => {
+            error!("查询包失败: {:?}", err);
+            return;
+        }
+    };
+
+    let package_full_name = match deploy::package_full_name(&package) {
+        Ok(package_full_name) => package_full_name,
+        Err(err) => {
+            error!("无法获取包全名: {:?}", err);
+            return;
+        }
+    };
+
+    let preserve_data = export_path.is_none() && !wipe_data;
+    if !assume_yes {
+        let prompt = if preserve_data {
+            format!("确认移除 {}（保留应用数据）?", package_full_name)
+        } else {
+            format!("确认移除 {} 并删除应用数据?", package_full_name)
+        };
+        if !confirm(&prompt) {
+            info!("已取消");
+            return;
         }
     }
+
+    if stop_running_game(&edition.package_family_name(), running_policy).is_none() {
+        return;
+    }
+
+    if let Some(export_path) = export_path {
+        match edition.package_data_dir() {
+            Some(data_dir) if data_dir.exists() => {
+                info!("导出应用数据到: {}", export_path);
+                if let Err(err) = zip_dir(&data_dir, Path::new(export_path)) {
+                    error!("导出应用数据失败，已取消移除: {:?}", err);
+                    return;
+                }
+                info!("应用数据导出成功");
+            }
+            _ => info!("没有可导出的应用数据"),
+        }
+    }
+
+    if let Err(err) = deploy::remove_package(&package_full_name, preserve_data).await {
+        error!("移除包失败: {}", err);
+    }
 }
@@ -0,0 +1,122 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use xml::reader::{EventReader, XmlEvent};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageIdentity {
+    pub name: String,
+    pub publisher: String,
+    pub version: String,
+    pub processor_architecture: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ManifestApplication {
+    pub id: String,
+    pub executable: Option<String>,
+    pub square44x44_logo: Option<String>,
+    pub square150x150_logo: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageDependency {
+    pub name: String,
+    pub publisher: String,
+    pub min_version: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TargetDeviceFamily {
+    pub name: String,
+    pub min_version: String,
+    pub max_version_tested: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AppxManifest {
+    pub identity: PackageIdentity,
+    pub applications: Vec<ManifestApplication>,
+    pub dependencies: Vec<PackageDependency>,
+    pub target_device_families: Vec<TargetDeviceFamily>,
+    // Properties/Logo，商店图标的资源路径
+    pub logo: Option<String>,
+}
+
+impl AppxManifest {
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let xml_data = fs::read_to_string(path)?;
+        Self::parse(&xml_data)
+    }
+
+    pub fn parse(xml_data: &str) -> io::Result<Self> {
+        let mut manifest = AppxManifest::default();
+        let mut current_element = String::new();
+
+        for event in EventReader::from_str(xml_data) {
+            let event = event.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            match &event {
+                XmlEvent::Characters(text) if current_element == "Logo" => manifest.logo = Some(text.trim().to_string()),
+                XmlEvent::EndElement { .. } => current_element.clear(),
+                _ => {}
+            }
+            if let XmlEvent::StartElement { name, attributes, .. } = event {
+                current_element = name.local_name.clone();
+                let attr = |key: &str| {
+                    attributes.iter()
+                        .find(|attr| attr.name.local_name == key && attr.name.prefix.is_none())
+                        .map(|attr| attr.value.clone())
+                };
+
+                match name.local_name.as_str() {
+                    "Identity" => {
+                        manifest.identity = PackageIdentity {
+                            name: attr("Name").unwrap_or_default(),
+                            publisher: attr("Publisher").unwrap_or_default(),
+                            version: attr("Version").unwrap_or_default(),
+                            processor_architecture: attr("ProcessorArchitecture"),
+                        };
+                    }
+                    "Application" => {
+                        manifest.applications.push(ManifestApplication {
+                            id: attr("Id").unwrap_or_default(),
+                            executable: attr("Executable"),
+                            ..Default::default()
+                        });
+                    }
+                    "VisualElements" => {
+                        if let Some(application) = manifest.applications.last_mut() {
+                            application.square44x44_logo = attr("Square44x44Logo");
+                            application.square150x150_logo = attr("Square150x150Logo");
+                        }
+                    }
+                    "PackageDependency" => {
+                        manifest.dependencies.push(PackageDependency {
+                            name: attr("Name").unwrap_or_default(),
+                            publisher: attr("Publisher").unwrap_or_default(),
+                            min_version: attr("MinVersion").unwrap_or_default(),
+                        });
+                    }
+                    "TargetDeviceFamily" => {
+                        manifest.target_device_families.push(TargetDeviceFamily {
+                            name: attr("Name").unwrap_or_default(),
+                            min_version: attr("MinVersion").unwrap_or_default(),
+                            max_version_tested: attr("MaxVersionTested").unwrap_or_default(),
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if manifest.identity.name.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "清单缺少 Identity Name"));
+        }
+        Ok(manifest)
+    }
+
+    pub fn app_id(&self) -> Option<&str> {
+        self.applications.first().map(|application| application.id.as_str())
+    }
+}
@@ -0,0 +1,398 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+// 基岩版的 NBT 全部是小端序
+pub const TAG_END: u8 = 0;
+pub const TAG_BYTE: u8 = 1;
+pub const TAG_SHORT: u8 = 2;
+pub const TAG_INT: u8 = 3;
+pub const TAG_LONG: u8 = 4;
+pub const TAG_FLOAT: u8 = 5;
+pub const TAG_DOUBLE: u8 = 6;
+pub const TAG_BYTE_ARRAY: u8 = 7;
+pub const TAG_STRING: u8 = 8;
+pub const TAG_LIST: u8 = 9;
+pub const TAG_COMPOUND: u8 = 10;
+pub const TAG_INT_ARRAY: u8 = 11;
+pub const TAG_LONG_ARRAY: u8 = 12;
+
+const MAX_DEPTH: usize = 512;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    // 空列表也要保留元素类型，写回时才能和原文件一致
+    List(u8, Vec<Tag>),
+    // 保持原来的顺序
+    Compound(Vec<(String, Tag)>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    pub fn tag_type(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => TAG_BYTE,
+            Tag::Short(_) => TAG_SHORT,
+            Tag::Int(_) => TAG_INT,
+            Tag::Long(_) => TAG_LONG,
+            Tag::Float(_) => TAG_FLOAT,
+            Tag::Double(_) => TAG_DOUBLE,
+            Tag::ByteArray(_) => TAG_BYTE_ARRAY,
+            Tag::String(_) => TAG_STRING,
+            Tag::List(_, _) => TAG_LIST,
+            Tag::Compound(_) => TAG_COMPOUND,
+            Tag::IntArray(_) => TAG_INT_ARRAY,
+            Tag::LongArray(_) => TAG_LONG_ARRAY,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(entries) => entries.iter().find(|(key, _)| key == name).map(|(_, tag)| tag),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Tag::Byte(value) => Some(*value as i64),
+            Tag::Short(value) => Some(*value as i64),
+            Tag::Int(value) => Some(*value as i64),
+            Tag::Long(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(_, items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&[(String, Tag)]> {
+        match self {
+            Tag::Compound(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Tag> {
+        match self {
+            Tag::Compound(entries) => entries.iter_mut().find(|(key, _)| key == name).map(|(_, tag)| tag),
+            _ => None,
+        }
+    }
+
+    // 已存在时替换，否则追加到末尾
+    pub fn insert(&mut self, name: &str, tag: Tag) -> bool {
+        let Tag::Compound(entries) = self else {
+            return false;
+        };
+        match entries.iter_mut().find(|(key, _)| key == name) {
+            Some((_, existing)) => *existing = tag,
+            None => entries.push((name.to_string(), tag)),
+        }
+        true
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_bytes<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut buffer = [0u8; N];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn read_length(reader: &mut impl Read) -> io::Result<usize> {
+    let length = i32::from_le_bytes(read_bytes(reader)?);
+    usize::try_from(length).map_err(|_| invalid_data(format!("无效的长度: {}", length)))
+}
+
+pub fn read_string(reader: &mut impl Read) -> io::Result<String> {
+    let length = u16::from_le_bytes(read_bytes(reader)?) as usize;
+    let mut buffer = vec![0u8; length];
+    reader.read_exact(&mut buffer)?;
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
+
+fn read_array<T, const N: usize>(reader: &mut impl Read, convert: fn([u8; N]) -> T) -> io::Result<Vec<T>> {
+    let length = read_length(reader)?;
+    // 长度来自文件，先不按它预分配，避免损坏的文件申请过多内存
+    let mut items = Vec::new();
+    for _ in 0..length {
+        items.push(convert(read_bytes(reader)?));
+    }
+    Ok(items)
+}
+
+pub fn read_payload(reader: &mut impl Read, tag_type: u8, depth: usize) -> io::Result<Tag> {
+    if depth > MAX_DEPTH {
+        return Err(invalid_data("NBT 嵌套层数过多".to_string()));
+    }
+
+    Ok(match tag_type {
+        TAG_BYTE => Tag::Byte(i8::from_le_bytes(read_bytes(reader)?)),
+        TAG_SHORT => Tag::Short(i16::from_le_bytes(read_bytes(reader)?)),
+        TAG_INT => Tag::Int(i32::from_le_bytes(read_bytes(reader)?)),
+        TAG_LONG => Tag::Long(i64::from_le_bytes(read_bytes(reader)?)),
+        TAG_FLOAT => Tag::Float(f32::from_le_bytes(read_bytes(reader)?)),
+        TAG_DOUBLE => Tag::Double(f64::from_le_bytes(read_bytes(reader)?)),
+        TAG_BYTE_ARRAY => Tag::ByteArray(read_array(reader, i8::from_le_bytes)?),
+        TAG_STRING => Tag::String(read_string(reader)?),
+        TAG_LIST => {
+            let element_type = read_bytes::<1>(reader)?[0];
+            let length = read_length(reader)?;
+            let mut items = Vec::new();
+            for _ in 0..length {
+                items.push(read_payload(reader, element_type, depth + 1)?);
+            }
+            Tag::List(element_type, items)
+        }
+        TAG_COMPOUND => {
+            let mut entries = Vec::new();
+            loop {
+                let entry_type = read_bytes::<1>(reader)?[0];
+                if entry_type == TAG_END {
+                    break;
+                }
+                let name = read_string(reader)?;
+                entries.push((name, read_payload(reader, entry_type, depth + 1)?));
+            }
+            Tag::Compound(entries)
+        }
+        TAG_INT_ARRAY => Tag::IntArray(read_array(reader, i32::from_le_bytes)?),
+        TAG_LONG_ARRAY => Tag::LongArray(read_array(reader, i64::from_le_bytes)?),
+        _ => return Err(invalid_data(format!("未知的 NBT 类型: {}", tag_type))),
+    })
+}
+
+pub fn read_named_tag(reader: &mut impl Read) -> io::Result<(String, Tag)> {
+    let tag_type = read_bytes::<1>(reader)?[0];
+    if tag_type == TAG_END {
+        return Err(invalid_data("根标签不能为空".to_string()));
+    }
+    let name = read_string(reader)?;
+    Ok((name, read_payload(reader, tag_type, 0)?))
+}
+
+pub fn write_string(writer: &mut impl Write, value: &str) -> io::Result<()> {
+    let length = u16::try_from(value.len()).map_err(|_| invalid_data(format!("字符串过长: {} 字节", value.len())))?;
+    writer.write_all(&length.to_le_bytes())?;
+    writer.write_all(value.as_bytes())
+}
+
+fn write_length(writer: &mut impl Write, length: usize) -> io::Result<()> {
+    let length = i32::try_from(length).map_err(|_| invalid_data(format!("数组过长: {}", length)))?;
+    writer.write_all(&length.to_le_bytes())
+}
+
+pub fn write_payload(writer: &mut impl Write, tag: &Tag) -> io::Result<()> {
+    match tag {
+        Tag::Byte(value) => writer.write_all(&value.to_le_bytes()),
+        Tag::Short(value) => writer.write_all(&value.to_le_bytes()),
+        Tag::Int(value) => writer.write_all(&value.to_le_bytes()),
+        Tag::Long(value) => writer.write_all(&value.to_le_bytes()),
+        Tag::Float(value) => writer.write_all(&value.to_le_bytes()),
+        Tag::Double(value) => writer.write_all(&value.to_le_bytes()),
+        Tag::ByteArray(values) => {
+            write_length(writer, values.len())?;
+            values.iter().try_for_each(|value| writer.write_all(&value.to_le_bytes()))
+        }
+        Tag::String(value) => write_string(writer, value),
+        Tag::List(element_type, items) => {
+            if let Some(item) = items.iter().find(|item| item.tag_type() != *element_type) {
+                return Err(invalid_data(format!("列表元素类型不一致: {} 和 {}", element_type, item.tag_type())));
+            }
+            writer.write_all(&[*element_type])?;
+            write_length(writer, items.len())?;
+            items.iter().try_for_each(|item| write_payload(writer, item))
+        }
+        Tag::Compound(entries) => {
+            for (name, tag) in entries {
+                writer.write_all(&[tag.tag_type()])?;
+                write_string(writer, name)?;
+                write_payload(writer, tag)?;
+            }
+            writer.write_all(&[TAG_END])
+        }
+        Tag::IntArray(values) => {
+            write_length(writer, values.len())?;
+            values.iter().try_for_each(|value| writer.write_all(&value.to_le_bytes()))
+        }
+        Tag::LongArray(values) => {
+            write_length(writer, values.len())?;
+            values.iter().try_for_each(|value| writer.write_all(&value.to_le_bytes()))
+        }
+    }
+}
+
+pub fn write_named_tag(writer: &mut impl Write, name: &str, tag: &Tag) -> io::Result<()> {
+    writer.write_all(&[tag.tag_type()])?;
+    write_string(writer, name)?;
+    write_payload(writer, tag)
+}
+
+// level.dat: 4 字节存储版本 + 4 字节数据长度 + 根 compound
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelDat {
+    pub storage_version: i32,
+    pub root_name: String,
+    pub root: Tag,
+}
+
+impl LevelDat {
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read(path)?)
+    }
+
+    pub fn parse(data: &[u8]) -> io::Result<Self> {
+        if data.len() < 8 {
+            return Err(invalid_data("level.dat 太短".to_string()));
+        }
+        let storage_version = i32::from_le_bytes(data[0..4].try_into().unwrap_or_default());
+        let length = u32::from_le_bytes(data[4..8].try_into().unwrap_or_default());
+        // 32 位平台上 8 + length 可能溢出
+        let payload = usize::try_from(length).ok()
+            .and_then(|length| 8usize.checked_add(length))
+            .and_then(|end| data.get(8..end))
+            .ok_or_else(|| invalid_data(format!("level.dat 长度不符: 头部记录 {}，实际 {}", length, data.len() - 8)))?;
+
+        let (root_name, root) = read_named_tag(&mut &payload[..])?;
+        if root.tag_type() != TAG_COMPOUND {
+            return Err(invalid_data("level.dat 的根标签不是 compound".to_string()));
+        }
+        Ok(LevelDat { storage_version, root_name, root })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        self.root.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Tag> {
+        self.root.get_mut(name)
+    }
+
+    // 头部的数据长度按写出的内容重新计算
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut payload = Vec::new();
+        write_named_tag(&mut payload, &self.root_name, &self.root)?;
+        let length = u32::try_from(payload.len()).map_err(|_| invalid_data("level.dat 过大".to_string()))?;
+
+        let mut data = Vec::with_capacity(payload.len() + 8);
+        data.extend_from_slice(&self.storage_version.to_le_bytes());
+        data.extend_from_slice(&length.to_le_bytes());
+        data.extend_from_slice(&payload);
+        Ok(data)
+    }
+
+    // 先写临时文件再替换，避免写到一半损坏存档
+    pub fn write_to_file(&self, path: &Path) -> io::Result<()> {
+        let temp_path = path.with_extension("dat.tmp");
+        fs::write(&temp_path, self.to_bytes()?)?;
+        fs::rename(&temp_path, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &[u8] = include_bytes!("../tests/fixtures/level.dat");
+
+    #[test]
+    fn sample_round_trips_byte_for_byte() {
+        let level = LevelDat::parse(SAMPLE).unwrap();
+        assert_eq!(level.storage_version, 10);
+        assert_eq!(level.get("LevelName").and_then(Tag::as_str), Some("测试世界 QA"));
+        assert_eq!(level.to_bytes().unwrap(), SAMPLE);
+    }
+
+    #[test]
+    fn header_length_follows_payload() {
+        let mut level = LevelDat::parse(SAMPLE).unwrap();
+        level.root.insert("LevelName", Tag::String("一个更长一些的世界名称".to_string()));
+        let data = level.to_bytes().unwrap();
+
+        let length = u32::from_le_bytes(data[4..8].try_into().unwrap()) as usize;
+        assert_eq!(length, data.len() - 8);
+        assert_eq!(LevelDat::parse(&data).unwrap(), level);
+    }
+
+    fn level_dat(payload: &[u8]) -> Vec<u8> {
+        let mut data = 10i32.to_le_bytes().to_vec();
+        data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        data.extend_from_slice(payload);
+        data
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        assert!(LevelDat::parse(&SAMPLE[..7]).is_err());
+        // 头部记录的长度超过实际数据
+        assert!(LevelDat::parse(&SAMPLE[..SAMPLE.len() - 1]).is_err());
+        let mut data = SAMPLE.to_vec();
+        data[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(LevelDat::parse(&data).is_err());
+        // 长度一致但内容被截断
+        for end in 8..SAMPLE.len() {
+            assert!(LevelDat::parse(&level_dat(&SAMPLE[8..end])).is_err(), "{}", end);
+        }
+    }
+
+    #[test]
+    fn rejects_negative_lengths() {
+        // 根 compound 中名为 "l" 的 int 列表，长度为 -1
+        let mut payload = vec![TAG_COMPOUND, 0, 0, TAG_LIST, 1, 0, b'l', TAG_INT];
+        payload.extend_from_slice(&(-1i32).to_le_bytes());
+        assert_eq!(LevelDat::parse(&level_dat(&payload)).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        let mut payload = vec![TAG_COMPOUND, 0, 0, TAG_INT_ARRAY, 1, 0, b'a'];
+        payload.extend_from_slice(&i32::MIN.to_le_bytes());
+        assert_eq!(LevelDat::parse(&level_dat(&payload)).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_excessive_depth() {
+        // 每层是只有一个元素的 list，嵌套超过 MAX_DEPTH
+        let mut payload = vec![TAG_COMPOUND, 0, 0, TAG_LIST, 1, 0, b'l'];
+        for _ in 0..MAX_DEPTH + 8 {
+            payload.push(TAG_LIST);
+            payload.extend_from_slice(&1i32.to_le_bytes());
+        }
+        let err = LevelDat::parse(&level_dat(&payload)).unwrap_err();
+        assert!(err.to_string().contains("嵌套"));
+    }
+
+    #[test]
+    fn rejects_unknown_tag_types() {
+        let payload = [TAG_COMPOUND, 0, 0, 13, 1, 0, b'x', 0];
+        assert!(LevelDat::parse(&level_dat(&payload)).is_err());
+        assert!(LevelDat::parse(&level_dat(&[TAG_INT, 0, 0, 1, 0, 0, 0])).is_err());
+    }
+
+    #[test]
+    fn rejects_mismatched_list() {
+        let tag = Tag::List(TAG_INT, vec![Tag::Int(1), Tag::Byte(2)]);
+        assert!(write_payload(&mut Vec::new(), &tag).is_err());
+    }
+}
@@ -0,0 +1,139 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::io;
+
+use super::tuning::invalid_affinity;
+use super::{PriorityClass, ProcessInfo, ProcessQuery};
+
+const DEFAULT_CPU_COUNT: u32 = 8;
+
+// 测试用的进程后端，close/kill 只是把进程从列表里移除，优先级和亲和性只做记录
+#[derive(Debug)]
+pub struct FakeProcessQuery {
+    processes: RefCell<Vec<ProcessInfo>>,
+    ignore_close: bool,
+    // 这些进程没有窗口，close 返回错误
+    windowless: Vec<u32>,
+    // 查询这么多次之后所有进程自行退出
+    exit_after: Cell<Option<u32>>,
+    cpu_count: u32,
+    priorities: RefCell<HashMap<u32, PriorityClass>>,
+    affinities: RefCell<HashMap<u32, u64>>,
+}
+
+impl Default for FakeProcessQuery {
+    fn default() -> Self {
+        FakeProcessQuery {
+            processes: RefCell::default(),
+            ignore_close: false,
+            windowless: Vec::new(),
+            exit_after: Cell::new(None),
+            cpu_count: DEFAULT_CPU_COUNT,
+            priorities: RefCell::default(),
+            affinities: RefCell::default(),
+        }
+    }
+}
+
+impl FakeProcessQuery {
+    pub fn new(processes: Vec<ProcessInfo>) -> Self {
+        FakeProcessQuery { processes: RefCell::new(processes), ..Default::default() }
+    }
+
+    // 模拟的逻辑 CPU 数量，超出范围的亲和性掩码会被拒绝
+    pub fn with_cpu_count(mut self, cpu_count: u32) -> Self {
+        self.cpu_count = cpu_count;
+        self
+    }
+
+    pub fn priority(&self, pid: u32) -> Option<PriorityClass> {
+        self.priorities.borrow().get(&pid).copied()
+    }
+
+    pub fn affinity(&self, pid: u32) -> Option<u64> {
+        self.affinities.borrow().get(&pid).copied()
+    }
+
+    fn ensure_exists(&self, pid: u32) -> io::Result<()> {
+        if !self.processes.borrow().iter().any(|process| process.pid == pid) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("进程不存在: {}", pid)));
+        }
+        Ok(())
+    }
+
+    // 模拟不响应关闭请求的进程
+    pub fn ignoring_close(mut self) -> Self {
+        self.ignore_close = true;
+        self
+    }
+
+    pub fn without_window(mut self, pid: u32) -> Self {
+        self.windowless.push(pid);
+        self
+    }
+
+    pub fn exiting_after(self, queries: u32) -> Self {
+        self.exit_after.set(Some(queries));
+        self
+    }
+
+    pub fn spawn(&self, process: ProcessInfo) {
+        self.processes.borrow_mut().push(process);
+    }
+
+    fn remove(&self, pid: u32) -> io::Result<()> {
+        let mut processes = self.processes.borrow_mut();
+        let count = processes.len();
+        processes.retain(|process| process.pid != pid);
+        if processes.len() == count {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("进程不存在: {}", pid)));
+        }
+        Ok(())
+    }
+}
+
+impl ProcessQuery for FakeProcessQuery {
+    fn processes(&self) -> io::Result<Vec<ProcessInfo>> {
+        match self.exit_after.get() {
+            Some(0) => self.processes.borrow_mut().clear(),
+            Some(queries) => self.exit_after.set(Some(queries - 1)),
+            None => {}
+        }
+        Ok(self.processes.borrow().clone())
+    }
+
+    fn close(&self, pid: u32) -> io::Result<()> {
+        if self.windowless.contains(&pid) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("进程 {} 没有可关闭的窗口", pid)));
+        }
+        if self.ignore_close {
+            return Ok(());
+        }
+        self.remove(pid)
+    }
+
+    fn kill(&self, pid: u32) -> io::Result<()> {
+        self.remove(pid)
+    }
+
+    fn wait_for_exit(&self, pid: u32) -> io::Result<Option<u32>> {
+        self.remove(pid)?;
+        Ok(Some(0))
+    }
+
+    fn set_priority(&self, pid: u32, priority: PriorityClass) -> io::Result<()> {
+        self.ensure_exists(pid)?;
+        self.priorities.borrow_mut().insert(pid, priority);
+        Ok(())
+    }
+
+    fn set_affinity(&self, pid: u32, mask: u64) -> io::Result<()> {
+        self.ensure_exists(pid)?;
+        let available = if self.cpu_count >= 64 { u64::MAX } else { (1u64 << self.cpu_count) - 1 };
+        if mask & !available != 0 || mask == 0 {
+            return Err(invalid_affinity(mask));
+        }
+        self.affinities.borrow_mut().insert(pid, mask);
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+use std::io;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::warning;
+
+#[cfg(test)]
+mod fake;
+pub mod tuning;
+#[cfg(windows)]
+mod win32;
+
+#[cfg(test)]
+pub use fake::FakeProcessQuery;
+pub use tuning::{PriorityClass, ProcessTuning};
+#[cfg(windows)]
+pub use win32::WindowsProcessQuery;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub package_family_name: Option<String>,
+}
+
+pub trait ProcessQuery {
+    fn processes(&self) -> io::Result<Vec<ProcessInfo>>;
+
+    // 请求进程自行退出（关闭窗口），不等待结果
+    fn close(&self, pid: u32) -> io::Result<()>;
+
+    fn kill(&self, pid: u32) -> io::Result<()>;
+
+    // 阻塞直到进程退出，能取到时返回退出码
+    fn wait_for_exit(&self, pid: u32) -> io::Result<Option<u32>>;
+
+    fn set_priority(&self, pid: u32, priority: PriorityClass) -> io::Result<()>;
+
+    // 每一位对应一个逻辑 CPU
+    fn set_affinity(&self, pid: u32, mask: u64) -> io::Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RunningPolicy {
+    #[default]
+    Refuse,
+    Wait,
+    Close,
+    ForceClose,
+}
+
+impl FromStr for RunningPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "refuse" => Ok(RunningPolicy::Refuse),
+            "wait" => Ok(RunningPolicy::Wait),
+            "close" => Ok(RunningPolicy::Close),
+            "force" => Ok(RunningPolicy::ForceClose),
+            _ => Err(format!("未知的运行中处理方式: {} (可选 refuse, wait, close, force)", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunningResolution {
+    NotRunning,
+    Stopped,
+    // 已强制结束，注册时还需要带上 ForceTargetApplicationShutdown
+    ForceShutdown,
+    Refused(Vec<ProcessInfo>),
+}
+
+pub fn package_processes(query: &dyn ProcessQuery, package_family_name: &str) -> io::Result<Vec<ProcessInfo>> {
+    Ok(query.processes()?
+        .into_iter()
+        .filter(|process| {
+            process.package_family_name.as_deref()
+                .map(|name| name.eq_ignore_ascii_case(package_family_name))
+                .unwrap_or(false)
+        })
+        .collect())
+}
+
+pub fn wait_for_package_exit(query: &dyn ProcessQuery, package_family_name: &str, timeout: Option<Duration>, poll_interval: Duration) -> io::Result<bool> {
+    let started = Instant::now();
+    loop {
+        if package_processes(query, package_family_name)?.is_empty() {
+            return Ok(true);
+        }
+        if timeout.map(|timeout| started.elapsed() >= timeout).unwrap_or(false) {
+            return Ok(false);
+        }
+        thread::sleep(poll_interval);
+    }
+}
+
+pub fn resolve_running(query: &dyn ProcessQuery, package_family_name: &str, policy: RunningPolicy, timeout: Duration, poll_interval: Duration) -> io::Result<RunningResolution> {
+    let running = package_processes(query, package_family_name)?;
+    if running.is_empty() {
+        return Ok(RunningResolution::NotRunning);
+    }
+
+    match policy {
+        RunningPolicy::Refuse => Ok(RunningResolution::Refused(running)),
+        RunningPolicy::Wait => {
+            if wait_for_package_exit(query, package_family_name, Some(timeout), poll_interval)? {
+                Ok(RunningResolution::Stopped)
+            } else {
+                Ok(RunningResolution::Refused(package_processes(query, package_family_name)?))
+            }
+        }
+        RunningPolicy::Close => {
+            // 没有窗口的进程无法请求关闭，跳过它，超时后和没有退出的进程一起强制结束
+            let mut requested = false;
+            for process in &running {
+                match query.close(process.pid) {
+                    Ok(()) => requested = true,
+                    Err(err) => warning!("无法请求 {} (PID {}) 关闭: {}", process.name, process.pid, err),
+                }
+            }
+            if requested && wait_for_package_exit(query, package_family_name, Some(timeout), poll_interval)? {
+                return Ok(RunningResolution::Stopped);
+            }
+            let remaining = package_processes(query, package_family_name)?;
+            warning!("游戏没有正常关闭，强制结束 {} 个进程", remaining.len());
+            kill_all(query, &remaining)
+        }
+        RunningPolicy::ForceClose => kill_all(query, &running),
+    }
+}
+
+fn kill_all(query: &dyn ProcessQuery, processes: &[ProcessInfo]) -> io::Result<RunningResolution> {
+    for process in processes {
+        query.kill(process.pid)?;
+    }
+    Ok(RunningResolution::ForceShutdown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAMILY: &str = "Microsoft.MinecraftUWP_8wekyb3d8bbwe";
+    const TIMEOUT: Duration = Duration::from_millis(50);
+    const POLL: Duration = Duration::from_millis(1);
+
+    fn game(pid: u32) -> ProcessInfo {
+        ProcessInfo { pid, name: "Minecraft.Windows.exe".to_string(), package_family_name: Some(FAMILY.to_string()) }
+    }
+
+    fn other(pid: u32) -> ProcessInfo {
+        ProcessInfo { pid, name: "explorer.exe".to_string(), package_family_name: None }
+    }
+
+    fn resolve(query: &FakeProcessQuery, policy: RunningPolicy) -> RunningResolution {
+        resolve_running(query, FAMILY, policy, TIMEOUT, POLL).unwrap()
+    }
+
+    #[test]
+    fn other_packages_are_not_running() {
+        let query = FakeProcessQuery::new(vec![other(1), ProcessInfo { package_family_name: Some("Other_abc".to_string()), ..game(2) }]);
+        assert_eq!(resolve(&query, RunningPolicy::Refuse), RunningResolution::NotRunning);
+    }
+
+    #[test]
+    fn refuse_lists_running_processes() {
+        let query = FakeProcessQuery::new(vec![other(1), game(2)]);
+        assert_eq!(resolve(&query, RunningPolicy::Refuse), RunningResolution::Refused(vec![game(2)]));
+        assert_eq!(query.processes().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn wait_stops_when_game_exits() {
+        let query = FakeProcessQuery::new(vec![game(2)]).exiting_after(3);
+        assert_eq!(resolve(&query, RunningPolicy::Wait), RunningResolution::Stopped);
+    }
+
+    #[test]
+    fn wait_refuses_after_timeout() {
+        let query = FakeProcessQuery::new(vec![game(2)]);
+        assert_eq!(resolve(&query, RunningPolicy::Wait), RunningResolution::Refused(vec![game(2)]));
+    }
+
+    #[test]
+    fn close_stops_gracefully() {
+        let query = FakeProcessQuery::new(vec![other(1), game(2), game(3)]);
+        assert_eq!(resolve(&query, RunningPolicy::Close), RunningResolution::Stopped);
+        assert_eq!(query.processes().unwrap(), vec![other(1)]);
+    }
+
+    #[test]
+    fn close_falls_back_to_force_when_ignored() {
+        let query = FakeProcessQuery::new(vec![game(2)]).ignoring_close();
+        assert_eq!(resolve(&query, RunningPolicy::Close), RunningResolution::ForceShutdown);
+        assert!(query.processes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn close_failure_for_one_process_does_not_abort() {
+        let query = FakeProcessQuery::new(vec![game(2), game(3)]).without_window(3);
+        assert_eq!(resolve(&query, RunningPolicy::Close), RunningResolution::ForceShutdown);
+        assert!(query.processes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn force_kills_only_package_processes() {
+        let query = FakeProcessQuery::new(vec![other(1), game(2)]);
+        assert_eq!(resolve(&query, RunningPolicy::ForceClose), RunningResolution::ForceShutdown);
+        assert_eq!(query.processes().unwrap(), vec![other(1)]);
+    }
+
+    #[test]
+    fn policy_names_parse() {
+        assert_eq!("force".parse::<RunningPolicy>(), Ok(RunningPolicy::ForceClose));
+        assert_eq!("WAIT".parse::<RunningPolicy>(), Ok(RunningPolicy::Wait));
+        assert!("kill".parse::<RunningPolicy>().is_err());
+    }
+}
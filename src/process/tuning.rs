@@ -0,0 +1,180 @@
+#[cfg(any(windows, test))]
+use std::io;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::ProcessQuery;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriorityClass {
+    Idle,
+    BelowNormal,
+    Normal,
+    AboveNormal,
+    High,
+    Realtime,
+}
+
+impl PriorityClass {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PriorityClass::Idle => "idle",
+            PriorityClass::BelowNormal => "below_normal",
+            PriorityClass::Normal => "normal",
+            PriorityClass::AboveNormal => "above_normal",
+            PriorityClass::High => "high",
+            PriorityClass::Realtime => "realtime",
+        }
+    }
+}
+
+impl FromStr for PriorityClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "idle" | "low" => Ok(PriorityClass::Idle),
+            "below_normal" => Ok(PriorityClass::BelowNormal),
+            "normal" => Ok(PriorityClass::Normal),
+            "above_normal" => Ok(PriorityClass::AboveNormal),
+            "high" => Ok(PriorityClass::High),
+            "realtime" => Ok(PriorityClass::Realtime),
+            _ => Err(format!("未知的优先级: {} (可选 idle, below_normal, normal, above_normal, high, realtime)", s)),
+        }
+    }
+}
+
+// 支持十六进制掩码 (0xF) 或 CPU 编号列表 (0-3,6)
+pub fn parse_affinity_mask(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let mask = if let Some(hex) = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16).map_err(|_| format!("无效的亲和性掩码: {}", value))?
+    } else {
+        let mut mask = 0u64;
+        for part in value.split(',').map(|part| part.trim()).filter(|part| !part.is_empty()) {
+            let (first, last) = part.split_once('-').unwrap_or((part, part));
+            let parse_cpu = |cpu: &str| match cpu.trim().parse::<u32>() {
+                Ok(cpu) if cpu < 64 => Ok(cpu),
+                _ => Err(format!("无效的 CPU 编号: {} (范围 0-63)", cpu.trim())),
+            };
+            let (first, last) = (parse_cpu(first)?, parse_cpu(last)?);
+            if first > last {
+                return Err(format!("无效的 CPU 范围: {}", part));
+            }
+            for cpu in first..=last {
+                mask |= 1 << cpu;
+            }
+        }
+        mask
+    };
+
+    if mask == 0 {
+        return Err(format!("亲和性掩码不能为空: {}", value));
+    }
+    Ok(mask)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProcessTuning {
+    pub priority: Option<PriorityClass>,
+    pub affinity: Option<u64>,
+}
+
+impl ProcessTuning {
+    pub fn is_empty(&self) -> bool {
+        self.priority.is_none() && self.affinity.is_none()
+    }
+}
+
+// 两项分别设置，返回各自的错误，一项失败不影响另一项
+pub fn apply_tuning(query: &dyn ProcessQuery, pid: u32, tuning: &ProcessTuning) -> Vec<String> {
+    let mut errors = Vec::new();
+    if let Some(priority) = tuning.priority {
+        if let Err(err) = query.set_priority(pid, priority) {
+            errors.push(format!("无法设置优先级 {}: {}", priority.name(), err));
+        }
+    }
+    if let Some(affinity) = tuning.affinity {
+        if let Err(err) = query.set_affinity(pid, affinity) {
+            errors.push(format!("无法设置亲和性 0x{:X}: {}", affinity, err));
+        }
+    }
+    errors
+}
+
+#[cfg(any(windows, test))]
+pub(super) fn invalid_affinity(mask: u64) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, format!("亲和性掩码 0x{:X} 包含本机不存在的 CPU", mask))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::{FakeProcessQuery, ProcessInfo};
+
+    fn game() -> FakeProcessQuery {
+        FakeProcessQuery::new(vec![ProcessInfo { pid: 42, name: "Minecraft.Windows.exe".to_string(), package_family_name: None }])
+    }
+
+    #[test]
+    fn parses_hex_masks() {
+        assert_eq!(parse_affinity_mask("0xF"), Ok(0xF));
+        assert_eq!(parse_affinity_mask(" 0X30 "), Ok(0x30));
+        assert!(parse_affinity_mask("0xZZ").is_err());
+        assert!(parse_affinity_mask("0x0").is_err());
+    }
+
+    #[test]
+    fn parses_cpu_lists() {
+        assert_eq!(parse_affinity_mask("0-3,6"), Ok(0b100_1111));
+        assert_eq!(parse_affinity_mask("2, 2, 5"), Ok(0b10_0100));
+        assert_eq!(parse_affinity_mask("63"), Ok(1 << 63));
+        assert!(parse_affinity_mask("3-1").is_err());
+        assert!(parse_affinity_mask("").is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_range_cpus() {
+        assert!(parse_affinity_mask("64").is_err());
+        assert!(parse_affinity_mask("0-64").is_err());
+        assert!(parse_affinity_mask("-1").is_err());
+    }
+
+    #[test]
+    fn applies_priority_and_affinity() {
+        let query = game();
+        let tuning = ProcessTuning { priority: Some(PriorityClass::High), affinity: Some(0b11) };
+        assert!(apply_tuning(&query, 42, &tuning).is_empty());
+        assert_eq!(query.priority(42), Some(PriorityClass::High));
+        assert_eq!(query.affinity(42), Some(0b11));
+    }
+
+    #[test]
+    fn affinity_failure_does_not_block_priority() {
+        let query = game().with_cpu_count(4);
+        let tuning = ProcessTuning { priority: Some(PriorityClass::BelowNormal), affinity: Some(0x10) };
+        let errors = apply_tuning(&query, 42, &tuning);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("0x10"));
+        assert_eq!(query.priority(42), Some(PriorityClass::BelowNormal));
+        assert_eq!(query.affinity(42), None);
+    }
+
+    #[test]
+    fn missing_process_reports_both_errors() {
+        let query = game();
+        let tuning = ProcessTuning { priority: Some(PriorityClass::Idle), affinity: Some(1) };
+        assert_eq!(apply_tuning(&query, 7, &tuning).len(), 2);
+        assert!(apply_tuning(&query, 7, &ProcessTuning::default()).is_empty());
+    }
+
+    #[test]
+    fn priority_names_round_trip() {
+        for priority in [PriorityClass::Idle, PriorityClass::BelowNormal, PriorityClass::Normal, PriorityClass::AboveNormal, PriorityClass::High, PriorityClass::Realtime] {
+            assert_eq!(priority.name().parse::<PriorityClass>(), Ok(priority));
+        }
+        assert_eq!("above-normal".parse::<PriorityClass>(), Ok(PriorityClass::AboveNormal));
+    }
+}
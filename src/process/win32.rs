@@ -0,0 +1,167 @@
+use std::io;
+use std::mem::size_of;
+
+use windows::core::PWSTR;
+use windows::Win32::Foundation::{CloseHandle, BOOL, ERROR_SUCCESS, HANDLE, HWND, LPARAM, WAIT_OBJECT_0, WPARAM};
+use windows::Win32::Storage::Packaging::Appx::GetPackageFamilyName;
+use windows::Win32::System::Diagnostics::ToolHelp::{CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS};
+use windows::Win32::System::Threading::{
+    GetExitCodeProcess, GetProcessAffinityMask, OpenProcess, SetPriorityClass, SetProcessAffinityMask, TerminateProcess, WaitForSingleObject,
+    ABOVE_NORMAL_PRIORITY_CLASS, BELOW_NORMAL_PRIORITY_CLASS, HIGH_PRIORITY_CLASS, IDLE_PRIORITY_CLASS, INFINITE, NORMAL_PRIORITY_CLASS,
+    PROCESS_CREATION_FLAGS, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SET_INFORMATION, PROCESS_SYNCHRONIZE, PROCESS_TERMINATE, REALTIME_PRIORITY_CLASS,
+};
+use windows::Win32::UI::WindowsAndMessaging::{EnumChildWindows, EnumWindows, GetWindowThreadProcessId, PostMessageW, WM_CLOSE};
+
+use super::tuning::invalid_affinity;
+use super::{PriorityClass, ProcessInfo, ProcessQuery};
+
+pub struct WindowsProcessQuery;
+
+pub(super) struct OwnedHandle(pub(super) HANDLE);
+
+impl Drop for OwnedHandle {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.0);
+        }
+    }
+}
+
+pub(super) fn to_io_error(err: windows::core::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err)
+}
+
+fn from_wide(buffer: &[u16]) -> String {
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    String::from_utf16_lossy(&buffer[..len])
+}
+
+fn package_family_name(pid: u32) -> Option<String> {
+    unsafe {
+        let process = OwnedHandle(OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?);
+        let mut buffer = [0u16; 256];
+        let mut length = buffer.len() as u32;
+        if GetPackageFamilyName(process.0, &mut length, PWSTR(buffer.as_mut_ptr())) != ERROR_SUCCESS {
+            return None;
+        }
+        Some(from_wide(&buffer))
+    }
+}
+
+fn priority_class_flags(priority: PriorityClass) -> PROCESS_CREATION_FLAGS {
+    match priority {
+        PriorityClass::Idle => IDLE_PRIORITY_CLASS,
+        PriorityClass::BelowNormal => BELOW_NORMAL_PRIORITY_CLASS,
+        PriorityClass::Normal => NORMAL_PRIORITY_CLASS,
+        PriorityClass::AboveNormal => ABOVE_NORMAL_PRIORITY_CLASS,
+        PriorityClass::High => HIGH_PRIORITY_CLASS,
+        // 没有管理员权限时系统会自动降为 high
+        PriorityClass::Realtime => REALTIME_PRIORITY_CLASS,
+    }
+}
+
+struct WindowSearch {
+    pid: u32,
+    found: bool,
+}
+
+unsafe fn window_pid(hwnd: HWND) -> u32 {
+    let mut pid = 0;
+    GetWindowThreadProcessId(hwnd, Some(&mut pid));
+    pid
+}
+
+unsafe extern "system" fn find_child_window(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let search = &mut *(lparam.0 as *mut WindowSearch);
+    if window_pid(hwnd) == search.pid {
+        search.found = true;
+        return false.into();
+    }
+    true.into()
+}
+
+// UWP 的 CoreWindow 挂在 ApplicationFrameHost 的框架窗口下，所以也要检查子窗口
+unsafe extern "system" fn close_window(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let target = &mut *(lparam.0 as *mut WindowSearch);
+    let owned = window_pid(hwnd) == target.pid || {
+        let mut child = WindowSearch { pid: target.pid, found: false };
+        let _ = EnumChildWindows(hwnd, Some(find_child_window), LPARAM(&mut child as *mut WindowSearch as isize));
+        child.found
+    };
+
+    if owned {
+        let _ = PostMessageW(hwnd, WM_CLOSE, WPARAM(0), LPARAM(0));
+        target.found = true;
+    }
+    true.into()
+}
+
+impl ProcessQuery for WindowsProcessQuery {
+    fn processes(&self) -> io::Result<Vec<ProcessInfo>> {
+        let mut processes = Vec::new();
+        unsafe {
+            let snapshot = OwnedHandle(CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0).map_err(to_io_error)?);
+            let mut entry = PROCESSENTRY32W { dwSize: size_of::<PROCESSENTRY32W>() as u32, ..Default::default() };
+
+            let mut next = Process32FirstW(snapshot.0, &mut entry);
+            while next.is_ok() {
+                processes.push(ProcessInfo {
+                    pid: entry.th32ProcessID,
+                    name: from_wide(&entry.szExeFile),
+                    package_family_name: package_family_name(entry.th32ProcessID),
+                });
+                next = Process32NextW(snapshot.0, &mut entry);
+            }
+        }
+        Ok(processes)
+    }
+
+    fn close(&self, pid: u32) -> io::Result<()> {
+        let mut target = WindowSearch { pid, found: false };
+        unsafe {
+            EnumWindows(Some(close_window), LPARAM(&mut target as *mut WindowSearch as isize)).map_err(to_io_error)?;
+        }
+        if !target.found {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("进程 {} 没有可关闭的窗口", pid)));
+        }
+        Ok(())
+    }
+
+    fn kill(&self, pid: u32) -> io::Result<()> {
+        unsafe {
+            let process = OwnedHandle(OpenProcess(PROCESS_TERMINATE, false, pid).map_err(to_io_error)?);
+            TerminateProcess(process.0, 1).map_err(to_io_error)
+        }
+    }
+
+    fn wait_for_exit(&self, pid: u32) -> io::Result<Option<u32>> {
+        unsafe {
+            let process = OwnedHandle(OpenProcess(PROCESS_SYNCHRONIZE | PROCESS_QUERY_LIMITED_INFORMATION, false, pid).map_err(to_io_error)?);
+            if WaitForSingleObject(process.0, INFINITE) != WAIT_OBJECT_0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let mut exit_code = 0;
+            Ok(GetExitCodeProcess(process.0, &mut exit_code).ok().map(|_| exit_code))
+        }
+    }
+
+    fn set_priority(&self, pid: u32, priority: PriorityClass) -> io::Result<()> {
+        unsafe {
+            let process = OwnedHandle(OpenProcess(PROCESS_SET_INFORMATION, false, pid).map_err(to_io_error)?);
+            SetPriorityClass(process.0, priority_class_flags(priority)).map_err(to_io_error)
+        }
+    }
+
+    fn set_affinity(&self, pid: u32, mask: u64) -> io::Result<()> {
+        unsafe {
+            let process = OwnedHandle(OpenProcess(PROCESS_SET_INFORMATION | PROCESS_QUERY_LIMITED_INFORMATION, false, pid).map_err(to_io_error)?);
+            let (mut process_mask, mut system_mask) = (0usize, 0usize);
+            GetProcessAffinityMask(process.0, &mut process_mask, &mut system_mask).map_err(to_io_error)?;
+            if mask as usize & !system_mask != 0 {
+                return Err(invalid_affinity(mask));
+            }
+            SetProcessAffinityMask(process.0, mask as usize).map_err(to_io_error)
+        }
+    }
+}
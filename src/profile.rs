@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+use crate::process::tuning::parse_affinity_mask;
+use crate::process::{PriorityClass, ProcessTuning};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LaunchProfile {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<PriorityClass>,
+    // 十六进制掩码或 CPU 编号列表，如 "0xF"、"0-3,6"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub affinity: Option<String>,
+}
+
+impl LaunchProfile {
+    pub fn new(name: &str) -> Self {
+        LaunchProfile { name: name.to_string(), ..Default::default() }
+    }
+
+    pub fn tuning(&self) -> Result<ProcessTuning, String> {
+        Ok(ProcessTuning {
+            priority: self.priority,
+            affinity: self.affinity.as_deref().map(parse_affinity_mask).transpose()?,
+        })
+    }
+}
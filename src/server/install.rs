@@ -0,0 +1,395 @@
+use std::fs;
+use std::io::{self, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::Local;
+
+use super::run::{CONTROL_PORT_FILE, CONTROL_TOKEN_FILE};
+use super::{server_executable, version_from_archive_name, ServerInfo, SERVER_INFO_FILE};
+use crate::utils::archive::unzip;
+use crate::utils::files::create_temp_dir;
+use crate::error;
+
+// 更新时保留的配置文件，whitelist.json 是旧版本的名字
+const PRESERVED_FILES: [&str; 4] = ["server.properties", "allowlist.json", "whitelist.json", "permissions.json"];
+// 这些目录下已有的文件一律保留
+const PRESERVED_DIRS: [&str; 2] = ["worlds", "config"];
+// 自定义的包放在这里，新版本没有的子目录就是自定义包
+const PACK_DIRS: [&str; 2] = ["behavior_packs", "resource_packs"];
+// 本程序写入安装目录的文件，不算过时
+const OWN_FILES: [&str; 4] = [SERVER_INFO_FILE, CONTROL_PORT_FILE, CONTROL_TOKEN_FILE, "update.log"];
+const CONTROL_CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileChange {
+    Added,
+    Replaced,
+    Kept,
+    // 新版本中已经没有的文件，只记录不删除
+    Stale,
+}
+
+impl FileChange {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileChange::Added => "新增",
+            FileChange::Replaced => "替换",
+            FileChange::Kept => "保留",
+            FileChange::Stale => "过时",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ServerChangelog {
+    pub previous_version: Option<String>,
+    pub version: Option<String>,
+    // 路径相对安装目录，用 / 分隔
+    pub changes: Vec<(String, FileChange)>,
+}
+
+impl ServerChangelog {
+    pub fn count(&self, change: FileChange) -> usize {
+        self.changes.iter().filter(|(_, kind)| *kind == change).count()
+    }
+
+    pub fn render(&self) -> String {
+        let mut text = format!(
+            "{} 更新 {} -> {}\n",
+            Local::now().format("%Y-%m-%d %H:%M:%S"),
+            self.previous_version.as_deref().unwrap_or("未知"),
+            self.version.as_deref().unwrap_or("未知"),
+        );
+        for change in [FileChange::Kept, FileChange::Replaced, FileChange::Added, FileChange::Stale] {
+            for (path, _) in self.changes.iter().filter(|(_, kind)| *kind == change) {
+                text.push_str(&format!("{} {}\n", change.name(), path));
+            }
+        }
+        text
+    }
+}
+
+fn is_preserved(relative: &str) -> bool {
+    PRESERVED_FILES.contains(&relative)
+        || PRESERVED_DIRS.iter().any(|dir| relative.strip_prefix(dir).is_some_and(|rest| rest.starts_with('/')))
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push(relative.components().map(|component| component.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"));
+        }
+    }
+    Ok(())
+}
+
+fn same_content(a: &Path, b: &Path) -> bool {
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a_meta), Ok(b_meta)) if a_meta.len() != b_meta.len() => false,
+        (Ok(_), Ok(_)) => matches!((fs::read(a), fs::read(b)), (Ok(a), Ok(b)) if a == b),
+        _ => false,
+    }
+}
+
+fn child_names(dir: &Path) -> Vec<String> {
+    fs::read_dir(dir)
+        .map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| entry.file_name().to_string_lossy().into_owned()).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(unix)]
+fn mark_executable(server_dir: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if let Some(executable) = server_executable(server_dir) {
+        fs::set_permissions(executable, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn mark_executable(_server_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+// 先解压到临时目录，确认是 BDS 再继续
+fn extract_server(archive_path: &Path) -> io::Result<PathBuf> {
+    let staging_dir = create_temp_dir("bbl-server")?;
+    let result = unzip(archive_path, &staging_dir).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err));
+    if result.is_ok() && server_executable(&staging_dir).is_some() {
+        return Ok(staging_dir);
+    }
+
+    let _ = fs::remove_dir_all(&staging_dir);
+    result?;
+    Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} 不是 Bedrock Dedicated Server 压缩包 (缺少 bedrock_server)", archive_path.display())))
+}
+
+// 安装到空目录
+pub fn install_server(archive_path: &Path, server_dir: &Path) -> io::Result<ServerChangelog> {
+    if server_executable(server_dir).is_some() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} 已经安装了服务器，请使用 update", server_dir.display())));
+    }
+    apply_archive(archive_path, server_dir)
+}
+
+pub fn update_server(archive_path: &Path, server_dir: &Path) -> io::Result<ServerChangelog> {
+    if server_executable(server_dir).is_none() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} 没有安装服务器，请先使用 install", server_dir.display())));
+    }
+    ensure_not_running(server_dir)?;
+    apply_archive(archive_path, server_dir)
+}
+
+// 控制端口还能连上，或者可执行文件因为正在运行无法以写方式打开，都说明服务器正在运行
+pub fn ensure_not_running(server_dir: &Path) -> io::Result<()> {
+    let running = || io::Error::new(io::ErrorKind::ResourceBusy, format!("{} 中的服务器正在运行，请先停止", server_dir.display()));
+    let port = fs::read_to_string(server_dir.join(CONTROL_PORT_FILE)).ok().and_then(|port| port.trim().parse::<u16>().ok());
+    if let Some(port) = port {
+        if TcpStream::connect_timeout(&([127, 0, 0, 1], port).into(), CONTROL_CONNECT_TIMEOUT).is_ok() {
+            return Err(running());
+        }
+    }
+    if server_executable(server_dir).is_some_and(|executable| executable_in_use(&executable)) {
+        return Err(running());
+    }
+    Ok(())
+}
+
+fn executable_in_use(executable: &Path) -> bool {
+    // Windows: ERROR_SHARING_VIOLATION，Linux: ETXTBSY
+    const BUSY: i32 = if cfg!(windows) { 32 } else { 26 };
+    fs::OpenOptions::new().write(true).open(executable).is_err_and(|err| err.raw_os_error() == Some(BUSY))
+}
+
+// 上次安装记录的文件中，新版本没有且还在磁盘上的；旧的记录没有文件列表时，扫描安装目录
+fn stale_files(server_dir: &Path, previous: Option<&ServerInfo>, shipped: &[String]) -> io::Result<Vec<String>> {
+    let mut candidates = match previous.filter(|info| !info.files.is_empty()) {
+        Some(info) => info.files.clone(),
+        None if !server_dir.is_dir() => Vec::new(),
+        None => {
+            let mut files = Vec::new();
+            collect_files(server_dir, server_dir, &mut files)?;
+            let custom_packs: Vec<String> = PACK_DIRS.iter()
+                .flat_map(|dir| child_names(&server_dir.join(dir)).into_iter().map(move |name| format!("{}/{}/", dir, name)))
+                .collect();
+            files.retain(|relative| {
+                !OWN_FILES.contains(&relative.as_str())
+                    && !relative.starts_with("worlds/")
+                    && !custom_packs.iter().any(|pack| relative.starts_with(pack.as_str()))
+            });
+            files
+        }
+    };
+    candidates.retain(|relative| !shipped.contains(relative) && !is_preserved(relative) && server_dir.join(relative).is_file());
+    candidates.sort();
+    Ok(candidates)
+}
+
+// 被替换的文件先备份到 rollback_dir，出错时按相反顺序撤销
+fn rollback(server_dir: &Path, rollback_dir: &Path, applied: &[(String, FileChange)]) -> io::Result<()> {
+    for (relative, change) in applied.iter().rev() {
+        let target = server_dir.join(relative);
+        match change {
+            FileChange::Added => fs::remove_file(&target)?,
+            FileChange::Replaced => {
+                fs::copy(rollback_dir.join(relative), &target)?;
+            }
+            FileChange::Kept | FileChange::Stale => {}
+        }
+    }
+    Ok(())
+}
+
+// 压缩包里的文件: 受保护且已存在的保留，内容不同的替换，没有的新增；压缩包里没有的文件不动，记录为过时
+// 任何一步失败都会撤销已经复制的文件
+fn apply_archive(archive_path: &Path, server_dir: &Path) -> io::Result<ServerChangelog> {
+    let staging_dir = extract_server(archive_path)?;
+    let rollback_dir = match create_temp_dir("bbl-server-rollback") {
+        Ok(rollback_dir) => rollback_dir,
+        Err(err) => {
+            let _ = fs::remove_dir_all(&staging_dir);
+            return Err(err);
+        }
+    };
+    let previous_info = ServerInfo::load(server_dir);
+    let mut applied = Vec::new();
+    let result = (|| {
+        let mut changelog = ServerChangelog {
+            previous_version: previous_info.as_ref().and_then(|info| info.version.clone()),
+            version: version_from_archive_name(archive_path),
+            changes: Vec::new(),
+        };
+
+        let mut files = Vec::new();
+        collect_files(&staging_dir, &staging_dir, &mut files)?;
+        files.sort();
+        let stale = stale_files(server_dir, previous_info.as_ref(), &files)?;
+
+        // 安装信息最后写入，同样需要能撤销
+        if server_dir.join(SERVER_INFO_FILE).is_file() {
+            fs::copy(server_dir.join(SERVER_INFO_FILE), rollback_dir.join(SERVER_INFO_FILE))?;
+        }
+        for relative in &files {
+            let source = staging_dir.join(relative);
+            let target = server_dir.join(relative);
+            let change = match target.exists() {
+                false => FileChange::Added,
+                true if is_preserved(relative) => {
+                    changelog.changes.push((relative.clone(), FileChange::Kept));
+                    continue;
+                }
+                true if same_content(&source, &target) => continue,
+                true => FileChange::Replaced,
+            };
+            if change == FileChange::Replaced {
+                let backup = rollback_dir.join(relative);
+                if let Some(parent) = backup.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(&target, &backup)?;
+            } else if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(&source, &target)?;
+            applied.push((relative.clone(), change));
+            changelog.changes.push((relative.clone(), change));
+        }
+
+        // 自定义的包和世界不在压缩包里，按目录记录为保留
+        for dir in PACK_DIRS {
+            let shipped = child_names(&staging_dir.join(dir));
+            for name in child_names(&server_dir.join(dir)).into_iter().filter(|name| !shipped.contains(name)) {
+                changelog.changes.push((format!("{}/{}/", dir, name), FileChange::Kept));
+            }
+        }
+        for name in child_names(&server_dir.join("worlds")) {
+            changelog.changes.push((format!("worlds/{}/", name), FileChange::Kept));
+        }
+        changelog.changes.extend(stale.into_iter().map(|relative| (relative, FileChange::Stale)));
+
+        mark_executable(server_dir)?;
+        let archive = archive_path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        ServerInfo { version: changelog.version.clone(), archive, updated_at: Local::now(), files }.save(server_dir)?;
+        Ok(changelog)
+    })();
+
+    let _ = fs::remove_dir_all(&staging_dir);
+    if result.is_err() {
+        let info_backup = rollback_dir.join(SERVER_INFO_FILE);
+        let restored = rollback(server_dir, &rollback_dir, &applied).and_then(|_| match info_backup.is_file() {
+            true => fs::copy(&info_backup, server_dir.join(SERVER_INFO_FILE)).map(|_| ()),
+            false => Ok(()),
+        });
+        if let Err(err) = restored {
+            // 保留备份目录，方便手动恢复
+            error!("撤销失败: {}，被替换的文件备份在 {}", err, rollback_dir.display());
+            return result;
+        }
+    }
+    let _ = fs::remove_dir_all(&rollback_dir);
+    result
+}
+
+// 追加到安装目录下的 update.log
+pub fn append_changelog(server_dir: &Path, changelog: &ServerChangelog) -> io::Result<PathBuf> {
+    let path = server_dir.join("update.log");
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "{}", changelog.render())?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::net::TcpListener;
+
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    use super::*;
+
+    fn write_archive(path: &Path, files: &[(&str, &str)]) {
+        let mut writer = ZipWriter::new(File::create(path).unwrap());
+        for (name, content) in files {
+            writer.start_file(*name, SimpleFileOptions::default()).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    fn installed(files: &[(&str, &str)]) -> (PathBuf, PathBuf) {
+        let root = create_temp_dir("bbl-install").unwrap();
+        let server_dir = root.join("server");
+        let archive = root.join("bedrock-server-1.0.0.1.zip");
+        write_archive(&archive, files);
+        install_server(&archive, &server_dir).unwrap();
+        (root, server_dir)
+    }
+
+    #[test]
+    fn update_lists_stale_files() {
+        let (root, server_dir) = installed(&[("bedrock_server", "v1"), ("old.txt", "x"), ("server.properties", "a=1")]);
+        fs::write(server_dir.join("server.properties"), "a=2").unwrap();
+        let archive = root.join("bedrock-server-1.0.0.2.zip");
+        write_archive(&archive, &[("bedrock_server", "v2"), ("new.txt", "y"), ("server.properties", "a=1")]);
+
+        let changelog = update_server(&archive, &server_dir).unwrap();
+        let properties = fs::read_to_string(server_dir.join("server.properties")).unwrap();
+        let old_kept = server_dir.join("old.txt").is_file();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(changelog.previous_version.as_deref(), Some("1.0.0.1"));
+        assert!(changelog.changes.contains(&("bedrock_server".to_string(), FileChange::Replaced)));
+        assert!(changelog.changes.contains(&("new.txt".to_string(), FileChange::Added)));
+        assert!(changelog.changes.contains(&("server.properties".to_string(), FileChange::Kept)));
+        assert!(changelog.changes.contains(&("old.txt".to_string(), FileChange::Stale)));
+        assert_eq!(properties, "a=2");
+        assert!(old_kept);
+    }
+
+    #[test]
+    fn failed_update_rolls_back() {
+        let (root, server_dir) = installed(&[("bedrock_server", "v1"), ("a.txt", "old")]);
+        // b 是文件，b/c.txt 无法写入
+        fs::write(server_dir.join("b"), "").unwrap();
+        let archive = root.join("bedrock-server-1.0.0.2.zip");
+        write_archive(&archive, &[("bedrock_server", "v2"), ("a.txt", "new"), ("a0.txt", "added"), ("b/c.txt", "z")]);
+
+        let result = update_server(&archive, &server_dir);
+        let a = fs::read_to_string(server_dir.join("a.txt")).unwrap();
+        let executable = fs::read_to_string(server_dir.join("bedrock_server")).unwrap();
+        let added_left = server_dir.join("a0.txt").exists();
+        let version = ServerInfo::load(&server_dir).and_then(|info| info.version);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(result.is_err());
+        assert_eq!(a, "old");
+        assert_eq!(executable, "v1");
+        assert!(!added_left);
+        assert_eq!(version.as_deref(), Some("1.0.0.1"));
+    }
+
+    #[test]
+    fn refuses_to_update_a_running_server() {
+        let (root, server_dir) = installed(&[("bedrock_server", "v1")]);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        fs::write(server_dir.join(CONTROL_PORT_FILE), listener.local_addr().unwrap().port().to_string()).unwrap();
+        let archive = root.join("bedrock-server-1.0.0.2.zip");
+        write_archive(&archive, &[("bedrock_server", "v2")]);
+
+        let running = update_server(&archive, &server_dir).map(|_| ());
+        drop(listener);
+        // 端口已经关闭，残留的端口文件不影响更新
+        let stopped = update_server(&archive, &server_dir).map(|_| ());
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(running.unwrap_err().kind(), io::ErrorKind::ResourceBusy);
+        assert!(stopped.is_ok());
+    }
+}
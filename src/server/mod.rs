@@ -0,0 +1,79 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::paths::app_dir;
+
+pub mod install;
+pub mod properties;
+pub mod run;
+
+// 安装目录中记录版本信息的文件，BDS 本身不带版本文件
+pub const SERVER_INFO_FILE: &str = "bbl_server.json";
+pub const DEFAULT_SERVER_NAME: &str = "default";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerInfo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub archive: String,
+    pub updated_at: DateTime<Local>,
+    // 压缩包中的文件，下次更新时用来找出新版本删掉的文件
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+}
+
+impl ServerInfo {
+    pub fn load(server_dir: &Path) -> Option<Self> {
+        let data = fs::read_to_string(server_dir.join(SERVER_INFO_FILE)).ok()?;
+        serde_json::from_str(&data).ok()
+    }
+
+    pub fn save(&self, server_dir: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::write(server_dir.join(SERVER_INFO_FILE), json)
+    }
+}
+
+pub fn default_servers_dir() -> PathBuf {
+    app_dir().join("servers")
+}
+
+pub fn server_dir(name: &str) -> PathBuf {
+    default_servers_dir().join(name)
+}
+
+// Windows 版是 bedrock_server.exe，Linux 版是 bedrock_server
+pub fn server_executable(server_dir: &Path) -> Option<PathBuf> {
+    ["bedrock_server.exe", "bedrock_server"].iter()
+        .map(|name| server_dir.join(name))
+        .find(|path| path.is_file())
+}
+
+// bedrock-server-1.21.2.02.zip -> 1.21.2.02
+pub fn version_from_archive_name(archive_path: &Path) -> Option<String> {
+    let stem = archive_path.file_stem()?.to_string_lossy().into_owned();
+    let version = stem.strip_prefix("bedrock-server-")?;
+    version.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit())).then(|| version.to_string())
+}
+
+pub fn list_servers() -> Vec<(String, PathBuf, Option<ServerInfo>)> {
+    let mut servers: Vec<_> = fs::read_dir(default_servers_dir())
+        .map(|entries| {
+            entries.filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| server_executable(path).is_some())
+                .map(|path| {
+                    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                    let info = ServerInfo::load(&path);
+                    (name, path, info)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    servers.sort_by(|a, b| a.0.cmp(&b.0));
+    servers
+}
@@ -0,0 +1,375 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::validation::{Severity, ValidationIssue};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PropertyType {
+    Bool,
+    Int { min: i64, max: i64 },
+    Float { min: f64, max: f64 },
+    Enum(&'static [&'static str]),
+    String,
+}
+
+impl PropertyType {
+    pub fn describe(&self) -> String {
+        match self {
+            PropertyType::Bool => "true/false".to_string(),
+            PropertyType::Int { min, max } if *max == i64::MAX => format!("整数 >= {}", min),
+            PropertyType::Int { min, max } => format!("整数 {}-{}", min, max),
+            PropertyType::Float { min, max } if *max == f64::MAX => format!("小数 >= {}", min),
+            PropertyType::Float { min, max } => format!("小数 {}-{}", min, max),
+            PropertyType::Enum(values) => values.join("/"),
+            PropertyType::String => "文本".to_string(),
+        }
+    }
+
+    pub fn validate(&self, value: &str) -> Result<(), String> {
+        let valid = match self {
+            PropertyType::Bool => matches!(value, "true" | "false"),
+            PropertyType::Int { min, max } => value.parse::<i64>().is_ok_and(|number| (*min..=*max).contains(&number)),
+            PropertyType::Float { min, max } => value.parse::<f64>().is_ok_and(|number| (*min..=*max).contains(&number)),
+            PropertyType::Enum(values) => values.contains(&value),
+            PropertyType::String => true,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("无效的值 \"{}\"，应为 {}", value, self.describe()))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PropertySpec {
+    pub key: &'static str,
+    pub kind: PropertyType,
+    pub default: &'static str,
+}
+
+const fn spec(key: &'static str, kind: PropertyType, default: &'static str) -> PropertySpec {
+    PropertySpec { key, kind, default }
+}
+
+const ANY_COUNT: i64 = i64::MAX;
+
+// BDS 自带 server.properties 中的字段，默认值同官方
+pub const KNOWN_PROPERTIES: &[PropertySpec] = &[
+    spec("server-name", PropertyType::String, "Dedicated Server"),
+    spec("gamemode", PropertyType::Enum(&["survival", "creative", "adventure"]), "survival"),
+    spec("force-gamemode", PropertyType::Bool, "false"),
+    spec("difficulty", PropertyType::Enum(&["peaceful", "easy", "normal", "hard"]), "easy"),
+    spec("allow-cheats", PropertyType::Bool, "false"),
+    spec("max-players", PropertyType::Int { min: 1, max: ANY_COUNT }, "10"),
+    spec("online-mode", PropertyType::Bool, "true"),
+    spec("allow-list", PropertyType::Bool, "false"),
+    spec("server-port", PropertyType::Int { min: 1, max: 65535 }, "19132"),
+    spec("server-portv6", PropertyType::Int { min: 1, max: 65535 }, "19133"),
+    spec("enable-lan-visibility", PropertyType::Bool, "true"),
+    spec("view-distance", PropertyType::Int { min: 5, max: ANY_COUNT }, "32"),
+    spec("tick-distance", PropertyType::Int { min: 4, max: 12 }, "4"),
+    spec("player-idle-timeout", PropertyType::Int { min: 0, max: ANY_COUNT }, "30"),
+    spec("max-threads", PropertyType::Int { min: 0, max: ANY_COUNT }, "8"),
+    spec("level-name", PropertyType::String, "Bedrock level"),
+    spec("level-seed", PropertyType::String, ""),
+    spec("default-player-permission-level", PropertyType::Enum(&["visitor", "member", "operator"]), "member"),
+    spec("texturepack-required", PropertyType::Bool, "false"),
+    spec("content-log-file-enabled", PropertyType::Bool, "false"),
+    spec("compression-threshold", PropertyType::Int { min: 0, max: 65535 }, "1"),
+    spec("compression-algorithm", PropertyType::Enum(&["zlib", "snappy"]), "zlib"),
+    spec("server-authoritative-movement", PropertyType::Enum(&["client-auth", "server-auth", "server-auth-with-rewind"]), "server-auth"),
+    spec("player-position-acceptance-threshold", PropertyType::Float { min: 0.0, max: f64::MAX }, "0.5"),
+    spec("player-movement-action-direction-threshold", PropertyType::Float { min: 0.0, max: 1.0 }, "0.85"),
+    spec("server-authoritative-block-breaking-pick-range-scalar", PropertyType::Float { min: 0.0, max: f64::MAX }, "1.5"),
+    spec("chat-restriction", PropertyType::Enum(&["None", "Dropped", "Disabled"]), "None"),
+    spec("disable-player-interaction", PropertyType::Bool, "false"),
+    spec("client-side-chunk-generation-enabled", PropertyType::Bool, "true"),
+    spec("block-network-ids-are-hashes", PropertyType::Bool, "true"),
+    spec("disable-persona", PropertyType::Bool, "false"),
+    spec("disable-custom-skins", PropertyType::Bool, "false"),
+    spec("server-build-radius-ratio", PropertyType::String, "Disabled"),
+    spec("allow-outbound-script-debugging", PropertyType::Bool, "false"),
+    spec("allow-inbound-script-debugging", PropertyType::Bool, "false"),
+    spec("script-debugger-auto-attach", PropertyType::Enum(&["disabled", "connect", "listen"]), "disabled"),
+    spec("emit-server-telemetry", PropertyType::Bool, "false"),
+];
+
+pub fn find_spec(key: &str) -> Option<&'static PropertySpec> {
+    KNOWN_PROPERTIES.iter().find(|spec| spec.key == key)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// 拼错的字段名给出最接近的已知字段
+pub fn suggest_key(key: &str) -> Option<&'static str> {
+    KNOWN_PROPERTIES.iter()
+        .map(|spec| (edit_distance(key, spec.key), spec.key))
+        .filter(|(distance, _)| *distance <= 3)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, key)| key)
+}
+
+pub fn unknown_key_message(key: &str) -> String {
+    match suggest_key(key) {
+        Some(suggestion) => format!("未知的字段 {}，是否应为 {}", key, suggestion),
+        None => format!("未知的字段 {}", key),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PropertyLine {
+    // raw 是该行原文，只有值被修改的行才重新生成
+    Entry { key: String, value: String, raw: String },
+    // 注释和空行原样保留
+    Other(String),
+}
+
+// 保留原行中值之前的部分（含等号后的空白），只替换值本身
+fn replace_raw_value(raw: &str, value: &str) -> String {
+    match raw.split_once('=') {
+        Some((before, after)) => {
+            let spacing = &after[..after.len() - after.trim_start().len()];
+            format!("{}={}{}", before, spacing, value)
+        }
+        None => raw.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerProperties {
+    lines: Vec<PropertyLine>,
+    line_ending: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyChange {
+    pub key: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+impl ServerProperties {
+    pub fn parse(text: &str) -> Self {
+        let line_ending = if text.contains("\r\n") { "\r\n" } else { "\n" };
+        let lines = text.lines()
+            .map(|line| {
+                let trimmed = line.trim_start();
+                match trimmed.split_once('=') {
+                    Some((key, value)) if !trimmed.starts_with('#') && !trimmed.starts_with('!') => {
+                        PropertyLine::Entry { key: key.trim().to_string(), value: value.trim().to_string(), raw: line.to_string() }
+                    }
+                    _ => PropertyLine::Other(line.to_string()),
+                }
+            })
+            .collect();
+        ServerProperties { lines, line_ending }
+    }
+
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.render())
+    }
+
+    pub fn render(&self) -> String {
+        let mut text: String = self.lines.iter()
+            .map(|line| match line {
+                PropertyLine::Entry { raw, .. } => raw.clone(),
+                PropertyLine::Other(line) => line.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(self.line_ending);
+        text.push_str(self.line_ending);
+        text
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.lines.iter().filter_map(|line| match line {
+            PropertyLine::Entry { key, value, .. } => Some((key.as_str(), value.as_str())),
+            PropertyLine::Other(_) => None,
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries().find(|(entry_key, _)| *entry_key == key).map(|(_, value)| value)
+    }
+
+    // 已有的字段原地修改（重复的字段一并修改），保留等号前后的原有格式；新字段追加到末尾
+    pub fn set(&mut self, key: &str, value: &str) {
+        let mut found = false;
+        for line in &mut self.lines {
+            if let PropertyLine::Entry { key: entry_key, value: entry_value, raw } = line {
+                if entry_key != key {
+                    continue;
+                }
+                found = true;
+                if entry_value != value {
+                    *raw = replace_raw_value(raw, value);
+                    *entry_value = value.to_string();
+                }
+            }
+        }
+        if !found {
+            self.lines.push(PropertyLine::Entry { key: key.to_string(), value: value.to_string(), raw: format!("{}={}", key, value) });
+        }
+    }
+
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        let mut seen = HashSet::new();
+        for (key, value) in self.entries() {
+            if !seen.insert(key) {
+                issues.push(ValidationIssue { severity: Severity::Warning, message: format!("字段 {} 重复出现", key) });
+                continue;
+            }
+            match find_spec(key) {
+                Some(spec) => {
+                    if let Err(err) = spec.kind.validate(value) {
+                        issues.push(ValidationIssue { severity: Severity::Error, message: format!("{}: {}", key, err) });
+                    }
+                }
+                None => issues.push(ValidationIssue { severity: Severity::Warning, message: format!("{}，服务器会忽略它", unknown_key_message(key)) }),
+            }
+        }
+        issues
+    }
+
+    // 按 self 中的顺序列出变化，other 中新增的字段排在最后
+    pub fn diff(&self, other: &ServerProperties) -> Vec<PropertyChange> {
+        let mut changes: Vec<PropertyChange> = self.entries()
+            .filter_map(|(key, value)| {
+                let new_value = other.get(key);
+                (new_value != Some(value)).then(|| PropertyChange {
+                    key: key.to_string(),
+                    old_value: Some(value.to_string()),
+                    new_value: new_value.map(|value| value.to_string()),
+                })
+            })
+            .collect();
+        changes.extend(other.entries().filter(|(key, _)| self.get(key).is_none()).map(|(key, value)| PropertyChange {
+            key: key.to_string(),
+            old_value: None,
+            new_value: Some(value.to_string()),
+        }));
+        changes
+    }
+
+    // 与官方默认值不同的字段
+    pub fn changed_from_defaults(&self) -> Vec<PropertyChange> {
+        KNOWN_PROPERTIES.iter()
+            .filter_map(|spec| {
+                let value = self.get(spec.key)?;
+                (value != spec.default).then(|| PropertyChange {
+                    key: spec.key.to_string(),
+                    old_value: Some(spec.default.to_string()),
+                    new_value: Some(value.to_string()),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "# 服务器名\nserver-name = My Server\n\ngamemode=creative\nmax-players=10\n";
+
+    #[test]
+    fn round_trip_keeps_comments_and_formatting() {
+        let properties = ServerProperties::parse(SAMPLE);
+        assert_eq!(properties.render(), SAMPLE);
+        assert_eq!(properties.get("server-name"), Some("My Server"));
+        assert_eq!(properties.get("gamemode"), Some("creative"));
+    }
+
+    #[test]
+    fn round_trip_keeps_crlf() {
+        let text = SAMPLE.replace('\n', "\r\n");
+        let mut properties = ServerProperties::parse(&text);
+        assert_eq!(properties.get("server-name"), Some("My Server"));
+        assert_eq!(properties.render(), text);
+
+        properties.set("gamemode", "survival");
+        assert_eq!(properties.render(), text.replace("gamemode=creative", "gamemode=survival"));
+    }
+
+    #[test]
+    fn set_only_rewrites_changed_lines() {
+        let mut properties = ServerProperties::parse(SAMPLE);
+        properties.set("server-name", "Other");
+        properties.set("gamemode", "creative");
+        properties.set("level-seed", "42");
+        assert_eq!(properties.render(), "# 服务器名\nserver-name = Other\n\ngamemode=creative\nmax-players=10\nlevel-seed=42\n");
+    }
+
+    #[test]
+    fn duplicate_keys_are_set_together_and_reported() {
+        let mut properties = ServerProperties::parse("max-players=5\nmax-players = 7\n");
+        assert_eq!(properties.get("max-players"), Some("5"));
+        let issues = properties.validate();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, Severity::Warning);
+
+        properties.set("max-players", "20");
+        assert_eq!(properties.render(), "max-players=20\nmax-players = 20\n");
+    }
+
+    #[test]
+    fn diff_lists_changed_removed_and_added_keys() {
+        let old = ServerProperties::parse("gamemode=survival\nmax-players=10\nlevel-name=a\n");
+        let new = ServerProperties::parse("gamemode=creative\nmax-players=10\nlevel-seed=1\n");
+        let changes = old.diff(&new);
+        assert_eq!(changes, vec![
+            PropertyChange { key: "gamemode".to_string(), old_value: Some("survival".to_string()), new_value: Some("creative".to_string()) },
+            PropertyChange { key: "level-name".to_string(), old_value: Some("a".to_string()), new_value: None },
+            PropertyChange { key: "level-seed".to_string(), old_value: None, new_value: Some("1".to_string()) },
+        ]);
+    }
+
+    #[test]
+    fn changed_from_defaults_skips_default_values() {
+        let properties = ServerProperties::parse("gamemode=survival\ndifficulty=hard\nunknown=1\n");
+        let changes = properties.changed_from_defaults();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].key, "difficulty");
+        assert_eq!(changes[0].old_value.as_deref(), Some("easy"));
+        assert_eq!(changes[0].new_value.as_deref(), Some("hard"));
+    }
+
+    #[test]
+    fn validate_checks_types_and_unknown_keys() {
+        let properties = ServerProperties::parse("server-port=70000\nonline-mode=yes\ntick-distance=8\nplayer-movement-action-direction-threshold=0.5\nmax-player=5\n");
+        let issues = properties.validate();
+        let errors: Vec<_> = issues.iter().filter(|issue| issue.severity == Severity::Error).collect();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].message.starts_with("server-port"));
+        assert!(errors[1].message.starts_with("online-mode"));
+        assert_eq!(issues.len(), 3);
+        assert!(issues[2].message.contains("max-players"));
+    }
+
+    #[test]
+    fn suggests_closest_known_key() {
+        assert_eq!(suggest_key("max-player"), Some("max-players"));
+        assert_eq!(suggest_key("gamemod"), Some("gamemode"));
+        assert_eq!(suggest_key("completely-unrelated-key"), None);
+    }
+}
@@ -0,0 +1,414 @@
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
+use std::hash::{BuildHasher, Hasher};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{self, Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use chrono::{Local, NaiveDateTime};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use super::server_executable;
+use crate::utils::archive::to_io_error;
+use crate::utils::paths::app_dir;
+use crate::{error, info, warning};
+
+// 运行时把控制端口写到服务器目录，server send 从这里读取
+pub const CONTROL_PORT_FILE: &str = "bbl_control.port";
+// 每次运行生成的令牌，连接后第一行必须是它
+pub const CONTROL_TOKEN_FILE: &str = "bbl_control.token";
+const SAVE_READY: &str = "Data saved. Files are now ready to be copied.";
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const SAVE_QUERY_TIMEOUT: Duration = Duration::from_secs(60);
+const STOP_TIMEOUT: Duration = Duration::from_secs(30);
+const BACKUP_NAME_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    pub control_port: Option<u16>,
+    pub backup_interval: Option<Duration>,
+    pub backup_dir: PathBuf,
+    // 只保留最新的几份，0 表示全部保留
+    pub keep_backups: usize,
+}
+
+enum ServerEvent {
+    Output(String),
+    Command(String),
+    Closed,
+}
+
+pub fn default_server_backup_dir(name: &str) -> PathBuf {
+    app_dir().join("backups").join(format!("server-{}", name))
+}
+
+// save query 返回的文件列表: "Bedrock level/db/000005.ldb:1234, Bedrock level/level.dat:2211"
+pub fn parse_save_query(line: &str) -> Option<Vec<(String, u64)>> {
+    let files: Vec<(String, u64)> = line.trim()
+        .split(", ")
+        .map(|entry| {
+            let (path, length) = entry.rsplit_once(':')?;
+            Some((path.to_string(), length.trim().parse().ok()?))
+        })
+        .collect::<Option<_>>()?;
+    (!files.is_empty()).then_some(files)
+}
+
+// 只复制 save query 给出的长度，之后写入的部分不完整
+pub fn write_backup(worlds_dir: &Path, files: &[(String, u64)], archive_path: &Path) -> io::Result<()> {
+    if let Some(parent) = archive_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut writer = ZipWriter::new(File::create(archive_path)?);
+    for (path, length) in files {
+        writer.start_file(path.replace('\\', "/"), SimpleFileOptions::default()).map_err(to_io_error)?;
+        io::copy(&mut File::open(worlds_dir.join(path))?.take(*length), &mut writer)?;
+    }
+    writer.finish().map_err(to_io_error)?;
+    Ok(())
+}
+
+// 只认 backup 写出的 <%Y%m%d-%H%M%S>.zip，目录里的其他文件不动
+fn is_backup_archive(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    name.strip_suffix(".zip").is_some_and(|stem| NaiveDateTime::parse_from_str(stem, BACKUP_NAME_FORMAT).is_ok())
+}
+
+// 按文件名 (时间) 排序，删除最旧的
+pub fn prune_backups(backup_dir: &Path, keep: usize) -> io::Result<Vec<PathBuf>> {
+    if keep == 0 {
+        return Ok(Vec::new());
+    }
+    let mut backups: Vec<PathBuf> = fs::read_dir(backup_dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && is_backup_archive(path))
+        .collect();
+    backups.sort();
+    let removed: Vec<PathBuf> = backups.drain(..backups.len().saturating_sub(keep)).collect();
+    for path in &removed {
+        fs::remove_file(path)?;
+    }
+    Ok(removed)
+}
+
+pub fn send_control_command(server_dir: &Path, command: &str) -> io::Result<()> {
+    let not_running = |_| io::Error::new(io::ErrorKind::NotFound, "服务器没有运行，或启动时没有指定 --control-port");
+    let port = fs::read_to_string(server_dir.join(CONTROL_PORT_FILE)).map_err(not_running)?;
+    let port: u16 = port.trim().parse().map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("无效的控制端口: {}", port.trim())))?;
+    let token = fs::read_to_string(server_dir.join(CONTROL_TOKEN_FILE)).map_err(not_running)?;
+    let mut stream = TcpStream::connect(("127.0.0.1", port))?;
+    writeln!(stream, "{}", token.trim())?;
+    writeln!(stream, "{}", command)
+}
+
+// RandomState 的密钥来自系统随机数，每次新建都不同
+fn generate_token() -> String {
+    (0..2)
+        .map(|_| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u128(SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_nanos()).unwrap_or(0));
+            hasher.write_u32(process::id());
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
+
+// 令牌只给当前用户读取
+fn write_token_file(path: &Path, token: &str) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(token.as_bytes())
+}
+
+fn forward_lines(reader: impl Read + Send + 'static, events: Option<Sender<ServerEvent>>, to_stderr: bool) {
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
+            if to_stderr {
+                eprintln!("{}", line);
+            } else {
+                println!("{}", line);
+            }
+            if let Some(events) = &events {
+                let _ = events.send(ServerEvent::Output(line));
+            }
+        }
+        if let Some(events) = events {
+            let _ = events.send(ServerEvent::Closed);
+        }
+    });
+}
+
+fn read_commands(reader: impl Read + Send + 'static, events: Sender<ServerEvent>) {
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
+            if events.send(ServerEvent::Command(line)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+// 第一行是令牌，之后每行一条命令；令牌不对时直接断开
+fn read_control_commands(stream: TcpStream, token: Arc<String>, events: Sender<ServerEvent>) {
+    thread::spawn(move || {
+        let mut lines = BufReader::new(stream).lines().map_while(Result::ok);
+        if lines.next().as_deref().map(str::trim) != Some(token.as_str()) {
+            warning!("控制端口收到的令牌无效，已断开连接");
+            return;
+        }
+        for line in lines {
+            if events.send(ServerEvent::Command(line)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+// 只监听本机，连接时要先发送 CONTROL_TOKEN_FILE 中的令牌
+fn listen_control_socket(port: u16, token: &str, events: Sender<ServerEvent>) -> io::Result<u16> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    let port = listener.local_addr()?.port();
+    let token = Arc::new(token.to_string());
+    thread::spawn(move || {
+        for stream in listener.incoming().map_while(Result::ok) {
+            read_control_commands(stream, token.clone(), events.clone());
+        }
+    });
+    Ok(port)
+}
+
+struct ServerRunner {
+    child: Child,
+    stdin: ChildStdin,
+    events: Receiver<ServerEvent>,
+    // 等待 save query 期间收到的命令，稍后处理
+    pending: VecDeque<String>,
+    worlds_dir: PathBuf,
+}
+
+impl ServerRunner {
+    fn send(&mut self, command: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{}", command)?;
+        self.stdin.flush()
+    }
+
+    fn wait_for_output(&mut self, timeout: Duration, matches: impl Fn(&str) -> bool) -> Option<String> {
+        let deadline = Instant::now() + timeout;
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            match self.events.recv_timeout(remaining) {
+                Ok(ServerEvent::Output(line)) if matches(&line) => return Some(line),
+                Ok(ServerEvent::Output(_)) => {}
+                Ok(ServerEvent::Command(command)) => self.pending.push_back(command),
+                Ok(ServerEvent::Closed) | Err(RecvTimeoutError::Disconnected) => return None,
+                Err(RecvTimeoutError::Timeout) => break,
+            }
+        }
+        None
+    }
+
+    // save hold 后反复 save query，直到服务器给出可以复制的文件列表
+    fn query_saved_files(&mut self) -> io::Result<Vec<(String, u64)>> {
+        let deadline = Instant::now() + SAVE_QUERY_TIMEOUT;
+        while Instant::now() < deadline {
+            self.send("save query")?;
+            if self.wait_for_output(Duration::from_secs(2), |line| line.contains(SAVE_READY)).is_some() {
+                if let Some(files) = self.wait_for_output(Duration::from_secs(5), |line| parse_save_query(line).is_some()).and_then(|line| parse_save_query(&line)) {
+                    return Ok(files);
+                }
+            }
+        }
+        Err(io::Error::new(io::ErrorKind::TimedOut, "等待 save query 超时"))
+    }
+
+    fn backup(&mut self, options: &RunOptions) -> io::Result<PathBuf> {
+        self.send("save hold")?;
+        let archive_path = options.backup_dir.join(format!("{}.zip", Local::now().format(BACKUP_NAME_FORMAT)));
+        let result = self.query_saved_files().and_then(|files| write_backup(&self.worlds_dir, &files, &archive_path));
+        // 无论备份是否成功都要恢复写入
+        self.send("save resume")?;
+        result?;
+
+        match prune_backups(&options.backup_dir, options.keep_backups) {
+            Ok(removed) => removed.iter().for_each(|path| info!("已删除旧备份: {}", path.display())),
+            Err(err) => warning!("清理旧备份失败: {}", err),
+        }
+        Ok(archive_path)
+    }
+
+    fn stop(&mut self) -> io::Result<Option<i32>> {
+        info!("正在停止服务器...");
+        self.send("stop")?;
+        let deadline = Instant::now() + STOP_TIMEOUT;
+        while Instant::now() < deadline {
+            if let Some(status) = self.child.try_wait()? {
+                return Ok(status.code());
+            }
+            let _ = self.events.recv_timeout(POLL_INTERVAL);
+        }
+        warning!("服务器在 {} 秒内没有退出，强制结束", STOP_TIMEOUT.as_secs());
+        self.child.kill()?;
+        Ok(self.child.wait()?.code())
+    }
+
+    fn run(&mut self, options: &RunOptions) -> io::Result<Option<i32>> {
+        let mut next_backup = options.backup_interval.map(|interval| Instant::now() + interval);
+        loop {
+            if let Some(status) = self.child.try_wait()? {
+                return Ok(status.code());
+            }
+            if let (Some(due), Some(interval)) = (next_backup, options.backup_interval) {
+                if Instant::now() >= due {
+                    match self.backup(options) {
+                        Ok(path) => info!("定时备份完成: {}", path.display()),
+                        Err(err) => error!("定时备份失败: {}", err),
+                    }
+                    next_backup = Some(Instant::now() + interval);
+                }
+            }
+
+            let command = match self.pending.pop_front() {
+                Some(command) => command,
+                None => match self.events.recv_timeout(POLL_INTERVAL) {
+                    Ok(ServerEvent::Command(command)) => command,
+                    Ok(ServerEvent::Output(_)) | Err(RecvTimeoutError::Timeout) => continue,
+                    Ok(ServerEvent::Closed) | Err(RecvTimeoutError::Disconnected) => return Ok(self.child.wait()?.code()),
+                },
+            };
+            match command.trim() {
+                "" => {}
+                "stop" => return self.stop(),
+                "backup" => match self.backup(options) {
+                    Ok(path) => info!("备份完成: {}", path.display()),
+                    Err(err) => error!("备份失败: {}", err),
+                },
+                command => self.send(command)?,
+            }
+        }
+    }
+}
+
+fn spawn_server(server_dir: &Path) -> io::Result<Child> {
+    let executable = server_executable(server_dir)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} 中没有 bedrock_server", server_dir.display())))?;
+    let mut command = Command::new(executable);
+    command.current_dir(server_dir).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped());
+    // Linux 版需要从当前目录加载自带的库
+    #[cfg(unix)]
+    command.env("LD_LIBRARY_PATH", server_dir);
+    command.spawn()
+}
+
+// 返回服务器的退出码；输入 stop 正常停止，backup 立即备份，其余内容原样发给服务器
+pub fn run_server(server_dir: &Path, options: &RunOptions) -> io::Result<Option<i32>> {
+    let mut child = spawn_server(server_dir)?;
+    let (sender, events) = mpsc::channel();
+    let Some(stdin) = child.stdin.take() else {
+        // 已经启动的进程要结束掉，不能留在后台
+        let _ = child.kill();
+        let _ = child.wait();
+        return Err(io::Error::new(io::ErrorKind::BrokenPipe, "无法获取服务器的标准输入"));
+    };
+    if let Some(stdout) = child.stdout.take() {
+        forward_lines(stdout, Some(sender.clone()), false);
+    }
+    if let Some(stderr) = child.stderr.take() {
+        forward_lines(stderr, None, true);
+    }
+    read_commands(io::stdin(), sender.clone());
+
+    let port_file = server_dir.join(CONTROL_PORT_FILE);
+    let token_file = server_dir.join(CONTROL_TOKEN_FILE);
+    if let Some(port) = options.control_port {
+        let token = generate_token();
+        // 服务器已经在运行，写不了文件只影响 server send，不能中途返回
+        let written = write_token_file(&token_file, &token)
+            .and_then(|_| listen_control_socket(port, &token, sender.clone()))
+            .and_then(|port| fs::write(&port_file, port.to_string()).map(|_| port));
+        match written {
+            Ok(port) => info!("控制端口: 127.0.0.1:{}", port),
+            Err(err) => warning!("无法开启控制端口 {}: {}", port, err),
+        }
+    }
+    drop(sender);
+
+    let mut runner = ServerRunner { child, stdin, events, pending: VecDeque::new(), worlds_dir: server_dir.join("worlds") };
+    let result = runner.run(options);
+    let _ = fs::remove_file(&port_file);
+    let _ = fs::remove_file(&token_file);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::files::create_temp_dir;
+
+    #[test]
+    fn prune_keeps_unrelated_archives() {
+        let dir = create_temp_dir("bbl-prune").unwrap();
+        for name in ["20240101-000000.zip", "20240102-000000.zip", "20240103-000000.zip", "photos.zip", "2024-old.zip", "20240101-000000.zip.txt"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+
+        let removed = prune_backups(&dir, 1).unwrap();
+        let mut left: Vec<String> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned()).collect();
+        left.sort();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(removed, vec![dir.join("20240101-000000.zip"), dir.join("20240102-000000.zip")]);
+        assert_eq!(left, ["2024-old.zip", "20240101-000000.zip.txt", "20240103-000000.zip", "photos.zip"]);
+    }
+
+    #[test]
+    fn parses_save_query_output() {
+        let files = parse_save_query("Bedrock level/db/000005.ldb:1234, Bedrock level/level.dat:2211").unwrap();
+        assert_eq!(files, vec![("Bedrock level/db/000005.ldb".to_string(), 1234), ("Bedrock level/level.dat".to_string(), 2211)]);
+        assert_eq!(parse_save_query(SAVE_READY), None);
+    }
+
+    #[test]
+    fn control_socket_requires_token() {
+        let server_dir = create_temp_dir("bbl-control").unwrap();
+        let token = generate_token();
+        assert_eq!(token.len(), 32);
+        assert_ne!(token, generate_token());
+
+        let (sender, events) = mpsc::channel();
+        let port = listen_control_socket(0, &token, sender).unwrap();
+        fs::write(server_dir.join(CONTROL_PORT_FILE), port.to_string()).unwrap();
+
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        writeln!(stream, "wrong").unwrap();
+        writeln!(stream, "op intruder").unwrap();
+        drop(stream);
+        let missing_token = send_control_command(&server_dir, "say no token").unwrap_err();
+        write_token_file(&server_dir.join(CONTROL_TOKEN_FILE), &token).unwrap();
+        send_control_command(&server_dir, "say hello").unwrap();
+
+        let received = match events.recv_timeout(Duration::from_secs(5)) {
+            Ok(ServerEvent::Command(command)) => command,
+            _ => String::new(),
+        };
+        let extra = events.recv_timeout(Duration::from_millis(200)).is_ok();
+        fs::remove_dir_all(&server_dir).unwrap();
+
+        assert_eq!(missing_token.kind(), io::ErrorKind::NotFound);
+        assert_eq!(received, "say hello");
+        assert!(!extra);
+    }
+}
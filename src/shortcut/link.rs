@@ -0,0 +1,35 @@
+use std::io;
+use std::path::Path;
+
+use windows::core::{Interface, HSTRING};
+use windows::Win32::Foundation::TRUE;
+use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, IPersistFile, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED};
+use windows::Win32::UI::Shell::{IShellLinkW, ShellLink};
+
+use super::{display_path, quote_argument, Shortcut};
+
+fn save_link(shortcut: &Shortcut, link_path: &Path) -> windows::core::Result<()> {
+    let arguments = shortcut.arguments.iter()
+        .map(|arg| quote_argument(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    unsafe {
+        // 已经初始化过时返回 S_FALSE 或 RPC_E_CHANGED_MODE，都不影响创建快捷方式
+        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+        let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)?;
+        link.SetPath(&HSTRING::from(display_path(&shortcut.executable)))?;
+        link.SetArguments(&HSTRING::from(arguments))?;
+        link.SetWorkingDirectory(&HSTRING::from(display_path(&shortcut.working_dir)))?;
+        link.SetDescription(&HSTRING::from(shortcut.name.as_str()))?;
+        if let Some(icon) = &shortcut.icon {
+            link.SetIconLocation(&HSTRING::from(display_path(icon)), 0)?;
+        }
+        link.cast::<IPersistFile>()?.Save(&HSTRING::from(display_path(link_path)), TRUE)
+    }
+}
+
+pub fn write_link(shortcut: &Shortcut, link_path: &Path) -> io::Result<()> {
+    save_link(shortcut, link_path)
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("0x{:08X} {}", err.code().0 as u32, err.message())))
+}
@@ -0,0 +1,354 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::edition::{find_edition, Edition};
+use crate::hooks::Shell;
+use crate::manifest::AppxManifest;
+use crate::utils::files::sanitize_file_name;
+use crate::utils::paths::app_dir;
+
+#[cfg(windows)]
+mod link;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const MAX_ICON_SIZE: u32 = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShortcutKind {
+    Link,
+    Url,
+    Script,
+}
+
+impl ShortcutKind {
+    pub fn platform_default() -> Self {
+        if cfg!(windows) {
+            ShortcutKind::Link
+        } else {
+            ShortcutKind::Script
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ShortcutKind::Link => "lnk",
+            ShortcutKind::Url => "url",
+            ShortcutKind::Script if cfg!(windows) => "cmd",
+            ShortcutKind::Script => "sh",
+        }
+    }
+}
+
+impl FromStr for ShortcutKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "lnk" | "link" => Ok(ShortcutKind::Link),
+            "url" => Ok(ShortcutKind::Url),
+            "script" | "cmd" | "sh" => Ok(ShortcutKind::Script),
+            _ => Err(format!("未知的快捷方式类型: {} (可选 lnk, url, script)", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Shortcut {
+    pub name: String,
+    pub executable: PathBuf,
+    pub arguments: Vec<String>,
+    pub working_dir: PathBuf,
+    pub icon: Option<PathBuf>,
+}
+
+impl Shortcut {
+    // 通过 regpack -start 切换到该版本并启动
+    pub fn for_package(package_dir: &Path, name: &str) -> io::Result<Self> {
+        let package_dir = package_dir.canonicalize()?;
+        Ok(Shortcut {
+            name: name.to_string(),
+            executable: env::current_exe()?,
+            arguments: vec!["regpack".to_string(), display_path(&package_dir), "-start".to_string()],
+            working_dir: app_dir(),
+            icon: None,
+        })
+    }
+
+    pub fn command_line(&self, shell: Shell) -> String {
+        let args = std::iter::once(display_path(&self.executable)).chain(self.arguments.iter().cloned());
+        match shell {
+            Shell::Cmd => escape_cmd(&args.map(|arg| quote_argument(&arg)).collect::<Vec<_>>().join(" ")),
+            Shell::Sh => args.map(|arg| quote_sh_argument(&arg)).collect::<Vec<_>>().join(" "),
+        }
+    }
+
+    pub fn file_name(&self, kind: ShortcutKind) -> String {
+        format!("{}.{}", sanitize_file_name(&self.name), kind.extension())
+    }
+}
+
+// canonicalize 在 Windows 上会带 \\?\ 前缀，快捷方式里不需要
+fn display_path(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    path.strip_prefix(r"\\?\").unwrap_or(&path).to_string()
+}
+
+// 按 CommandLineToArgvW 的规则加引号: 引号前的反斜杠加倍，引号写成 \"
+fn quote_argument(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || c == '"') {
+        return arg.to_string();
+    }
+    let mut quoted = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        if c == '\\' {
+            backslashes += 1;
+            continue;
+        }
+        let count = if c == '"' { backslashes * 2 + 1 } else { backslashes };
+        quoted.push_str(&"\\".repeat(count));
+        quoted.push(c);
+        backslashes = 0;
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+// 批处理文件中 % 写成 %%，引号外的 & | < > ^ ( ) 用 ^ 转义
+fn escape_cmd(command_line: &str) -> String {
+    let mut escaped = String::new();
+    let mut quoted = false;
+    for c in command_line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '%' => escaped.push('%'),
+            '&' | '|' | '<' | '>' | '^' | '(' | ')' if !quoted => escaped.push('^'),
+            _ => {}
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn quote_sh_argument(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+// 如 "Minecraft 1.20.8001.0 release"
+pub fn default_shortcut_name(manifest: &AppxManifest, editions: &[Edition]) -> String {
+    let identity = &manifest.identity;
+    let edition = find_edition(editions, &identity.name)
+        .and_then(|edition| edition.aliases.first().cloned())
+        .unwrap_or_else(|| identity.name.clone());
+    format!("Minecraft {} {}", identity.version, edition)
+}
+
+pub fn default_output_dir() -> PathBuf {
+    env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" })
+        .map(|home| PathBuf::from(home).join("Desktop"))
+        .filter(|desktop| desktop.is_dir())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+// 图标和 .url 指向的脚本放在程序目录下，桌面上只放快捷方式本身
+pub fn shortcut_data_dir() -> PathBuf {
+    app_dir().join("shortcuts")
+}
+
+pub fn render_script(shortcut: &Shortcut, shell: Shell) -> String {
+    let working_dir = display_path(&shortcut.working_dir);
+    match shell {
+        Shell::Cmd => format!("@echo off\r\ncd /d {}\r\n{}\r\n", escape_cmd(&quote_argument(&working_dir)), shortcut.command_line(shell)),
+        Shell::Sh => format!("#!/bin/sh\ncd {}\nexec {}\n", quote_sh_argument(&working_dir), shortcut.command_line(shell)),
+    }
+}
+
+pub fn file_url(path: &Path) -> String {
+    let path = display_path(path).replace('\\', "/");
+    let mut url = String::from("file://");
+    if !path.starts_with('/') {
+        url.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' | b':' => url.push(byte as char),
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    url
+}
+
+pub fn render_url(target: &str, icon: Option<&Path>) -> String {
+    let mut content = format!("[InternetShortcut]\r\nURL={}\r\n", target);
+    if let Some(icon) = icon {
+        content.push_str(&format!("IconFile={}\r\nIconIndex=0\r\n", display_path(icon)));
+    }
+    content
+}
+
+fn png_size(data: &[u8]) -> Option<(u32, u32)> {
+    if data.len() < 24 || !data.starts_with(PNG_SIGNATURE) || &data[12..16] != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(data[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(data[20..24].try_into().ok()?);
+    Some((width, height))
+}
+
+// Vista 之后的 ico 可以直接内嵌 png
+pub fn png_to_ico(png: &[u8]) -> io::Result<Vec<u8>> {
+    let (width, height) = png_size(png).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "不是有效的 png 图片"))?;
+    if width > MAX_ICON_SIZE || height > MAX_ICON_SIZE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("图标尺寸 {}x{} 超过 256x256", width, height)));
+    }
+
+    let mut ico = Vec::with_capacity(22 + png.len());
+    ico.extend_from_slice(&0u16.to_le_bytes());
+    ico.extend_from_slice(&1u16.to_le_bytes());
+    ico.extend_from_slice(&1u16.to_le_bytes());
+    // 256 在目录项里记为 0
+    ico.push((width % 256) as u8);
+    ico.push((height % 256) as u8);
+    ico.push(0);
+    ico.push(0);
+    ico.extend_from_slice(&1u16.to_le_bytes());
+    ico.extend_from_slice(&32u16.to_le_bytes());
+    ico.extend_from_slice(&(png.len() as u32).to_le_bytes());
+    ico.extend_from_slice(&22u32.to_le_bytes());
+    ico.extend_from_slice(png);
+    Ok(ico)
+}
+
+// 清单里的 Assets\Logo.png 实际可能是 Logo.scale-200.png、Logo.targetsize-256.png 等
+fn logo_variants(package_dir: &Path, resource: &str) -> Vec<PathBuf> {
+    let resource_path = package_dir.join(resource.replace('\\', "/"));
+    let (Some(dir), Some(stem), Some(extension)) = (
+        resource_path.parent(),
+        resource_path.file_stem().and_then(|stem| stem.to_str()),
+        resource_path.extension().and_then(|extension| extension.to_str()),
+    ) else {
+        return Vec::new();
+    };
+
+    let prefix = format!("{}.", stem).to_lowercase();
+    let suffix = format!(".{}", extension).to_lowercase();
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries.filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    let file_name = path.file_name().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+                    path == &resource_path || (file_name.starts_with(&prefix) && file_name.ends_with(&suffix))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// 在清单引用的图标中选不超过 256 的最大一张
+pub fn find_logo(package_dir: &Path, manifest: &AppxManifest) -> Option<PathBuf> {
+    let resources = manifest.applications.iter()
+        .flat_map(|application| [application.square44x44_logo.clone(), application.square150x150_logo.clone()])
+        .chain(std::iter::once(manifest.logo.clone()))
+        .flatten();
+
+    resources
+        .flat_map(|resource| logo_variants(package_dir, &resource))
+        .filter_map(|path| {
+            let data = fs::read(&path).ok()?;
+            let (width, height) = png_size(&data)?;
+            (width <= MAX_ICON_SIZE && height <= MAX_ICON_SIZE).then_some((width * height, path))
+        })
+        .max_by_key(|(area, _)| *area)
+        .map(|(_, path)| path)
+}
+
+pub fn prepare_icon(package_dir: &Path, manifest: &AppxManifest, shortcut_name: &str) -> io::Result<Option<PathBuf>> {
+    let Some(logo) = find_logo(package_dir, manifest) else {
+        return Ok(None);
+    };
+    let icon_path = shortcut_data_dir().join(format!("{}.ico", sanitize_file_name(shortcut_name)));
+    fs::create_dir_all(shortcut_data_dir())?;
+    fs::write(&icon_path, png_to_ico(&fs::read(logo)?)?)?;
+    Ok(Some(icon_path))
+}
+
+fn write_script(path: &Path, shortcut: &Shortcut) -> io::Result<()> {
+    fs::write(path, render_script(shortcut, Shell::current()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
+
+pub fn create_shortcut(shortcut: &Shortcut, kind: ShortcutKind, output_dir: &Path) -> io::Result<PathBuf> {
+    fs::create_dir_all(output_dir)?;
+    let shortcut_path = output_dir.join(shortcut.file_name(kind));
+
+    match kind {
+        ShortcutKind::Script => write_script(&shortcut_path, shortcut)?,
+        ShortcutKind::Url => {
+            // .url 不能带参数，改为指向程序目录下的启动脚本
+            fs::create_dir_all(shortcut_data_dir())?;
+            let script_path = shortcut_data_dir().join(shortcut.file_name(ShortcutKind::Script));
+            write_script(&script_path, shortcut)?;
+            fs::write(&shortcut_path, render_url(&file_url(&script_path), shortcut.icon.as_deref()))?;
+        }
+        #[cfg(windows)]
+        ShortcutKind::Link => link::write_link(shortcut, &shortcut_path)?,
+        #[cfg(not(windows))]
+        ShortcutKind::Link => return Err(io::Error::new(io::ErrorKind::Unsupported, "只有 Windows 支持 .lnk 快捷方式，请使用 url 或 script")),
+    }
+    Ok(shortcut_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shortcut(package_dir: &str) -> Shortcut {
+        Shortcut {
+            name: "Minecraft".to_string(),
+            executable: PathBuf::from(r"C:\Program Files\BBL\BetterBedrockLauncherCore.exe"),
+            arguments: vec!["regpack".to_string(), package_dir.to_string(), "-start".to_string()],
+            working_dir: PathBuf::from(r"C:\BBL"),
+            icon: None,
+        }
+    }
+
+    #[test]
+    fn quotes_windows_arguments() {
+        assert_eq!(quote_argument("regpack"), "regpack");
+        assert_eq!(quote_argument(""), "\"\"");
+        assert_eq!(quote_argument(r"C:\my dir\"), r#""C:\my dir\\""#);
+        assert_eq!(quote_argument(r#"say "hi""#), r#""say \"hi\"""#);
+    }
+
+    #[test]
+    fn escapes_cmd_scripts() {
+        let script = render_script(&shortcut(r"D:\Games\100% & Friends (x)"), Shell::Cmd);
+        assert_eq!(script, "@echo off\r\ncd /d C:\\BBL\r\n\"C:\\Program Files\\BBL\\BetterBedrockLauncherCore.exe\" regpack \"D:\\Games\\100%% & Friends (x)\" -start\r\n");
+        assert_eq!(shortcut("D:\\a&b|c").command_line(Shell::Cmd), "\"C:\\Program Files\\BBL\\BetterBedrockLauncherCore.exe\" regpack D:\\a^&b^|c -start");
+        // \" 会让 cmd 认为引号已经结束，后面的 & 需要转义
+        assert_eq!(shortcut("a\"&b").command_line(Shell::Cmd), "\"C:\\Program Files\\BBL\\BetterBedrockLauncherCore.exe\" regpack \"a\\\"^&b\" -start");
+    }
+
+    #[test]
+    fn quotes_sh_scripts() {
+        let mut shortcut = shortcut("/home/steve/it's $HOME");
+        shortcut.executable = PathBuf::from("/opt/bbl/bbl");
+        shortcut.working_dir = PathBuf::from("/opt/bbl");
+        assert_eq!(render_script(&shortcut, Shell::Sh), "#!/bin/sh\ncd '/opt/bbl'\nexec '/opt/bbl/bbl' 'regpack' '/home/steve/it'\\''s $HOME' '-start'\n");
+    }
+
+    #[test]
+    fn builds_file_urls() {
+        assert_eq!(file_url(Path::new(r"C:\BBL\shortcuts\Minecraft 1.20.cmd")), "file:///C:/BBL/shortcuts/Minecraft%201.20.cmd");
+    }
+}
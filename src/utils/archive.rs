@@ -0,0 +1,94 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+use zip::result::{ZipError, ZipResult};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+// 压缩包出错时统一转换为 io::Error，本身就是 IO 错误的保留原来的错误
+pub fn to_io_error(err: ZipError) -> io::Error {
+    match err {
+        ZipError::Io(err) => err,
+        err => io::Error::new(io::ErrorKind::InvalidData, err),
+    }
+}
+
+pub fn zip_dir(source_dir: &Path, archive_path: &Path) -> ZipResult<()> {
+    zip_dirs(&[(source_dir, "")], archive_path)
+}
+
+// 多个目录打进同一个压缩包，每个目录放在对应的前缀下，前缀为空表示根目录
+pub fn zip_dirs(sources: &[(&Path, &str)], archive_path: &Path) -> ZipResult<()> {
+    if let Some(parent) = archive_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut writer = ZipWriter::new(File::create(archive_path)?);
+    let options = SimpleFileOptions::default();
+
+    for (source_dir, prefix) in sources {
+        let prefix = prefix.trim_matches('/');
+        if !prefix.is_empty() {
+            writer.add_directory(prefix, options)?;
+        }
+        add_dir_entries(&mut writer, source_dir, source_dir, prefix, options)?;
+    }
+
+    writer.finish()?;
+    Ok(())
+}
+
+fn add_dir_entries(writer: &mut ZipWriter<File>, root: &Path, dir: &Path, prefix: &str, options: SimpleFileOptions) -> ZipResult<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = entry_name(root, &path, prefix);
+
+        if path.is_dir() {
+            writer.add_directory(name, options)?;
+            add_dir_entries(writer, root, &path, prefix, options)?;
+        } else {
+            writer.start_file(name, options)?;
+            io::copy(&mut File::open(&path)?, writer)?;
+        }
+    }
+    Ok(())
+}
+
+// zip 内统一使用 '/' 作为路径分隔符
+fn entry_name(root: &Path, path: &Path, prefix: &str) -> String {
+    let name = path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    if prefix.is_empty() {
+        name
+    } else {
+        format!("{}/{}", prefix, name)
+    }
+}
+
+// 解压到 destination，跳过会写到目录外的条目
+pub fn unzip(archive_path: &Path, destination: &Path) -> ZipResult<()> {
+    let mut archive = ZipArchive::new(File::open(archive_path)?)?;
+    fs::create_dir_all(destination)?;
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let Some(name) = entry.enclosed_name() else {
+            continue;
+        };
+        let output_path = destination.join(name);
+
+        if entry.is_dir() {
+            fs::create_dir_all(&output_path)?;
+            continue;
+        }
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut entry, &mut File::create(&output_path)?)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,14 @@
+use std::io::{self, Write};
+
+pub fn confirm(prompt: &str) -> bool {
+    print!("{} [y/N]: ", prompt);
+    if io::stdout().flush().is_err() {
+        return false;
+    }
+
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
@@ -0,0 +1,77 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 去掉 Windows 文件名中不允许的字符
+pub fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if matches!(c, '\\' | '/' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control() { '_' } else { c })
+        .collect::<String>()
+        .trim()
+        .trim_end_matches('.')
+        .to_string()
+}
+
+pub fn copy_dir(source: &Path, destination: &Path) -> io::Result<()> {
+    fs::create_dir_all(destination)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let target = destination.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn dir_size(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries.filter_map(|entry| entry.ok())
+                .map(|entry| match entry.file_type() {
+                    Ok(file_type) if file_type.is_dir() => dir_size(&entry.path()),
+                    _ => entry.metadata().map(|metadata| metadata.len()).unwrap_or(0),
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+// 系统临时目录下新建一个不会重名的目录，用完由调用方删除
+pub fn create_temp_dir(prefix: &str) -> io::Result<PathBuf> {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_nanos()).unwrap_or(0);
+    let dir = env::temp_dir().join(format!("{}-{}-{}", prefix, process::id(), nanos));
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+// 目录已存在时依次尝试 name-1、name-2 ...
+pub fn unique_dir(parent: &Path, name: &str) -> PathBuf {
+    let mut candidate = parent.join(name);
+    let mut index = 1;
+    while candidate.exists() {
+        candidate = parent.join(format!("{}-{}", name, index));
+        index += 1;
+    }
+    candidate
+}
@@ -23,34 +23,35 @@ pub fn log(level: LogLevel, message: &str) {
     println!("[{}] {} {}", timestamp, log_level_str, message);
 }
 
+// 宏展开为表达式 (末尾不带分号)，才能用在 match 分支中，如 Err(e) => error!("{}", e)；带分号时新版编译器会报错
 mod logger {
     #![macro_use]
 
     #[macro_export]
     macro_rules! info {
     ($($arg:tt)*) => {
-        $crate::utils::logger::log($crate::utils::logger::LogLevel::Info, &format!($($arg)*));
+        $crate::utils::logger::log($crate::utils::logger::LogLevel::Info, &format!($($arg)*))
     };
 }
 
     #[macro_export]
     macro_rules! warning {
     ($($arg:tt)*) => {
-        $crate::utils::logger::log($crate::utils::logger::LogLevel::Warning, &format!($($arg)*));
+        $crate::utils::logger::log($crate::utils::logger::LogLevel::Warning, &format!($($arg)*))
     };
 }
 
     #[macro_export]
     macro_rules! error {
     ($($arg:tt)*) => {
-        $crate::utils::logger::log($crate::utils::logger::LogLevel::Error, &format!($($arg)*));
+        $crate::utils::logger::log($crate::utils::logger::LogLevel::Error, &format!($($arg)*))
     };
 }
 
     #[macro_export]
     macro_rules! debug {
     ($($arg:tt)*) => {
-        $crate::utils::logger::log($crate::utils::logger::LogLevel::Debug, &format!($($arg)*));
+        $crate::utils::logger::log($crate::utils::logger::LogLevel::Debug, &format!($($arg)*))
     };
 }
 
@@ -1 +1,5 @@
-pub mod logger;
\ No newline at end of file
+pub mod logger;
+pub mod archive;
+pub mod console;
+pub mod paths;
+pub mod files;pub mod hash;
@@ -0,0 +1,10 @@
+use std::env;
+use std::path::PathBuf;
+
+// 程序所在目录，配置和缓存都放在这里，方便整体拷贝
+pub fn app_dir() -> PathBuf {
+    env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|parent| parent.to_path_buf()))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
@@ -0,0 +1,227 @@
+use std::env;
+use std::path::Path;
+
+use crate::manifest::AppxManifest;
+use crate::version::PackageVersion;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ValidationContext {
+    pub host_architecture: String,
+    pub os_version: Option<PackageVersion>,
+}
+
+impl ValidationContext {
+    pub fn current() -> Self {
+        ValidationContext {
+            host_architecture: host_architecture(),
+            os_version: os_version(),
+        }
+    }
+}
+
+// 32 位进程在 64 位系统上时 PROCESSOR_ARCHITEW6432 才是系统真实架构
+pub fn host_architecture() -> String {
+    let architecture = env::var("PROCESSOR_ARCHITEW6432")
+        .or_else(|_| env::var("PROCESSOR_ARCHITECTURE"))
+        .unwrap_or_else(|_| env::consts::ARCH.to_string());
+
+    match architecture.to_lowercase().as_str() {
+        "amd64" | "x86_64" => "x64".to_string(),
+        "aarch64" => "arm64".to_string(),
+        "i686" | "i386" => "x86".to_string(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(windows)]
+pub fn os_version() -> Option<PackageVersion> {
+    use windows::System::Profile::AnalyticsInfo;
+
+    let device_family_version = AnalyticsInfo::VersionInfo().ok()?.DeviceFamilyVersion().ok()?;
+    let version = device_family_version.to_string().parse::<u64>().ok()?;
+    Some(PackageVersion([
+        (version >> 48) as u32,
+        (version >> 32 & 0xFFFF) as u32,
+        (version >> 16 & 0xFFFF) as u32,
+        (version & 0xFFFF) as u32,
+    ]))
+}
+
+#[cfg(not(windows))]
+pub fn os_version() -> Option<PackageVersion> {
+    None
+}
+
+pub fn architecture_supported(host: &str, package: &str) -> bool {
+    match package.to_lowercase().as_str() {
+        "neutral" => true,
+        "x86" => matches!(host, "x86" | "x64" | "arm64"),
+        "x64" => matches!(host, "x64" | "arm64"),
+        "arm" => matches!(host, "arm" | "arm64"),
+        "arm64" => host == "arm64",
+        _ => false,
+    }
+}
+
+pub fn validate_package(package_dir: &Path, manifest: &AppxManifest, context: &ValidationContext) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let mut issue = |severity, message: String| issues.push(ValidationIssue { severity, message });
+
+    if let Some(architecture) = &manifest.identity.processor_architecture {
+        if !architecture_supported(&context.host_architecture, architecture) {
+            issue(Severity::Error, format!("包架构 ProcessorArchitecture=\"{}\" 与系统架构 {} 不兼容", architecture, context.host_architecture));
+        }
+    }
+
+    let desktop_families = manifest.target_device_families.iter()
+        .filter(|family| matches!(family.name.as_str(), "Windows.Desktop" | "Windows.Universal"))
+        .collect::<Vec<_>>();
+    if desktop_families.is_empty() && !manifest.target_device_families.is_empty() {
+        let names = manifest.target_device_families.iter().map(|family| family.name.as_str()).collect::<Vec<_>>();
+        issue(Severity::Error, format!("TargetDeviceFamily 不包含 Windows.Desktop: {}", names.join(", ")));
+    }
+    if let Some(os_version) = context.os_version {
+        let required = desktop_families.iter()
+            .filter_map(|family| family.min_version.parse::<PackageVersion>().ok().map(|version| (version, family)))
+            .min_by_key(|(version, _)| *version);
+        if let Some((min_version, family)) = required {
+            if min_version > os_version {
+                issue(Severity::Error, format!("TargetDeviceFamily {} MinVersion=\"{}\" 高于当前系统版本 {}", family.name, family.min_version, os_version));
+            }
+        }
+    }
+
+    if package_dir.join("AppxSignature.p7x").exists() {
+        issue(Severity::Error, "存在签名文件 AppxSignature.p7x，开发模式注册前需要删除 (unpack -dsign)".to_string());
+    }
+
+    if manifest.applications.is_empty() {
+        issue(Severity::Warning, "清单中没有 Application，注册后无法启动".to_string());
+    }
+    for application in &manifest.applications {
+        // $targetnametoken$ 之类的占位符由打包工具替换，这里无法检查
+        if let Some(executable) = application.executable.as_ref().filter(|executable| !executable.contains('$')) {
+            if !package_dir.join(executable).exists() {
+                issue(Severity::Error, format!("Application Id=\"{}\" 的 Executable=\"{}\" 不存在", application.id, executable));
+            }
+        }
+    }
+
+    issues
+}
+
+pub fn has_errors(issues: &[ValidationIssue]) -> bool {
+    issues.iter().any(|issue| issue.severity == Severity::Error)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::utils::files::create_temp_dir;
+
+    fn manifest(architecture: &str, family: &str, min_version: &str) -> AppxManifest {
+        AppxManifest::parse(&format!(
+            r#"<Package xmlns="http://schemas.microsoft.com/appx/manifest/foundation/windows10">
+  <Identity Name="Microsoft.MinecraftUWP" Publisher="CN=Microsoft Corporation" Version="1.20.8001.0" ProcessorArchitecture="{}" />
+  <Dependencies><TargetDeviceFamily Name="{}" MinVersion="{}" MaxVersionTested="10.0.19041.0" /></Dependencies>
+  <Applications><Application Id="App" Executable="Minecraft.Windows.exe" /></Applications>
+</Package>"#,
+            architecture, family, min_version
+        )).unwrap()
+    }
+
+    fn context(host_architecture: &str, os_version: Option<&str>) -> ValidationContext {
+        ValidationContext {
+            host_architecture: host_architecture.to_string(),
+            os_version: os_version.map(|version| version.parse().unwrap()),
+        }
+    }
+
+    // 返回临时包目录，executable 为 true 时放一个空的主程序
+    fn package_dir(executable: bool) -> std::path::PathBuf {
+        let dir = create_temp_dir("bbl-validation").unwrap();
+        if executable {
+            fs::write(dir.join("Minecraft.Windows.exe"), b"").unwrap();
+        }
+        dir
+    }
+
+    fn errors(issues: &[ValidationIssue]) -> Vec<&str> {
+        issues.iter().filter(|issue| issue.severity == Severity::Error).map(|issue| issue.message.as_str()).collect()
+    }
+
+    #[test]
+    fn valid_package_has_no_issues() {
+        let dir = package_dir(true);
+        let issues = validate_package(&dir, &manifest("x64", "Windows.Desktop", "10.0.17763.0"), &context("x64", Some("10.0.19045.0")));
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn reports_architecture_mismatch() {
+        let dir = package_dir(true);
+        let issues = validate_package(&dir, &manifest("arm64", "Windows.Desktop", "10.0.17763.0"), &context("x64", None));
+        let x86_on_arm = validate_package(&dir, &manifest("x86", "Windows.Desktop", "10.0.17763.0"), &context("arm64", None));
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(errors(&issues).len(), 1);
+        assert!(errors(&issues)[0].contains("arm64"));
+        assert!(x86_on_arm.is_empty());
+    }
+
+    #[test]
+    fn reports_unsatisfied_device_family() {
+        let dir = package_dir(true);
+        let too_new = validate_package(&dir, &manifest("x64", "Windows.Desktop", "10.0.22621.0"), &context("x64", Some("10.0.19045.0")));
+        let unknown_os = validate_package(&dir, &manifest("x64", "Windows.Desktop", "10.0.22621.0"), &context("x64", None));
+        let not_desktop = validate_package(&dir, &manifest("x64", "Windows.Xbox", "10.0.17763.0"), &context("x64", None));
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(errors(&too_new).len(), 1);
+        assert!(errors(&too_new)[0].contains("MinVersion=\"10.0.22621.0\""));
+        assert!(unknown_os.is_empty());
+        assert_eq!(errors(&not_desktop).len(), 1);
+        assert!(errors(&not_desktop)[0].contains("Windows.Xbox"));
+    }
+
+    #[test]
+    fn reports_signature_and_missing_executable() {
+        let dir = package_dir(false);
+        fs::write(dir.join("AppxSignature.p7x"), b"").unwrap();
+        let issues = validate_package(&dir, &manifest("x64", "Windows.Desktop", "10.0.17763.0"), &context("x64", None));
+        fs::remove_dir_all(&dir).unwrap();
+        let errors = errors(&issues);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("AppxSignature.p7x"));
+        assert!(errors[1].contains("Minecraft.Windows.exe"));
+        assert!(has_errors(&issues));
+    }
+
+    #[test]
+    fn skips_placeholder_executables() {
+        let dir = package_dir(false);
+        let mut manifest = manifest("neutral", "Windows.Universal", "10.0.17763.0");
+        manifest.applications[0].executable = Some("$targetnametoken$.exe".to_string());
+        let issues = validate_package(&dir, &manifest, &context("x64", None));
+        manifest.applications.clear();
+        let no_application = validate_package(&dir, &manifest, &context("x64", None));
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(issues.is_empty());
+        assert_eq!(no_application.len(), 1);
+        assert_eq!(no_application[0].severity, Severity::Warning);
+        assert!(!has_errors(&no_application));
+    }
+}
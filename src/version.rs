@@ -0,0 +1,63 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PackageVersion(pub [u32; 4]);
+
+impl PackageVersion {
+    pub fn from_parts(parts: &[u32]) -> Self {
+        let mut version = [0; 4];
+        for (slot, part) in version.iter_mut().zip(parts) {
+            *slot = *part;
+        }
+        PackageVersion(version)
+    }
+}
+
+impl FromStr for PackageVersion {
+    type Err = String;
+
+    // 缺少的部分按 0 处理，"1.20" 等同于 "1.20.0.0"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s.trim()
+            .split('.')
+            .map(|part| part.parse::<u32>().map_err(|_| format!("无效的版本号: {}", s)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if parts.is_empty() || parts.len() > 4 {
+            return Err(format!("无效的版本号: {}", s));
+        }
+        Ok(PackageVersion::from_parts(&parts))
+    }
+}
+
+impl fmt::Display for PackageVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [major, minor, build, revision] = self.0;
+        write!(f, "{}.{}.{}.{}", major, minor, build, revision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_versions() {
+        assert_eq!("1.20.8001.0".parse::<PackageVersion>(), Ok(PackageVersion([1, 20, 8001, 0])));
+        assert_eq!(" 1.20 ".parse::<PackageVersion>(), Ok(PackageVersion([1, 20, 0, 0])));
+        assert_eq!(PackageVersion([1, 20, 8001, 0]).to_string(), "1.20.8001.0");
+    }
+
+    #[test]
+    fn rejects_invalid_versions() {
+        for version in ["", "1..2", "1.2.3.4.5", "1.x", "-1.0", "4294967296.0"] {
+            assert!(version.parse::<PackageVersion>().is_err(), "{}", version);
+        }
+    }
+
+    #[test]
+    fn compares_parts_numerically() {
+        assert!("1.20.10.0".parse::<PackageVersion>().unwrap() > "1.20.9.0".parse().unwrap());
+        assert!("1.21".parse::<PackageVersion>().unwrap() > "1.20.8001.0".parse().unwrap());
+    }
+}